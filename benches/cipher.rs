@@ -0,0 +1,74 @@
+//! Compares building the cipher for every item against reusing one built by [`DerivedKey`].
+//! Run with `cargo bench`.
+
+use aes_gcm_siv::{
+    aead::{generic_array::GenericArray, Aead},
+    Aes256GcmSiv, KeyInit, Nonce,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use tinycrypt::DerivedKey;
+
+const ITEMS: usize = 10_000;
+const ITEM_LEN: usize = 64;
+
+fn items() -> Vec<Vec<u8>> {
+    (0..ITEMS).map(|i| vec![i as u8; ITEM_LEN]).collect()
+}
+
+fn small_items(c: &mut Criterion) {
+    let key = [7u8; 32];
+    let nonce = [3u8; 12];
+    let derived = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+
+    let mut group = c.benchmark_group("10k small items");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    group.sample_size(20);
+
+    group.bench_function("cipher per call", |b| {
+        b.iter_batched(
+            items,
+            |items| {
+                for item in items {
+                    let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key));
+                    cipher
+                        .encrypt(Nonce::from_slice(&nonce), item.as_slice())
+                        .unwrap();
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("cipher reused", |b| {
+        b.iter_batched(
+            items,
+            |items| {
+                let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key));
+
+                for item in items {
+                    cipher
+                        .encrypt(Nonce::from_slice(&nonce), item.as_slice())
+                        .unwrap();
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("DerivedKey::encrypt", |b| {
+        b.iter_batched(
+            items,
+            |items| {
+                for item in items {
+                    derived.encrypt(&item).unwrap();
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, small_items);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+//! Compares allocations & time per small blob for [`DerivedKey`] against a reused [`CryptoContext`].
+//! Run with `cargo bench --bench context`, the allocation counts are printed before the timings.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tinycrypt::{CryptoContext, DerivedKey};
+
+/// Counts every allocation made through the global allocator.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ITEM: [u8; 64] = [7u8; 64];
+const OPS: usize = 1000;
+
+fn allocations_per_op(mut op: impl FnMut()) -> f64 {
+    // Warm up, letting buffers reach their final size.
+    op();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..OPS {
+        op();
+    }
+
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / OPS as f64
+}
+
+fn small_blobs(c: &mut Criterion) {
+    let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    let mut context = CryptoContext::new(b"password").unwrap();
+    let mut encrypted = Vec::new();
+    let mut decrypted = Vec::new();
+
+    let blob = key.encrypt(&ITEM).unwrap();
+    context.encrypt_into(&ITEM, &mut encrypted).unwrap();
+    let own_blob = encrypted.clone();
+
+    let key_encrypt = allocations_per_op(|| drop(key.encrypt(&ITEM).unwrap()));
+    let key_decrypt = allocations_per_op(|| drop(key.decrypt(&blob).unwrap()));
+    let encrypt_into = allocations_per_op(|| context.encrypt_into(&ITEM, &mut encrypted).unwrap());
+    let decrypt_into =
+        allocations_per_op(|| context.decrypt_into(&own_blob, &mut decrypted).unwrap());
+
+    println!(
+        "allocations per op: DerivedKey::encrypt {key_encrypt}, DerivedKey::decrypt {key_decrypt}, \
+         CryptoContext::encrypt_into {encrypt_into}, CryptoContext::decrypt_into {decrypt_into}"
+    );
+
+    let mut group = c.benchmark_group("64 byte blob");
+
+    group.bench_function("DerivedKey round trip", |b| {
+        b.iter(|| key.decrypt(&key.encrypt(&ITEM).unwrap()).unwrap())
+    });
+
+    group.bench_function("CryptoContext round trip", |b| {
+        b.iter(|| {
+            context.encrypt_into(&ITEM, &mut encrypted).unwrap();
+            context.decrypt_into(&encrypted, &mut decrypted).unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, small_blobs);
+criterion_main!(benches);
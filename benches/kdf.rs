@@ -0,0 +1,26 @@
+//! Compares key derivation with 1, 4 and 8 argon2 lanes at the same memory cost.
+//! Run with `cargo bench --bench kdf`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tinycrypt::{encrypt_with_params, Argon2Params};
+
+fn lanes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("argon2 19 MiB, 2 passes");
+    group.sample_size(10);
+
+    for lanes in [1, 4, 8] {
+        let params = Argon2Params {
+            lanes,
+            ..Argon2Params::default()
+        };
+
+        group.bench_with_input(BenchmarkId::new("lanes", lanes), &params, |b, params| {
+            b.iter(|| encrypt_with_params(b"", b"password", params).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lanes);
+criterion_main!(benches);
@@ -0,0 +1,353 @@
+//! Encrypted directory archives.
+//!
+//! The directory tree is packed into a simple entry stream and encrypted with the [`stream`](crate::stream) format:
+//!
+//! ```text
+//! kind        1 byte    0 end of archive, 1 directory, 2 file
+//! path len    2 bytes   u16 LE
+//! path        UTF-8, relative, components separated by '/'
+//! mode        4 bytes   u32 LE, permission bits
+//! size        8 bytes   u64 LE, files only, followed by the file's contents
+//! ```
+//!
+//! Only regular files and directories are archived, symbolic links are skipped rather than followed.
+//!
+//! Paths are part of the same authenticated stream as the contents that follow them, so moving an entry, swapping
+//! two files' contents or renaming anything inside the archive fails authentication before the chunk is extracted.
+//! Paths are stored as the exact UTF-8 bytes the platform reported and extracted byte for byte, without Unicode
+//! normalization, so a name always round trips to itself.
+
+use crate::{
+    fs::into_cryptography_error,
+    stream::{DecryptingReader, EncryptingWriter},
+    CryptographyError,
+};
+use std::{
+    cmp::Reverse,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const KIND_END: u8 = 0;
+const KIND_DIR: u8 = 1;
+const KIND_FILE: u8 = 2;
+
+const DEFAULT_DIR_MODE: u32 = 0o755;
+const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/// Function for encrypting the directory tree under `root` into a single archive file at `output`.
+/// Stores every file & directory with its path relative to `root` and its permission bits.
+/// Errors from the filesystem return an Io error. Names [`decrypt_dir`] would refuse to extract, such as ones
+/// containing `\`, `:` or NUL, return an EncodingFailure.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_dir, decrypt_dir};
+///
+/// let dir = std::env::temp_dir().join("tinycrypt-doc-encrypt-dir");
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// std::fs::create_dir_all(dir.join("notes")).unwrap();
+/// std::fs::write(dir.join("notes/hello.txt"), b"Hello, world!").unwrap();
+///
+/// encrypt_dir(&dir.join("notes"), &dir.join("notes.tcry"), b"password").expect("Failed to encrypt!");
+/// decrypt_dir(&dir.join("notes.tcry"), &dir.join("restored"), b"password").expect("Failed to decrypt!");
+///
+/// assert_eq!(std::fs::read(dir.join("restored/hello.txt")).unwrap(), b"Hello, world!");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn encrypt_dir(root: &Path, output: &Path, password: &[u8]) -> Result<(), CryptographyError> {
+    let file = File::create(output).map_err(|error| CryptographyError::Io(error.into()))?;
+    let output = output
+        .canonicalize()
+        .map_err(|error| CryptographyError::Io(error.into()))?;
+
+    let mut writer = EncryptingWriter::new(BufWriter::new(file), password)?;
+
+    let packed = pack_dir(root, Path::new(""), &output, &mut writer).and_then(|_| {
+        writer
+            .write_all(&[KIND_END])
+            .map_err(into_cryptography_error)
+    });
+    let finished = writer.finish();
+
+    // Don't leave a partial archive behind.
+    if let Err(error) = packed.and(finished.map(|_| ())) {
+        let _ = fs::remove_file(&output);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Function for decrypting an archive made by [`encrypt_dir`] into the directory `output`, creating it if needed.
+///
+/// Entries with absolute paths or `..` components are rejected with a DecodingFailure, so nothing is ever written
+/// outside of `output`. Existing files are never overwritten (an Io error of kind AlreadyExists) and existing symbolic
+/// links are never followed.
+/// Directory permissions are restored once everything has been extracted, so read-only directories keep their contents.
+/// If decryption fails partway, the entries extracted before the failure are left in place.
+pub fn decrypt_dir(input: &Path, output: &Path, password: &[u8]) -> Result<(), CryptographyError> {
+    let file = File::open(input).map_err(|error| CryptographyError::Io(error.into()))?;
+    let mut reader = DecryptingReader::new(BufReader::new(file), password);
+
+    fs::create_dir_all(output).map_err(|error| CryptographyError::Io(error.into()))?;
+
+    let mut dir_modes = Vec::new();
+
+    loop {
+        let kind = read_array::<1>(&mut reader)?[0];
+
+        if kind == KIND_END {
+            break;
+        }
+
+        let path_len = u16::from_le_bytes(read_array(&mut reader)?) as usize;
+        let mut path = vec![0u8; path_len];
+        read_exact(&mut reader, &mut path)?;
+
+        let path = String::from_utf8(path).map_err(|_| CryptographyError::DecodingFailure)?;
+        let target = output.join(safe_path(&path)?);
+        check_parents(output, &target)?;
+
+        let mode = u32::from_le_bytes(read_array(&mut reader)?);
+
+        match kind {
+            KIND_DIR => {
+                match fs::symlink_metadata(&target) {
+                    Ok(metadata) if metadata.is_dir() => {}
+                    Ok(_) => return Err(CryptographyError::EncodingFailure),
+                    Err(_) => fs::create_dir(&target)
+                        .map_err(|error| CryptographyError::Io(error.into()))?,
+                }
+
+                dir_modes.push((target, mode));
+            }
+            KIND_FILE => {
+                let size = u64::from_le_bytes(read_array(&mut reader)?);
+
+                // create_new refuses existing files and symbolic links alike.
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&target)
+                    .map_err(|error| CryptographyError::Io(error.into()))?;
+                let mut file = BufWriter::new(file);
+
+                let copied = io::copy(&mut (&mut reader).take(size), &mut file)
+                    .map_err(into_cryptography_error)?;
+
+                if copied != size {
+                    return Err(CryptographyError::DecodingFailure);
+                }
+
+                file.flush()
+                    .map_err(|error| CryptographyError::Io(error.into()))?;
+                set_mode(&target, mode)?;
+            }
+            _ => return Err(CryptographyError::DecodingFailure),
+        }
+    }
+
+    // Nothing may follow the end marker, and reading to the end authenticates the final chunk.
+    let mut rest = [0u8; 1];
+
+    if reader.read(&mut rest).map_err(into_cryptography_error)? != 0 {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    // Deepest first, so a read-only directory is only applied once nothing more is written into it.
+    dir_modes.sort_by_key(|(target, _): &(PathBuf, u32)| Reverse(target.components().count()));
+
+    for (target, mode) in dir_modes {
+        set_mode(&target, mode)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the entries below `dir` in name order, skipping the archive being written.
+fn pack_dir<W: Write>(
+    dir: &Path,
+    relative: &Path,
+    output: &Path,
+    writer: &mut EncryptingWriter<W>,
+) -> Result<(), CryptographyError> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|error| CryptographyError::Io(error.into()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()
+        .map_err(|error| CryptographyError::Io(error.into()))?;
+    entries.sort();
+
+    for path in entries {
+        let metadata =
+            fs::symlink_metadata(&path).map_err(|error| CryptographyError::Io(error.into()))?;
+        let name = path.file_name().ok_or(CryptographyError::DecodingFailure)?;
+        let relative = relative.join(name);
+
+        if metadata.is_dir() {
+            write_entry(
+                writer,
+                KIND_DIR,
+                &relative,
+                mode_of(&metadata, DEFAULT_DIR_MODE),
+            )?;
+            pack_dir(&path, &relative, output, writer)?;
+        } else if metadata.is_file() {
+            if path.canonicalize().ok().as_deref() == Some(output) {
+                continue;
+            }
+
+            write_entry(
+                writer,
+                KIND_FILE,
+                &relative,
+                mode_of(&metadata, DEFAULT_FILE_MODE),
+            )?;
+            writer
+                .write_all(&metadata.len().to_le_bytes())
+                .map_err(into_cryptography_error)?;
+
+            let file = File::open(&path).map_err(|error| CryptographyError::Io(error.into()))?;
+            let copied = io::copy(&mut BufReader::new(file).take(metadata.len()), writer)
+                .map_err(into_cryptography_error)?;
+
+            // A file that shrank while being read would corrupt every entry after it.
+            if copied != metadata.len() {
+                return Err(CryptographyError::DecodingFailure);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_entry<W: Write>(
+    writer: &mut EncryptingWriter<W>,
+    kind: u8,
+    relative: &Path,
+    mode: u32,
+) -> Result<(), CryptographyError> {
+    let mut path = Vec::new();
+
+    for component in relative.components() {
+        let Component::Normal(name) = component else {
+            return Err(CryptographyError::EncodingFailure);
+        };
+
+        if !path.is_empty() {
+            path.push(b'/');
+        }
+
+        path.extend_from_slice(
+            name.to_str()
+                .ok_or(CryptographyError::EncodingFailure)?
+                .as_bytes(),
+        );
+    }
+
+    // Refuse what extraction would, instead of writing an archive that can never be extracted.
+    let path = String::from_utf8(path).map_err(|_| CryptographyError::EncodingFailure)?;
+    safe_path(&path).map_err(|_| CryptographyError::EncodingFailure)?;
+
+    let path_len = u16::try_from(path.len()).map_err(|_| CryptographyError::EncodingFailure)?;
+
+    let mut entry = vec![kind];
+    entry.extend_from_slice(&path_len.to_le_bytes());
+    entry.extend_from_slice(path.as_bytes());
+    entry.extend_from_slice(&mode.to_le_bytes());
+
+    writer.write_all(&entry).map_err(into_cryptography_error)
+}
+
+/// Turns an archived path into a relative path, rejecting anything that could escape the output directory.
+fn safe_path(path: &str) -> Result<PathBuf, CryptographyError> {
+    let mut safe = PathBuf::new();
+
+    for name in path.split('/') {
+        let unsafe_name =
+            name.is_empty() || name == "." || name == ".." || name.contains(['\\', ':', '\0']);
+
+        if unsafe_name {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        safe.push(name);
+    }
+
+    // Catches anything the platform still treats as special, such as reserved prefixes.
+    if !safe
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    Ok(safe)
+}
+
+/// Makes sure no directory between `output` and `target` is a symbolic link.
+fn check_parents(output: &Path, target: &Path) -> Result<(), CryptographyError> {
+    let mut parent = target.parent();
+
+    while let Some(dir) = parent {
+        if dir == output {
+            return Ok(());
+        }
+
+        let metadata = fs::symlink_metadata(dir).map_err(|error| match error.kind() {
+            // An entry in a directory the archive never created.
+            io::ErrorKind::NotFound => CryptographyError::DecodingFailure,
+            _ => CryptographyError::Io(error.into()),
+        })?;
+
+        if !metadata.is_dir() {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        parent = dir.parent();
+    }
+
+    Err(CryptographyError::DecodingFailure)
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &fs::Metadata, _default: u32) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o777
+}
+
+#[cfg(not(unix))]
+fn mode_of(_metadata: &fs::Metadata, default: u32) -> u32 {
+    default
+}
+
+/// Restores permission bits, never setuid, setgid or sticky bits.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<(), CryptographyError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<(), CryptographyError> {
+    Ok(())
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), CryptographyError> {
+    reader.read_exact(buf).map_err(|error| match error.kind() {
+        // The stream was authenticated to its end, the archive stops in the middle of an entry.
+        io::ErrorKind::UnexpectedEof => CryptographyError::DecodingFailure,
+        _ => into_cryptography_error(error),
+    })
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], CryptographyError> {
+    let mut array = [0u8; N];
+    read_exact(reader, &mut array)?;
+
+    Ok(array)
+}
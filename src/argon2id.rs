@@ -0,0 +1,388 @@
+//! Argon2id computed one slice at a time, so [`KeyDerivation`](crate::KeyDerivation) can stop between slices.
+//!
+//! Follows RFC 9106 (version 0x13) and derives the same keys as the argon2 backend, `tests/derivation.rs` compares the two.
+
+use crate::{Argon2Params, CryptographyError};
+use alloc::{vec, vec::Vec};
+use blake2::{
+    digest::{Update, VariableOutput},
+    Blake2bVar,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Words in one 1 KiB memory block.
+const BLOCK_WORDS: usize = 128;
+
+/// Argon2 slices every lane into this many segments.
+const SYNC_POINTS: u32 = 4;
+
+const VERSION: u32 = 0x13;
+
+/// Argon2 type id of argon2id.
+const ARGON2ID: u32 = 2;
+
+type Block = [u64; BLOCK_WORDS];
+
+/// An argon2id derivation in progress, filling its memory slice by slice. The memory is wiped when dropped.
+pub(crate) struct Argon2id {
+    memory: Zeroizing<Vec<Block>>,
+    lanes: u32,
+    lane_length: u32,
+    segment_length: u32,
+    passes: u32,
+    /// Slices filled so far, counted across passes.
+    slices_done: u32,
+    key_len: usize,
+}
+
+impl Argon2id {
+    /// Starts deriving a `key_len` byte key from `password` and `salt`, with `ad` as associated data.
+    /// Fills the first two blocks of every lane, the memory is allocated here.
+    pub(crate) fn new(
+        password: &[u8],
+        salt: &[u8],
+        ad: &[u8],
+        params: &Argon2Params,
+        key_len: usize,
+    ) -> Result<Self, CryptographyError> {
+        params.validate()?;
+
+        let blocks = params.mem_cost.max(2 * SYNC_POINTS * params.lanes);
+        let segment_length = blocks / (params.lanes * SYNC_POINTS);
+        let lane_length = segment_length * SYNC_POINTS;
+
+        let mut h0 = Zeroizing::new([0u8; 72]);
+        hash(
+            &mut h0[..64],
+            &[
+                &params.lanes.to_le_bytes(),
+                &(key_len as u32).to_le_bytes(),
+                &params.mem_cost.to_le_bytes(),
+                &params.time_cost.to_le_bytes(),
+                &VERSION.to_le_bytes(),
+                &ARGON2ID.to_le_bytes(),
+                &(password.len() as u32).to_le_bytes(),
+                password,
+                &(salt.len() as u32).to_le_bytes(),
+                salt,
+                // No secret.
+                &0u32.to_le_bytes(),
+                &(ad.len() as u32).to_le_bytes(),
+                ad,
+            ],
+        );
+
+        let mut memory = Zeroizing::new(vec![
+            [0u64; BLOCK_WORDS];
+            (lane_length * params.lanes) as usize
+        ]);
+        let mut bytes = Zeroizing::new([0u8; BLOCK_WORDS * 8]);
+
+        for lane in 0..params.lanes {
+            for index in 0..2 {
+                h0[64..68].copy_from_slice(&(index as u32).to_le_bytes());
+                h0[68..].copy_from_slice(&lane.to_le_bytes());
+                hash_long(&mut bytes[..], &h0[..]);
+
+                let block = &mut memory[(lane * lane_length) as usize + index];
+                for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+                    *word = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+                }
+            }
+        }
+
+        Ok(Self {
+            memory,
+            lanes: params.lanes,
+            lane_length,
+            segment_length,
+            passes: params.time_cost,
+            slices_done: 0,
+            key_len,
+        })
+    }
+
+    /// Slices filled so far and in total, `time_cost` passes of 4 slices each.
+    #[cfg(feature = "std")]
+    pub(crate) fn progress(&self) -> (u32, u32) {
+        (self.slices_done, self.passes * SYNC_POINTS)
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.slices_done == self.passes * SYNC_POINTS
+    }
+
+    /// Fills the next slice of every lane, does nothing once all are filled.
+    pub(crate) fn step(&mut self) {
+        if self.is_done() {
+            return;
+        }
+
+        let pass = self.slices_done / SYNC_POINTS;
+        let slice = self.slices_done % SYNC_POINTS;
+
+        for lane in 0..self.lanes {
+            self.fill_segment(pass, lane, slice);
+        }
+
+        self.slices_done += 1;
+    }
+
+    /// Fills the remaining slices and returns the key.
+    pub(crate) fn finish(mut self) -> Zeroizing<Vec<u8>> {
+        while !self.is_done() {
+            self.step();
+        }
+
+        // The last blocks of all lanes, xored together.
+        let mut last = Zeroizing::new([0u64; BLOCK_WORDS]);
+        for lane in 0..self.lanes {
+            let block = &self.memory[(lane * self.lane_length + self.lane_length - 1) as usize];
+            for (word, other) in last.iter_mut().zip(block) {
+                *word ^= other;
+            }
+        }
+
+        let mut bytes = Zeroizing::new([0u8; BLOCK_WORDS * 8]);
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(last.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        let mut key = Zeroizing::new(vec![0u8; self.key_len]);
+        hash_long(&mut key, &bytes[..]);
+
+        key
+    }
+
+    fn fill_segment(&mut self, pass: u32, lane: u32, slice: u32) {
+        // Argon2id addresses the first half of the first pass independently of the password.
+        let data_independent = pass == 0 && slice < SYNC_POINTS / 2;
+
+        let mut input = [0u64; BLOCK_WORDS];
+        let mut addresses = [0u64; BLOCK_WORDS];
+
+        if data_independent {
+            input[..6].copy_from_slice(&[
+                pass.into(),
+                lane.into(),
+                slice.into(),
+                self.memory.len() as u64,
+                self.passes.into(),
+                ARGON2ID.into(),
+            ]);
+        }
+
+        // The first two blocks of every lane were filled by new.
+        let start = if pass == 0 && slice == 0 {
+            if data_independent {
+                next_addresses(&mut addresses, &mut input);
+            }
+
+            2
+        } else {
+            0
+        };
+
+        let lane_start = lane * self.lane_length;
+
+        for index in start..self.segment_length {
+            let offset = slice * self.segment_length + index;
+            let current = lane_start + offset;
+            // The first block of a lane follows its last one.
+            let previous = if offset == 0 {
+                lane_start + self.lane_length - 1
+            } else {
+                current - 1
+            };
+
+            let pseudo_random = if data_independent {
+                if (index as usize).is_multiple_of(BLOCK_WORDS) {
+                    next_addresses(&mut addresses, &mut input);
+                }
+
+                addresses[index as usize % BLOCK_WORDS]
+            } else {
+                self.memory[previous as usize][0]
+            };
+
+            let reference_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_random >> 32) % u64::from(self.lanes)) as u32
+            };
+            let reference = reference_lane * self.lane_length
+                + self.reference_index(
+                    pass,
+                    slice,
+                    index,
+                    pseudo_random as u32,
+                    reference_lane == lane,
+                );
+
+            let mut block = xor(
+                &self.memory[previous as usize],
+                &self.memory[reference as usize],
+            );
+            // Version 0x13 xors later passes into the block's previous content.
+            compress(&mut block, &mut self.memory[current as usize], pass != 0);
+            block.zeroize();
+        }
+
+        addresses.zeroize();
+    }
+
+    /// Index within its lane of the block the block at `index` of a segment references.
+    fn reference_index(
+        &self,
+        pass: u32,
+        slice: u32,
+        index: u32,
+        pseudo_random: u32,
+        same_lane: bool,
+    ) -> u32 {
+        let segment_length = u64::from(self.segment_length);
+        let lane_length = u64::from(self.lane_length);
+        let index = u64::from(index);
+
+        // Blocks already filled, except the one right before the current block in its own lane.
+        let finished = if pass == 0 {
+            u64::from(slice) * segment_length
+        } else {
+            lane_length - segment_length
+        };
+        let area = if same_lane {
+            finished + index - 1
+        } else if index == 0 {
+            finished - 1
+        } else {
+            finished
+        };
+
+        let x = (u64::from(pseudo_random) * u64::from(pseudo_random)) >> 32;
+        let relative = area - 1 - ((area * x) >> 32);
+
+        let start = if pass == 0 || slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            u64::from(slice + 1) * segment_length
+        };
+
+        ((start + relative) % lane_length) as u32
+    }
+}
+
+/// Generates the next block of data-independent addresses.
+fn next_addresses(addresses: &mut Block, input: &mut Block) {
+    input[6] += 1;
+
+    let mut block = *input;
+    compress(&mut block, addresses, false);
+
+    let mut block = *addresses;
+    compress(&mut block, addresses, false);
+}
+
+fn xor(a: &Block, b: &Block) -> Block {
+    let mut out = *a;
+    for (word, other) in out.iter_mut().zip(b) {
+        *word ^= other;
+    }
+
+    out
+}
+
+/// Argon2's compression of `r`, the xor of its two inputs, into `next`.
+/// `r` is permuted in place, wipe it afterwards.
+fn compress(r: &mut Block, next: &mut Block, with_xor: bool) {
+    let mut original = *r;
+    if with_xor {
+        for (word, other) in original.iter_mut().zip(next.iter()) {
+            *word ^= other;
+        }
+    }
+
+    // Rows of 16 words, then columns of pairs of words.
+    for row in 0..8 {
+        round(r, core::array::from_fn(|i| 16 * row + i));
+    }
+    for column in 0..8 {
+        round(
+            r,
+            core::array::from_fn(|i| 2 * column + 16 * (i / 2) + i % 2),
+        );
+    }
+
+    for ((word, &a), &b) in next.iter_mut().zip(original.iter()).zip(r.iter()) {
+        *word = a ^ b;
+    }
+
+    original.zeroize();
+}
+
+/// BLAKE2b's round without a message, over the 16 words of `v` at `at`.
+fn round(v: &mut Block, at: [usize; 16]) {
+    for [a, b, c, d] in [
+        [0, 4, 8, 12],
+        [1, 5, 9, 13],
+        [2, 6, 10, 14],
+        [3, 7, 11, 15],
+        [0, 5, 10, 15],
+        [1, 6, 11, 12],
+        [2, 7, 8, 13],
+        [3, 4, 9, 14],
+    ] {
+        let (a, b, c, d) = (at[a], at[b], at[c], at[d]);
+
+        v[a] = blamka(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = blamka(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = blamka(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = blamka(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(63);
+    }
+}
+
+/// BLAKE2b's addition, with the product of the low halves mixed in.
+fn blamka(x: u64, y: u64) -> u64 {
+    let product = u64::from(x as u32) * u64::from(y as u32);
+
+    x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+}
+
+/// BLAKE2b of `inputs` concatenated, `out` is 1 to 64 bytes long.
+fn hash(out: &mut [u8], inputs: &[&[u8]]) {
+    let mut hasher = Blake2bVar::new(out.len()).expect("BLAKE2b output of 1 to 64 bytes");
+    for input in inputs {
+        hasher.update(input);
+    }
+
+    hasher
+        .finalize_variable(out)
+        .expect("output of the length the hasher was created with");
+}
+
+/// Argon2's variable length hash H' of `input`, filling `out`.
+fn hash_long(out: &mut [u8], input: &[u8]) {
+    let len = (out.len() as u32).to_le_bytes();
+
+    if out.len() <= 64 {
+        return hash(out, &[&len, input]);
+    }
+
+    // The first 32 bytes of a chain of 64 byte hashes, the last one filling whatever is left.
+    let mut v = Zeroizing::new([0u8; 64]);
+    hash(&mut v[..], &[&len, input]);
+    out[..32].copy_from_slice(&v[..32]);
+
+    let mut position = 32;
+    while out.len() - position > 64 {
+        let previous = Zeroizing::new(*v);
+        hash(&mut v[..], &[&previous[..]]);
+        out[position..position + 32].copy_from_slice(&v[..32]);
+        position += 32;
+    }
+
+    hash(&mut out[position..], &[&v[..]]);
+}
@@ -0,0 +1,112 @@
+//! ASCII armor, blobs as PEM-style text for config files, environment variables & tickets.
+//!
+//! ```text
+//! -----BEGIN TINYCRYPT MESSAGE-----
+//! base64 of the blob, 64 characters per line
+//! =CRC-32 of the blob, little endian as unpadded base64
+//! -----END TINYCRYPT MESSAGE-----
+//! ```
+//!
+//! Whitespace around the block and at the ends of lines is ignored, so armor survives CRLF line endings & indentation.
+
+use crate::{decrypt, encrypt, format::crc32, CryptographyError};
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    Engine,
+};
+
+const BEGIN: &str = "-----BEGIN TINYCRYPT MESSAGE-----";
+const END: &str = "-----END TINYCRYPT MESSAGE-----";
+
+/// Base64 characters per line.
+const LINE_LEN: usize = 64;
+
+/// Function for encrypting data to armored text, see [`encrypt`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt_armored, decrypt_armored};
+///
+/// let armored = encrypt_armored(b"Hello, world!", b"password").expect("Failed to encrypt!");
+/// assert!(armored.starts_with("-----BEGIN TINYCRYPT MESSAGE-----"));
+///
+/// let decrypted_data = decrypt_armored(&armored, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_armored(data: &[u8], password: &[u8]) -> Result<String, CryptographyError> {
+    encrypt(data, password).map(|blob| armor(&blob))
+}
+
+/// Function for decrypting armored text, see [`decrypt`], which also accepts armored text.
+/// Returns InvalidArmor if `text` isn't a well-formed armor block.
+pub fn decrypt_armored(text: &str, password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    decrypt(&dearmor(text)?, password)
+}
+
+/// Function for armoring any blob, such as one made with [`encrypt_with`](crate::encrypt_with).
+pub fn armor(blob: &[u8]) -> String {
+    let encoded = STANDARD.encode(blob);
+    let checksum = STANDARD_NO_PAD.encode(crc32(&[blob]).to_le_bytes());
+
+    let mut text = String::with_capacity(encoded.len() + encoded.len() / LINE_LEN + 80);
+    text.push_str(BEGIN);
+    text.push('\n');
+
+    for line in encoded.as_bytes().chunks(LINE_LEN) {
+        text.extend(line.iter().map(|&byte| char::from(byte)));
+        text.push('\n');
+    }
+
+    text.push('=');
+    text.push_str(&checksum);
+    text.push('\n');
+    text.push_str(END);
+    text.push('\n');
+
+    text
+}
+
+/// Function for getting the blob back out of armored text.
+/// Returns InvalidArmor for missing markers, characters outside base64 or a checksum that doesn't match.
+///
+/// ```rust
+/// use tinycrypt::{armor, dearmor, CryptographyError};
+///
+/// let armored = armor(b"any blob");
+/// assert_eq!(dearmor(&armored).unwrap(), b"any blob");
+///
+/// let tampered = armored.replacen("YW55", "YW56", 1);
+/// assert_eq!(dearmor(&tampered), Err(CryptographyError::InvalidArmor));
+/// ```
+pub fn dearmor(text: &str) -> Result<Vec<u8>, CryptographyError> {
+    let inner = text
+        .trim()
+        .strip_prefix(BEGIN)
+        .and_then(|rest| rest.strip_suffix(END))
+        .ok_or(CryptographyError::InvalidArmor)?;
+
+    let mut lines: Vec<&str> = inner
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let checksum = lines
+        .pop()
+        .and_then(|line| line.strip_prefix('='))
+        .and_then(|checksum| STANDARD_NO_PAD.decode(checksum).ok())
+        .ok_or(CryptographyError::InvalidArmor)?;
+
+    let blob = STANDARD
+        .decode(lines.concat())
+        .map_err(|_| CryptographyError::InvalidArmor)?;
+
+    if checksum != crc32(&[&blob]).to_le_bytes() {
+        return Err(CryptographyError::InvalidArmor);
+    }
+
+    Ok(blob)
+}
+
+/// Returns true if `data` looks like armored text, leading whitespace aside.
+pub(crate) fn is_armored(data: &[u8]) -> bool {
+    data.trim_ascii_start().starts_with(BEGIN.as_bytes())
+}
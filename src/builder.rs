@@ -0,0 +1,276 @@
+//! Builders for encrypting with custom options & for encrypting and decrypting with extra associated data.
+
+use crate::{
+    envelope::{self, Binary},
+    new_cipher, open_with_key, random_header, random_suite_header, seal_with_cipher, seal_with_key,
+    sign::add_verifier,
+    Argon2Params, CipherSuite, Compression, CryptographyError, Envelope, Header, DEFAULT_CONTEXT,
+};
+use std::time::SystemTime;
+
+/// Options for [`encrypt_with`], set builder style starting from the defaults [`encrypt`](crate::encrypt) uses.
+/// Everything chosen is stored in the blob's header, so [`decrypt`](crate::decrypt) needs no options at all.
+///
+/// ```rust
+/// use tinycrypt::{Argon2Params, EncryptOptions};
+///
+/// // A server that can afford a stronger key derivation.
+/// let options = EncryptOptions::new()
+///     .params(Argon2Params { mem_cost: 64 * 1024, time_cost: 3, lanes: 1 })
+///     .context(b"my-app-v1")
+///     .timestamped();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptOptions {
+    params: Argon2Params,
+    context: Vec<u8>,
+    timestamped: bool,
+    verifier: bool,
+    suite: CipherSuite,
+    compression: Compression,
+}
+
+impl Default for EncryptOptions {
+    fn default() -> Self {
+        Self {
+            params: Argon2Params::default(),
+            context: DEFAULT_CONTEXT.to_vec(),
+            timestamped: false,
+            verifier: false,
+            suite: CipherSuite::Aes256GcmSiv,
+            compression: Compression::None,
+        }
+    }
+}
+
+impl EncryptOptions {
+    /// Options matching [`encrypt`](crate::encrypt).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the argon2 cost parameters, see [`encrypt_with_params`](crate::encrypt_with_params).
+    pub fn params(mut self, params: Argon2Params) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Sets the KDF context label, see [`encrypt_with_context`](crate::encrypt_with_context).
+    pub fn context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    /// Records the creation time, see [`encrypt_timestamped`](crate::encrypt_timestamped).
+    pub fn timestamped(mut self) -> Self {
+        self.timestamped = true;
+        self
+    }
+
+    /// Adds a password verifier to the header, so [`verify_header`](crate::verify_header) can check a password
+    /// with only the blob's first bytes. Costs 35 bytes.
+    pub fn verifier(mut self) -> Self {
+        self.verifier = true;
+        self
+    }
+
+    /// Sets the cipher suite, AES-256-GCM-SIV by default. Costs 4 bytes for any other suite,
+    /// and XChaCha20-Poly1305's longer nonce 12 more.
+    pub fn suite(mut self, suite: CipherSuite) -> Self {
+        self.suite = suite;
+        self
+    }
+
+    /// Compresses the plaintext before encrypting it, [`decrypt`](crate::decrypt) decompresses it again.
+    /// The blob's length then depends on the plaintext's content, which can leak secrets mixed with attacker chosen data.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// Function for encrypting data with the given [`EncryptOptions`].
+/// Returns a KeyGenerationFailure if argon2 doesn't accept the parameters, or an EncodingFailure for a context label
+/// longer than [`MAX_CONTEXT_LEN`](crate::MAX_CONTEXT_LEN).
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with, decrypt, Argon2Params, EncryptOptions};
+///
+/// // An embedded device that can't spare the default 19 MiB.
+/// let options = EncryptOptions::new().params(Argon2Params { mem_cost: 4 * 1024, time_cost: 4, lanes: 1 });
+///
+/// let encrypted_data = encrypt_with(b"Hello, world!", b"password", &options).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with(
+    data: &[u8],
+    password: &[u8],
+    options: &EncryptOptions,
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = options_header(options)?;
+
+    // The caller chose the parameters, only decryption is limited.
+    let key = header.derive_key_within(password, usize::MAX)?;
+
+    seal_with_options(header, &key, data, options)
+}
+
+/// A fresh header with everything `options` chose, except the verifier which needs the key.
+pub(crate) fn options_header(options: &EncryptOptions) -> Result<Header, CryptographyError> {
+    options.params.validate()?;
+
+    let mut header = random_suite_header(&options.context, options.suite)?;
+    header.params = options.params;
+    header.set_compression(options.compression);
+
+    if options.timestamped {
+        let created = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|_| CryptographyError::EncodingFailure)?;
+
+        header.created = Some(created.as_secs());
+    }
+
+    Ok(header)
+}
+
+/// Encrypts `data` under the key derived for a header from [`options_header`].
+pub(crate) fn seal_with_options(
+    mut header: Header,
+    key: &[u8],
+    data: &[u8],
+    options: &EncryptOptions,
+) -> Result<Vec<u8>, CryptographyError> {
+    if options.verifier {
+        add_verifier(&mut header, key)?;
+    }
+
+    let ciphertext = seal_with_key(key, &header, data, &[])?;
+
+    Binary.serialize(&header, &ciphertext)
+}
+
+/// Function for starting an encryption with associated data added piece by piece.
+///
+/// Each [`aad`](EncryptBuilder::aad) piece is authenticated but not stored in the blob,
+/// so decrypting needs the identical pieces in the same order through [`decrypt_builder`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt_builder, decrypt_builder};
+///
+/// let sender_id = b"alice";
+/// let seq: u64 = 7;
+///
+/// let encrypted_data = encrypt_builder(b"Hello, world!")
+///     .aad(b"v1")
+///     .aad(sender_id)
+///     .aad(&seq.to_le_bytes())
+///     .run(b"password")
+///     .expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_builder(&encrypted_data)
+///     .aad(b"v1")
+///     .aad(sender_id)
+///     .aad(&seq.to_le_bytes())
+///     .run(b"password")
+///     .expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_builder(data: &[u8]) -> EncryptBuilder<'_> {
+    EncryptBuilder {
+        data,
+        aad: Vec::new(),
+    }
+}
+
+/// Function for starting a decryption of a blob made by [`encrypt_builder`].
+pub fn decrypt_builder(data: &[u8]) -> DecryptBuilder<'_> {
+    DecryptBuilder {
+        data,
+        aad: Vec::new(),
+    }
+}
+
+/// Function for encrypting data bound to `aad`, such as a record id or file name, which isn't stored in the blob.
+/// Decrypting needs the identical `aad` through [`decrypt_with_aad`], so a blob copied to another record fails to decrypt.
+/// Equivalent to an [`encrypt_builder`] with a single piece.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_aad, decrypt_with_aad, CryptographyError};
+///
+/// let encrypted_data = encrypt_with_aad(b"Hello, world!", b"password", b"user/42").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_with_aad(&encrypted_data, b"password", b"user/42").expect("Failed to decrypt data!");
+/// assert_eq!(decrypt_with_aad(&encrypted_data, b"password", b"user/43"), Err(CryptographyError::IncorrectPassword));
+/// ```
+pub fn encrypt_with_aad(
+    data: &[u8],
+    password: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    encrypt_builder(data).aad(aad).run(password)
+}
+
+/// Function for decrypting data made by [`encrypt_with_aad`].
+/// Returns IncorrectPassword if the password or `aad` differs from what was encrypted with.
+pub fn decrypt_with_aad(
+    data: &[u8],
+    password: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    decrypt_builder(data).aad(aad).run(password)
+}
+
+/// Encryption with associated data accumulated piece by piece, see [`encrypt_builder`].
+pub struct EncryptBuilder<'a> {
+    data: &'a [u8],
+    aad: Vec<u8>,
+}
+
+impl EncryptBuilder<'_> {
+    /// Appends a piece of associated data.
+    pub fn aad(mut self, piece: &[u8]) -> Self {
+        push_piece(&mut self.aad, piece);
+        self
+    }
+
+    /// Encrypts the data with `password`, authenticating every piece added so far.
+    pub fn run(self, password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        let header = random_header(DEFAULT_CONTEXT)?;
+        let key = header.derive_key(password)?;
+
+        let ciphertext = seal_with_cipher(&new_cipher(&key), &header, self.data, &self.aad)?;
+
+        Binary.serialize(&header, &ciphertext)
+    }
+}
+
+/// Decryption with associated data accumulated piece by piece, see [`decrypt_builder`].
+pub struct DecryptBuilder<'a> {
+    data: &'a [u8],
+    aad: Vec<u8>,
+}
+
+impl DecryptBuilder<'_> {
+    /// Appends a piece of associated data.
+    pub fn aad(mut self, piece: &[u8]) -> Self {
+        push_piece(&mut self.aad, piece);
+        self
+    }
+
+    /// Decrypts the data with `password`.
+    /// Returns IncorrectPassword if the password or any associated data differs from what was encrypted with.
+    pub fn run(self, password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        let envelope = envelope::detect(self.data).ok_or(CryptographyError::DecodingFailure)?;
+        let (header, ciphertext) = envelope.deserialize(self.data)?;
+        let key = header.derive_key(password)?;
+
+        open_with_key(&key, &header, &ciphertext, &self.aad)
+    }
+}
+
+/// Appends a length prefixed piece, so `["ab", "c"]` and `["a", "bc"]` authenticate differently.
+fn push_piece(aad: &mut Vec<u8>, piece: &[u8]) {
+    aad.extend_from_slice(&(piece.len() as u64).to_le_bytes());
+    aad.extend_from_slice(piece);
+}
@@ -0,0 +1,82 @@
+//! Convergent encryption of content addressed chunks.
+//!
+//! Each chunk is encrypted under a key derived from a master key and the SHA-256 hash of its content,
+//! so identical chunks encrypt to identical ciphertext and can be deduplicated.
+//!
+//! This deliberately leaks equality: anyone holding the ciphertexts can tell which chunks are the same,
+//! and anyone holding the master key can confirm whether a guessed chunk is stored by encrypting it themselves.
+//! Keep chunk hashes as secret as the data they address, and don't use this for low entropy content such as short records.
+
+use crate::{new_cipher, CipherSuite, CryptographyError};
+use aes_gcm_siv::{aead::Aead, Nonce};
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+const CHUNK_INFO: &[u8] = b"tinycrypt-v1-chunk";
+
+/// Every chunk key encrypts exactly one plaintext, so a fixed nonce is safe.
+const CHUNK_NONCE: [u8; 12] = [0u8; 12];
+
+/// Function for deterministically encrypting a chunk for a deduplicating store.
+/// Returns the chunk's content hash, needed to decrypt it (store it in your encrypted index), along with the ciphertext.
+/// The same content under the same master key always gives the same ciphertext.
+/// Chunks are encrypted with AES-256-GCM-SIV, so the master key must be 32 bytes, other lengths return InvalidKeyLength.
+///
+/// ```rust
+/// use tinycrypt::{chunk_encrypt, chunk_decrypt};
+///
+/// let master_key = [7u8; 32];
+///
+/// let (hash, encrypted_chunk) = chunk_encrypt(b"Hello, world!", &master_key).expect("Failed to encrypt!");
+/// let (_, duplicate) = chunk_encrypt(b"Hello, world!", &master_key).expect("Failed to encrypt!");
+/// assert_eq!(encrypted_chunk, duplicate);
+///
+/// let chunk = chunk_decrypt(&encrypted_chunk, &master_key, &hash).expect("Failed to decrypt chunk!");
+/// ```
+pub fn chunk_encrypt<const N: usize>(
+    content: &[u8],
+    master_key: &[u8; N],
+) -> Result<([u8; 32], Vec<u8>), CryptographyError> {
+    let hash: [u8; 32] = Sha256::digest(content).into();
+    let key = chunk_key(master_key, &hash)?;
+
+    let ciphertext = new_cipher(key.as_ref())
+        .encrypt(Nonce::from_slice(&CHUNK_NONCE), content)
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    Ok((hash, ciphertext))
+}
+
+/// Function for decrypting a chunk made by [`chunk_encrypt`] with its content hash.
+/// Returns an IncorrectPassword if the master key or hash is wrong, or the chunk was modified.
+pub fn chunk_decrypt<const N: usize>(
+    ciphertext: &[u8],
+    master_key: &[u8; N],
+    hash: &[u8; 32],
+) -> Result<Vec<u8>, CryptographyError> {
+    let key = chunk_key(master_key, hash)?;
+
+    let content = new_cipher(key.as_ref())
+        .decrypt(Nonce::from_slice(&CHUNK_NONCE), ciphertext)
+        .map_err(|_| CryptographyError::IncorrectPassword)?;
+
+    if Sha256::digest(&content).as_slice() != hash {
+        return Err(CryptographyError::IncorrectPassword);
+    }
+
+    Ok(content)
+}
+
+/// Derives the key for a chunk, HKDF-SHA256 with the content hash as salt.
+fn chunk_key(master_key: &[u8], hash: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>, CryptographyError> {
+    CipherSuite::Aes256GcmSiv.check_key_len(master_key.len())?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+
+    Hkdf::<Sha256>::new(Some(hash), master_key)
+        .expand(CHUNK_INFO, key.as_mut())
+        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
+
+    Ok(key)
+}
@@ -0,0 +1,168 @@
+//! Compressing the plaintext before it's encrypted, see [`EncryptOptions::compression`](crate::EncryptOptions::compression).
+//!
+//! Ciphertext doesn't compress, so it has to happen before encrypting. The algorithm is stored in the blob's header
+//! and [`decrypt`](crate::decrypt) decompresses transparently, up to [`DEFAULT_MAX_DECOMPRESSED_BYTES`].
+
+use crate::CryptographyError;
+use alloc::vec::Vec;
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+use std::io::Read;
+use zeroize::Zeroizing;
+
+/// Most bytes [`decrypt`](crate::decrypt) decompresses a blob to (1 GiB), anything larger returns InputTooLarge.
+/// Compressed data can expand a thousandfold, so this keeps a small blob from exhausting memory.
+/// [`decrypt_bounded`](crate::decrypt_bounded) takes a limit of its own.
+pub const DEFAULT_MAX_DECOMPRESSED_BYTES: usize = 1 << 30;
+
+/// Compression applied to the plaintext before encrypting it. Each algorithm needs the cargo feature of the same name.
+///
+/// The blob's length then depends on the plaintext's content, so never compress secrets mixed with attacker chosen data,
+/// the length alone can reveal them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Compression {
+    /// No compression, the default.
+    #[default]
+    None,
+    /// DEFLATE (RFC 1951) at its default level.
+    #[cfg(feature = "deflate")]
+    Deflate,
+    /// Zstandard at the given level, 1 to 22 or negative for faster levels.
+    /// Only the algorithm is stored in the blob, headers read back from a blob carry zstd's default level.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Every compression this build supports, zstd at its default level.
+    pub(crate) const ALL: &'static [Self] = &[
+        Self::None,
+        #[cfg(feature = "deflate")]
+        Self::Deflate,
+        #[cfg(feature = "zstd")]
+        Self::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL),
+    ];
+
+    /// Id of the compression in a header's compression field, no compression's id 0 is never written out.
+    pub(crate) const fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "deflate")]
+            Self::Deflate => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(_) => 2,
+        }
+    }
+
+    /// The compression with id `id`, UnsupportedCompression if this build doesn't implement it.
+    pub(crate) fn from_id(id: u8) -> Result<Self, CryptographyError> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|compression| compression.id() == id)
+            .ok_or(CryptographyError::UnsupportedCompression(id))
+    }
+
+    pub(crate) fn is_none(&self) -> bool {
+        *self == Self::None
+    }
+
+    /// Compresses `data`, which is returned as is without compression.
+    pub(crate) fn compress(self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+        let compressed = match self {
+            Self::None => data.to_vec(),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                use std::io::Write;
+
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(data)
+                    .map_err(|_| CryptographyError::EncodingFailure)?;
+                encoder
+                    .finish()
+                    .map_err(|_| CryptographyError::EncodingFailure)?
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd(level) => {
+                zstd::bulk::compress(data, level).map_err(|_| CryptographyError::EncodingFailure)?
+            }
+        };
+
+        Ok(Zeroizing::new(compressed))
+    }
+
+    /// Decompresses `data`, returning InputTooLarge as soon as it expands past `max_len` bytes.
+    /// Data the decoder rejects returns Io, carrying the decoder's error.
+    pub(crate) fn decompress(
+        self,
+        data: &[u8],
+        max_len: usize,
+    ) -> Result<Vec<u8>, CryptographyError> {
+        let decompressed = match self {
+            Self::None if data.len() > max_len => return Err(CryptographyError::InputTooLarge),
+            Self::None => data.to_vec(),
+            // DEFLATE doesn't record the size, it's at least that of the compressed data.
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                read_at_most(flate2::read::DeflateDecoder::new(data), data.len(), max_len)?
+            }
+            // zstd frames record their size, unless written by something that left it out.
+            #[cfg(feature = "zstd")]
+            Self::Zstd(_) => read_at_most(
+                zstd::stream::read::Decoder::with_buffer(data)
+                    .map_err(|error| CryptographyError::Io(error.into()))?,
+                zstd::zstd_safe::get_frame_content_size(data)
+                    .ok()
+                    .flatten()
+                    .and_then(|size| usize::try_from(size).ok())
+                    .unwrap_or(data.len()),
+                max_len,
+            )?,
+        };
+
+        Ok(decompressed)
+    }
+}
+
+/// Reads `reader` to its end, returning InputTooLarge once more than `max_len` bytes come out of it.
+/// Room for `expected_len` bytes is made up front. The buffer is grown by hand, wiping the old one, where a
+/// reallocation would free memory with part of the plaintext still in it.
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+fn read_at_most<R: Read>(
+    mut reader: R,
+    expected_len: usize,
+    max_len: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut decompressed = Zeroizing::new(Vec::with_capacity(expected_len.min(max_len)));
+    let mut buffer = Zeroizing::new([0u8; 8 * 1024]);
+
+    loop {
+        let read = match reader.read(&mut buffer[..]) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(CryptographyError::Io(error.into())),
+        };
+
+        if read > max_len - decompressed.len() {
+            return Err(CryptographyError::InputTooLarge);
+        }
+
+        if read > decompressed.capacity() - decompressed.len() {
+            let capacity = (decompressed.capacity() * 2)
+                .max(decompressed.len() + read)
+                .min(max_len);
+
+            let mut grown = Zeroizing::new(Vec::with_capacity(capacity));
+            grown.extend_from_slice(&decompressed);
+            decompressed = grown;
+        }
+
+        decompressed.extend_from_slice(&buffer[..read]);
+    }
+
+    Ok(std::mem::take(&mut *decompressed))
+}
@@ -0,0 +1,258 @@
+//! Key derivation one argon2 slice at a time, for progress bars & for keeping a browser's main thread responsive.
+
+use crate::{
+    argon2id::Argon2id,
+    builder::{options_header, seal_with_options},
+    derive_key, envelope, open_with_key, Argon2Params, CipherSuite, CryptographyError, DerivedKey,
+    EncryptOptions, Header, Kdf, DEFAULT_CONTEXT, DEFAULT_MAX_MEMORY_BYTES,
+};
+#[cfg(feature = "armor")]
+use crate::{armor, dearmor};
+use zeroize::Zeroizing;
+
+/// A key derivation that runs one argon2 slice per [`step`](Self::step), rather than blocking until it's done.
+///
+/// Argon2 goes over its memory in `time_cost` passes of 4 slices each. Between two steps a UI can show
+/// [`progress`](Self::progress), and code on a browser's main thread can return to the event loop (say one step
+/// per animation frame), so a derivation never freezes the page. The key is the same [`decrypt`](crate::decrypt)
+/// and [`DerivedKey::from_password_with_params`] derive.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, KeyDerivation};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let mut derivation = KeyDerivation::for_blob(&encrypted_data, b"password").unwrap();
+///
+/// while !derivation.step() {
+///     let (done, total) = derivation.progress();
+///     println!("{done} of {total}");
+/// }
+///
+/// let decrypted_data = derivation.decrypt(&encrypted_data).expect("Failed to decrypt data!");
+/// assert_eq!(decrypted_data, b"Hello, world!");
+/// ```
+pub struct KeyDerivation {
+    salt: [u8; 32],
+    context: Vec<u8>,
+    kdf: Kdf,
+    params: Argon2Params,
+    suite: CipherSuite,
+    state: State,
+}
+
+enum State {
+    Argon2(Box<Argon2id>),
+    /// Keys of the other KDFs are derived right away.
+    Derived(Zeroizing<Vec<u8>>),
+}
+
+impl KeyDerivation {
+    /// Starts deriving the key [`DerivedKey::from_password_with_params`] derives, finish it with [`into_key`](Self::into_key).
+    /// Returns a KeyGenerationFailure if argon2 doesn't accept the parameters.
+    pub fn new(
+        password: &[u8],
+        salt: &[u8; 32],
+        params: &Argon2Params,
+    ) -> Result<Self, CryptographyError> {
+        Self::start(
+            password,
+            *salt,
+            DEFAULT_CONTEXT,
+            Kdf::current(),
+            *params,
+            CipherSuite::Aes256GcmSiv,
+        )
+    }
+
+    /// Starts deriving the key blob `data` was encrypted with, from the salt & parameters in its header.
+    /// Like [`decrypt`](crate::decrypt), returns InsufficientMemory for a header asking for more than
+    /// [`DEFAULT_MAX_MEMORY_BYTES`]. Blobs from before the versioned format return a DecodingFailure.
+    pub fn for_blob(data: &[u8], password: &[u8]) -> Result<Self, CryptographyError> {
+        #[cfg(feature = "armor")]
+        if armor::is_armored(data) {
+            let text = std::str::from_utf8(data).map_err(|_| CryptographyError::InvalidArmor)?;
+
+            return Self::for_blob(&dearmor(text)?, password);
+        }
+
+        let (header, _) = parse(data)?;
+
+        if header.kdf.is_argon2()
+            && header.params.estimated_memory_bytes() > DEFAULT_MAX_MEMORY_BYTES
+        {
+            return Err(CryptographyError::InsufficientMemory(header.params));
+        }
+
+        Self::for_header(&header, password)
+    }
+
+    /// Starts deriving the key for `header`, without any memory limit.
+    fn for_header(header: &Header, password: &[u8]) -> Result<Self, CryptographyError> {
+        Self::start(
+            password,
+            header.salt,
+            header.context_label(),
+            header.kdf,
+            header.params,
+            header.suite,
+        )
+    }
+
+    fn start(
+        password: &[u8],
+        salt: [u8; 32],
+        context: &[u8],
+        kdf: Kdf,
+        params: Argon2Params,
+        suite: CipherSuite,
+    ) -> Result<Self, CryptographyError> {
+        params.validate()?;
+
+        let state = if kdf.is_argon2() {
+            State::Argon2(Box::new(Argon2id::new(
+                password,
+                &salt,
+                context,
+                &params,
+                suite.key_len(),
+            )?))
+        } else {
+            State::Derived(derive_key(password, &salt, context, kdf, &params, suite)?)
+        };
+
+        Ok(Self {
+            salt,
+            context: context.to_vec(),
+            kdf,
+            params,
+            suite,
+            state,
+        })
+    }
+
+    /// Runs the next slice of the derivation, returning true once the key is derived.
+    /// Each call takes about `1 / (4 * time_cost)` of the whole derivation.
+    pub fn step(&mut self) -> bool {
+        match &mut self.state {
+            State::Argon2(argon2) => {
+                argon2.step();
+                argon2.is_done()
+            }
+            State::Derived(_) => true,
+        }
+    }
+
+    /// Slices derived so far and in total. Keys that don't use argon2 report 1 of 1 from the start.
+    pub fn progress(&self) -> (u32, u32) {
+        match &self.state {
+            State::Argon2(argon2) => argon2.progress(),
+            State::Derived(_) => (1, 1),
+        }
+    }
+
+    /// Runs the remaining steps and returns the key.
+    fn key(self) -> Zeroizing<Vec<u8>> {
+        match self.state {
+            State::Argon2(argon2) => argon2.finish(),
+            State::Derived(key) => key,
+        }
+    }
+
+    /// Runs the remaining steps and returns the key as a [`DerivedKey`].
+    /// A DerivedKey only holds AES-256-GCM-SIV keys derived with the default context label,
+    /// so derivations of other blobs return a KeyGenerationFailure.
+    pub fn into_key(self) -> Result<DerivedKey, CryptographyError> {
+        if self.context != DEFAULT_CONTEXT || self.kdf != Kdf::current() || !self.suite.is_default()
+        {
+            return Err(CryptographyError::KeyGenerationFailure);
+        }
+
+        let (salt, params) = (self.salt, self.params);
+
+        Ok(DerivedKey::from_key(&self.key(), &salt, &params))
+    }
+
+    /// Runs the remaining steps and decrypts `data` with the key.
+    /// Returns IncorrectPassword for a blob whose key was derived from a different salt or different parameters.
+    pub fn decrypt(self, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        #[cfg(feature = "armor")]
+        if armor::is_armored(data) {
+            let text = std::str::from_utf8(data).map_err(|_| CryptographyError::InvalidArmor)?;
+
+            return self.decrypt(&dearmor(text)?);
+        }
+
+        let (header, ciphertext) = parse(data)?;
+
+        if header.salt != self.salt
+            || header.context_label() != self.context
+            || header.kdf != self.kdf
+            || header.params != self.params
+            || header.suite != self.suite
+        {
+            return Err(CryptographyError::IncorrectPassword);
+        }
+
+        open_with_key(&self.key(), &header, &ciphertext, &[])
+    }
+}
+
+fn parse(data: &[u8]) -> Result<(Header, std::borrow::Cow<'_, [u8]>), CryptographyError> {
+    envelope::detect(data)
+        .ok_or(CryptographyError::DecodingFailure)?
+        .deserialize(data)
+}
+
+/// Function for decrypting data like [`decrypt`](crate::decrypt), calling `progress` with the slices derived so far
+/// and in total after every slice of the key derivation, see [`KeyDerivation`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt_with_progress};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_with_progress(&encrypted_data, b"password", |done, total| {
+///     println!("Deriving key, {}%", done * 100 / total);
+/// })
+/// .expect("Failed to decrypt data!");
+/// ```
+pub fn decrypt_with_progress<F: FnMut(u32, u32)>(
+    data: &[u8],
+    password: &[u8],
+    progress: F,
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut derivation = KeyDerivation::for_blob(data, password)?;
+    run(&mut derivation, progress);
+
+    derivation.decrypt(data)
+}
+
+/// Function for encrypting data like [`encrypt_with`](crate::encrypt_with), calling `progress` with the slices derived so far
+/// and in total after every slice of the key derivation, see [`KeyDerivation`].
+pub fn encrypt_with_progress<F: FnMut(u32, u32)>(
+    data: &[u8],
+    password: &[u8],
+    options: &EncryptOptions,
+    progress: F,
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = options_header(options)?;
+
+    let mut derivation = KeyDerivation::for_header(&header, password)?;
+    run(&mut derivation, progress);
+
+    seal_with_options(header, &derivation.key(), data, options)
+}
+
+fn run<F: FnMut(u32, u32)>(derivation: &mut KeyDerivation, mut progress: F) {
+    loop {
+        let done = derivation.step();
+
+        let (slices, total) = derivation.progress();
+        progress(slices, total);
+
+        if done {
+            break;
+        }
+    }
+}
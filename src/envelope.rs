@@ -0,0 +1,133 @@
+//! Serialization formats for encrypted blobs.
+//!
+//! An [`Envelope`] decides how a blob's [`Header`] and ciphertext are laid out on disk, it never changes the cryptography.
+//! [`Binary`] is the default compact format. [`Json`] (behind the `json` feature) keeps the non-secret fields human readable.
+//!
+//! Each built-in envelope starts with a distinct format id (`TCRY` for binary, `{` for JSON) so [`decrypt`](crate::decrypt) can tell them apart.
+
+use crate::{
+    format::{verify_checksum, write_checksum},
+    CryptographyError, Header,
+};
+use alloc::{borrow::Cow, vec::Vec};
+
+/// Blob serialization format.
+pub trait Envelope {
+    /// Lays out a header and ciphertext in this format.
+    fn serialize(&self, header: &Header, ciphertext: &[u8]) -> Result<Vec<u8>, CryptographyError>;
+
+    /// Splits a blob in this format back into its header and ciphertext.
+    fn deserialize<'a>(&self, data: &'a [u8])
+        -> Result<(Header, Cow<'a, [u8]>), CryptographyError>;
+}
+
+/// Compact binary format, the encoded header followed by the ciphertext. Used by [`encrypt`](crate::encrypt).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Binary;
+
+impl Envelope for Binary {
+    fn serialize(&self, header: &Header, ciphertext: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        let mut blob = header.encode()?;
+        blob.extend_from_slice(ciphertext);
+        write_checksum(&mut blob);
+
+        Ok(blob)
+    }
+
+    fn deserialize<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<(Header, Cow<'a, [u8]>), CryptographyError> {
+        let (header, header_len) = Header::parse(data)?;
+        verify_checksum(data)?;
+
+        Ok((header, Cow::Borrowed(&data[header_len..])))
+    }
+}
+
+/// JSON format with the ciphertext base64 encoded, for inspecting the non-secret fields by hand.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_envelope, decrypt, envelope::Json};
+///
+/// let encrypted_data = encrypt_with_envelope(b"Hello, world!", b"password", &Json).expect("Failed to encrypt!");
+/// assert!(encrypted_data.starts_with(b"{"));
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonBlob<'a> {
+    format: Cow<'a, str>,
+    version: u8,
+    header: Header,
+    ciphertext: Cow<'a, str>,
+}
+
+#[cfg(feature = "json")]
+impl Envelope for Json {
+    fn serialize(&self, header: &Header, ciphertext: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let blob = JsonBlob {
+            format: Cow::Borrowed("tinycrypt"),
+            version: header.version,
+            header: header.clone(),
+            ciphertext: Cow::Owned(STANDARD.encode(ciphertext)),
+        };
+
+        serde_json::to_vec(&blob).map_err(|_| CryptographyError::EncodingFailure)
+    }
+
+    fn deserialize<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<(Header, Cow<'a, [u8]>), CryptographyError> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let mut blob: JsonBlob =
+            serde_json::from_slice(data).map_err(|_| CryptographyError::DecodingFailure)?;
+
+        let known_version = blob.version == crate::format::VERSION
+            || blob.version == crate::format::VERSION_NO_CHECKSUM;
+
+        if blob.format != "tinycrypt" {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        if blob.version > crate::format::VERSION {
+            return Err(CryptographyError::UnsupportedVersion(blob.version));
+        }
+
+        // The binary layout checks the nonce length while parsing.
+        if !known_version || blob.header.nonce.len() != blob.header.suite.nonce_len() {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        blob.header.version = blob.version;
+
+        let ciphertext = STANDARD
+            .decode(blob.ciphertext.as_bytes())
+            .map_err(|_| CryptographyError::DecodingFailure)?;
+
+        Ok((blob.header, Cow::Owned(ciphertext)))
+    }
+}
+
+/// Picks the built-in envelope matching the format id at the start of `data`.
+pub(crate) fn detect(data: &[u8]) -> Option<&'static dyn Envelope> {
+    if Header::is_versioned(data) {
+        return Some(&Binary);
+    }
+
+    #[cfg(feature = "json")]
+    if data.first() == Some(&b'{') {
+        return Some(&Json);
+    }
+
+    None
+}
@@ -0,0 +1,95 @@
+//! Errors of lower layers carried by [`CryptographyError`](crate::CryptographyError).
+//!
+//! They're shared behind an [`Arc`], so CryptographyError stays Clone & PartialEq.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    sync::Arc,
+};
+
+/// An [`io::Error`] carried by [`CryptographyError::Io`](crate::CryptographyError::Io).
+/// Two compare equal if they're of the same [`kind`](Self::kind).
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    /// The kind of the underlying error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// The underlying error.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl From<io::Error> for IoError {
+    fn from(error: io::Error) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+impl From<io::ErrorKind> for IoError {
+    fn from(kind: io::ErrorKind) -> Self {
+        io::Error::from(kind).into()
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
+impl Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Display already prints the underlying error, so its source is the next cause in the chain.
+impl Error for IoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// A bincode error carried by [`CryptographyError::Bincode`](crate::CryptographyError::Bincode).
+/// Two compare equal if they describe the same failure.
+#[derive(Debug, Clone)]
+pub struct BincodeError(Arc<bincode::ErrorKind>);
+
+impl BincodeError {
+    /// The underlying error.
+    pub fn get_ref(&self) -> &bincode::ErrorKind {
+        &self.0
+    }
+}
+
+impl From<bincode::Error> for BincodeError {
+    fn from(error: bincode::Error) -> Self {
+        Self(Arc::from(error))
+    }
+}
+
+impl PartialEq for BincodeError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Display for BincodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Display already prints the underlying error, so its source is the next cause in the chain.
+impl Error for BincodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,860 @@
+//! Versioned blob layout.
+//!
+//! ```text
+//! magic       4 bytes   b"TCRY"
+//! version     1 byte    2
+//! checksum    4 bytes   u32 LE, CRC-32 of the whole blob with these 4 bytes zeroed (version 2 only)
+//! fields len  2 bytes   u16 LE, total length of the fields below
+//! fields      tag (1 byte), length (u16 LE), value; repeated
+//! ciphertext  everything after the header, ciphertext followed by the 16 byte tag
+//! ```
+//!
+//! The whole header (magic through fields, with the checksum zeroed) is fed to the AEAD as associated data, so none of it can be altered.
+//! The checksum only catches accidental corruption, cheaply and before any key is derived; it's no protection against tampering.
+//! Version 1 blobs have no checksum and are still decrypted. Blobs that don't start with the magic bytes are the legacy bincode layout.
+//!
+//! The version byte is the major version, bumped for any change an older reader can't safely skip over.
+//! Blobs with a newer major version are rejected with UnsupportedVersion, unknown older ones with a DecodingFailure.
+//! Purely additive changes bump the minor version instead, stored in its own field (tag 7, 1 byte) that's left out for minor 0.
+//! The minor field follows the minor 0 fields, and the fields added by each minor follow it in order of their tag numbers.
+//! Blobs only use a minor above 0 when they need one of its fields.
+//! [`decrypt`](crate::decrypt) only accepts the minor versions it knows, while [`decrypt_lenient`](crate::decrypt_lenient)
+//! also accepts newer ones: the fields it doesn't know are kept verbatim, so they're still authenticated, but otherwise ignored.
+//!
+//! A tag is its field's number (the low 7 bits) plus a must-understand bit (0x80). Fields with the bit set change how the
+//! ciphertext is decrypted or how the plaintext has to be read, so ignoring them would return the wrong plaintext.
+//! A reader that doesn't know such a field rejects the blob with UnsupportedField, even on the lenient path.
+//!
+//! Minor 1 adds the codec field (tag 8, 1 byte), the serialization format of an encrypted value's plaintext.
+//! Minor 2 adds the epoch field (tag 9, u32 LE), the key rotation epoch the blob's key belongs to.
+//! Minor 3 adds the password verifier field (tag 10, 32 bytes).
+//! Minor 4 adds the cipher suite field (number 11, must-understand so tag 0x8b, 1 byte), left out for AES-256-GCM-SIV.
+//! The nonce field is as long as the suite's nonce.
+//! Minor 5 adds the compression field (number 12, must-understand so tag 0x8c, 1 byte), the algorithm the plaintext was
+//! compressed with before encrypting.
+
+use crate::{
+    derive_key, Argon2Params, CipherSuite, Compression, CryptographyError, Kdf, DEFAULT_CONTEXT,
+    DEFAULT_MAX_MEMORY_BYTES,
+};
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime};
+use zeroize::Zeroizing;
+
+pub(crate) const MAGIC: &[u8; 4] = b"TCRY";
+pub(crate) const VERSION: u8 = 2;
+
+/// First versioned layout, without the checksum.
+pub(crate) const VERSION_NO_CHECKSUM: u8 = 1;
+
+/// Newest minor version this build knows. Minor 0 has no minor field.
+pub(crate) const MINOR_VERSION: u8 = 5;
+
+/// Longest context label that can be stored in a header.
+pub const MAX_CONTEXT_LEN: usize = 255;
+
+const CHECKSUM_OFFSET: usize = MAGIC.len() + 1;
+const CHECKSUM_LEN: usize = 4;
+const FIELD_HEADER_LEN: usize = 1 + 2;
+
+const TAG_SALT: u8 = 1;
+const TAG_NONCE: u8 = 2;
+const TAG_CONTEXT: u8 = 3;
+const TAG_CREATED: u8 = 4;
+const TAG_KDF: u8 = 5;
+const TAG_PARAMS: u8 = 6;
+const TAG_MINOR: u8 = 7;
+const TAG_CODEC: u8 = 8;
+const TAG_EPOCH: u8 = 9;
+const TAG_VERIFIER: u8 = 10;
+const TAG_SUITE: u8 = MUST_UNDERSTAND | 11;
+const TAG_COMPRESSION: u8 = MUST_UNDERSTAND | 12;
+
+/// Bit of a tag marking a field a reader has to know to decrypt the blob correctly, it can never be skipped.
+const MUST_UNDERSTAND: u8 = 0x80;
+
+/// Highest field number this build knows, newer minor versions only add fields numbered above it.
+const LAST_NUMBER: u8 = 12;
+
+/// Minor version adding the epoch field.
+const MINOR_EPOCH: u8 = 2;
+
+/// First minor version with a password verifier field.
+pub(crate) const MINOR_VERIFIER: u8 = 3;
+
+/// Minor version adding the cipher suite field.
+const MINOR_SUITE: u8 = 4;
+
+/// Minor version adding the compression field.
+const MINOR_COMPRESSION: u8 = 5;
+
+/// Value of the KDF field for keys from the `insecure-fast-kdf` feature, argon2 headers have no KDF field.
+/// Any other single byte value is a KDF this version doesn't know, reported as UnsupportedKdf.
+const KDF_INSECURE_SHA256: u8 = 1;
+
+/// Value of the KDF field for keys supplied by a [`KeyProvider`](crate::KeyProvider).
+const KDF_PROVIDER: u8 = 2;
+
+/// Non-secret parameters stored alongside the ciphertext (salt, nonce, KDF context & creation time).
+///
+/// Its [`encode`](Self::encode)d form is authenticated as associated data, whichever [`Envelope`](crate::Envelope) the blob is stored in.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    /// Layout version the header is encoded with, JSON blobs store it outside the header.
+    #[cfg_attr(feature = "json", serde(skip, default = "version"))]
+    pub(crate) version: u8,
+    pub(crate) salt: [u8; 32],
+    /// Nonce of the header's cipher suite, 12 or 24 bytes long.
+    pub(crate) nonce: Vec<u8>,
+    /// Custom KDF context label, None for the default label.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) context: Option<Vec<u8>>,
+    /// Creation time in seconds since the unix epoch, if the blob was timestamped.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) created: Option<u64>,
+    /// Key derivation function, only stored for the insecure test KDF.
+    #[cfg_attr(
+        feature = "json",
+        serde(default = "argon2", skip_serializing_if = "Kdf::is_argon2")
+    )]
+    pub(crate) kdf: Kdf,
+    /// Argon2 cost parameters, only stored when they differ from the defaults.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Argon2Params::is_default")
+    )]
+    pub(crate) params: Argon2Params,
+    /// Minor version, 0 unless a field of a later minor is used.
+    #[cfg_attr(feature = "json", serde(default, skip_serializing_if = "is_zero"))]
+    pub(crate) minor: u8,
+    /// Serialization format id of an encrypted value's plaintext, 0 for bincode (minor 1).
+    #[cfg_attr(feature = "json", serde(default, skip_serializing_if = "is_zero"))]
+    pub(crate) codec: u8,
+    /// Key rotation epoch of the blob's key, if stamped (minor 2).
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) epoch: Option<u32>,
+    /// Password verifier, see [`verify_header`](crate::verify_header) (minor 3).
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) verifier: Option<[u8; 32]>,
+    /// AEAD the blob is encrypted with (minor 4).
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "CipherSuite::is_default")
+    )]
+    pub(crate) suite: CipherSuite,
+    /// Compression applied to the plaintext before encrypting (minor 5).
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Compression::is_none")
+    )]
+    pub(crate) compression: Compression,
+    /// The fields of a newer minor version this build doesn't know, see [`decrypt_lenient`](crate::decrypt_lenient).
+    #[cfg_attr(feature = "json", serde(skip))]
+    pub(crate) extra: Vec<u8>,
+}
+
+impl Header {
+    /// Returns true if `data` starts with the versioned header magic.
+    pub(crate) fn is_versioned(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// The KDF context label this header was written with.
+    pub(crate) fn context_label(&self) -> &[u8] {
+        self.context.as_deref().unwrap_or(DEFAULT_CONTEXT)
+    }
+
+    /// Derives the key for a blob with this header from `password`, within [`DEFAULT_MAX_MEMORY_BYTES`].
+    pub(crate) fn derive_key(
+        &self,
+        password: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+        self.derive_key_within(password, DEFAULT_MAX_MEMORY_BYTES)
+    }
+
+    /// Like [`derive_key`](Self::derive_key), returning InsufficientMemory before deriving anything if argon2
+    /// would need more than `max_memory_bytes`.
+    pub(crate) fn derive_key_within(
+        &self,
+        password: &[u8],
+        max_memory_bytes: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+        if self.kdf.is_argon2() && self.params.estimated_memory_bytes() > max_memory_bytes {
+            return Err(CryptographyError::InsufficientMemory(self.params));
+        }
+
+        derive_key(
+            password,
+            &self.salt,
+            self.context_label(),
+            self.kdf,
+            &self.params,
+            self.suite,
+        )
+    }
+
+    /// The argon2 cost parameters the key is derived with.
+    pub fn params(&self) -> Argon2Params {
+        self.params
+    }
+
+    /// The creation time this header was written with, if any.
+    #[cfg(feature = "std")]
+    pub fn created(&self) -> Result<Option<SystemTime>, CryptographyError> {
+        self.created.map(timestamp).transpose()
+    }
+
+    /// The key rotation epoch this header was stamped with, if any.
+    pub fn epoch(&self) -> Option<u32> {
+        self.epoch
+    }
+
+    /// The cipher suite the blob is encrypted with.
+    pub fn suite(&self) -> CipherSuite {
+        self.suite
+    }
+
+    /// Switches the header to `suite`, raising its minor version as needed.
+    /// The nonce must already be as long as the suite's.
+    pub(crate) fn set_suite(&mut self, suite: CipherSuite) {
+        self.suite = suite;
+
+        if !suite.is_default() {
+            self.minor = self.minor.max(MINOR_SUITE);
+        }
+    }
+
+    /// The compression the plaintext was compressed with before encrypting.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Compresses the plaintext with `compression` before encrypting, raising the minor version as needed.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+
+        if !compression.is_none() {
+            self.minor = self.minor.max(MINOR_COMPRESSION);
+        }
+    }
+
+    /// Stamps the header with a key rotation epoch, raising its minor version as needed.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_epoch(&mut self, epoch: u32) {
+        self.epoch = Some(epoch);
+        self.minor = self.minor.max(MINOR_EPOCH);
+    }
+
+    /// Number of bytes [`write`](Self::write) writes.
+    pub(crate) fn encoded_len(&self) -> usize {
+        let mut length =
+            prefix_len(self.version) + FIELD_HEADER_LEN * 2 + self.salt.len() + self.nonce.len();
+
+        if let Some(context) = &self.context {
+            length += FIELD_HEADER_LEN + context.len();
+        }
+
+        if self.created.is_some() {
+            length += FIELD_HEADER_LEN + 8;
+        }
+
+        if !self.kdf.is_argon2() {
+            length += FIELD_HEADER_LEN + 1;
+        }
+
+        if !self.params.is_default() {
+            length += FIELD_HEADER_LEN + 12;
+        }
+
+        if self.minor != 0 {
+            length += FIELD_HEADER_LEN + 1;
+        }
+
+        if self.codec != 0 {
+            length += FIELD_HEADER_LEN + 1;
+        }
+
+        if self.epoch.is_some() {
+            length += FIELD_HEADER_LEN + 4;
+        }
+
+        if self.verifier.is_some() {
+            length += FIELD_HEADER_LEN + 32;
+        }
+
+        if !self.suite.is_default() {
+            length += FIELD_HEADER_LEN + 1;
+        }
+
+        if !self.compression.is_none() {
+            length += FIELD_HEADER_LEN + 1;
+        }
+
+        length + self.extra.len()
+    }
+
+    /// Writes the header into the start of `out`, which must be at least [`encoded_len`](Self::encoded_len) long.
+    pub(crate) fn write(&self, out: &mut [u8]) -> Result<usize, CryptographyError> {
+        let length = self.encoded_len();
+        let fields_len = u16::try_from(length - prefix_len(self.version))
+            .map_err(|_| CryptographyError::EncodingFailure)?;
+
+        let mut writer = Writer {
+            out: out
+                .get_mut(..length)
+                .ok_or(CryptographyError::BufferTooSmall)?,
+            position: 0,
+        };
+
+        writer.prefix(self.version, fields_len)?;
+        writer.field(TAG_SALT, &self.salt)?;
+        writer.field(TAG_NONCE, &self.nonce)?;
+
+        if let Some(context) = &self.context {
+            writer.field(TAG_CONTEXT, context)?;
+        }
+
+        if let Some(created) = self.created {
+            writer.field(TAG_CREATED, &created.to_le_bytes())?;
+        }
+
+        match self.kdf {
+            Kdf::Argon2 => {}
+            Kdf::InsecureSha256 => writer.field(TAG_KDF, &[KDF_INSECURE_SHA256])?,
+            Kdf::Provider => writer.field(TAG_KDF, &[KDF_PROVIDER])?,
+        }
+
+        if !self.params.is_default() {
+            let mut params = [0u8; 12];
+            params[..4].copy_from_slice(&self.params.mem_cost.to_le_bytes());
+            params[4..8].copy_from_slice(&self.params.time_cost.to_le_bytes());
+            params[8..].copy_from_slice(&self.params.lanes.to_le_bytes());
+
+            writer.field(TAG_PARAMS, &params)?;
+        }
+
+        if self.minor != 0 {
+            writer.field(TAG_MINOR, &[self.minor])?;
+        }
+
+        if self.codec != 0 {
+            writer.field(TAG_CODEC, &[self.codec])?;
+        }
+
+        if let Some(epoch) = self.epoch {
+            writer.field(TAG_EPOCH, &epoch.to_le_bytes())?;
+        }
+
+        if let Some(verifier) = &self.verifier {
+            writer.field(TAG_VERIFIER, verifier)?;
+        }
+
+        if !self.suite.is_default() {
+            writer.field(TAG_SUITE, &[self.suite.id()])?;
+        }
+
+        if !self.compression.is_none() {
+            writer.field(TAG_COMPRESSION, &[self.compression.id()])?;
+        }
+
+        writer.put(&self.extra)?;
+
+        Ok(length)
+    }
+
+    /// Encodes the header into its canonical binary form, as found at the start of a [`Binary`](crate::envelope::Binary) blob
+    /// (with the checksum zeroed).
+    pub fn encode(&self) -> Result<Vec<u8>, CryptographyError> {
+        let mut out = vec![0u8; self.encoded_len()];
+        self.write(&mut out)?;
+
+        Ok(out)
+    }
+
+    /// Parses a binary header at the start of `data`, returning it along with its encoded length.
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), CryptographyError> {
+        Self::parse_with(data, false)
+    }
+
+    /// Like [`parse`](Self::parse), also accepting newer minor versions.
+    pub(crate) fn parse_lenient(data: &[u8]) -> Result<(Self, usize), CryptographyError> {
+        Self::parse_with(data, true)
+    }
+
+    fn parse_with(data: &[u8], lenient: bool) -> Result<(Self, usize), CryptographyError> {
+        if !Self::is_versioned(data) {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let version = *data.get(4).ok_or(CryptographyError::DecodingFailure)?;
+
+        if version > VERSION {
+            return Err(CryptographyError::UnsupportedVersion(version));
+        }
+
+        if version != VERSION && version != VERSION_NO_CHECKSUM {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let prefix_len = prefix_len(version);
+        let fields_len = read_u16(data, prefix_len - 2)? as usize;
+        let length = prefix_len + fields_len;
+        let mut fields = data
+            .get(prefix_len..length)
+            .ok_or(CryptographyError::DecodingFailure)?;
+
+        let mut salt = None;
+        let mut nonce = None;
+        let mut context = None;
+        let mut created = None;
+        let mut kdf = None;
+        let mut params = None;
+        let mut minor = None;
+        let mut codec = None;
+        let mut epoch = None;
+        let mut verifier = None;
+        let mut suite = None;
+        let mut compression = None;
+        let mut extra = Vec::new();
+
+        while !fields.is_empty() {
+            let tag = fields[0];
+            let value_len = read_u16(fields, 1)? as usize;
+            let value = fields
+                .get(FIELD_HEADER_LEN..FIELD_HEADER_LEN + value_len)
+                .ok_or(CryptographyError::DecodingFailure)?;
+
+            let duplicate = match tag {
+                TAG_SALT => salt.replace(to_array(value)?).is_some(),
+                TAG_NONCE => nonce.replace(value.to_vec()).is_some(),
+                TAG_CONTEXT if value.len() <= MAX_CONTEXT_LEN => {
+                    context.replace(value.to_vec()).is_some()
+                }
+                TAG_CREATED => {
+                    let seconds = u64::from_le_bytes(to_array(value)?);
+                    // Without std there's no SystemTime to represent it as.
+                    #[cfg(feature = "std")]
+                    timestamp(seconds)?;
+
+                    created.replace(seconds).is_some()
+                }
+                TAG_KDF if value == [KDF_INSECURE_SHA256] => {
+                    kdf.replace(Kdf::InsecureSha256).is_some()
+                }
+                TAG_KDF if value == [KDF_PROVIDER] => kdf.replace(Kdf::Provider).is_some(),
+                // Most likely written by a newer version with a KDF this one doesn't know yet.
+                TAG_KDF if value.len() == 1 => {
+                    return Err(CryptographyError::UnsupportedKdf(value[0]))
+                }
+                TAG_PARAMS => {
+                    let value: [u8; 12] = to_array(value)?;
+                    let field = |offset: usize| {
+                        u32::from_le_bytes([
+                            value[offset],
+                            value[offset + 1],
+                            value[offset + 2],
+                            value[offset + 3],
+                        ])
+                    };
+                    let decoded = Argon2Params {
+                        mem_cost: field(0),
+                        time_cost: field(4),
+                        lanes: field(8),
+                    };
+
+                    decoded
+                        .validate()
+                        .map_err(|_| CryptographyError::DecodingFailure)?;
+
+                    params.replace(decoded).is_some()
+                }
+                // Minor 0 is never written out.
+                TAG_MINOR if value.len() == 1 && value[0] != 0 => {
+                    if value[0] > MINOR_VERSION && !lenient {
+                        return Err(CryptographyError::DecodingFailure);
+                    }
+
+                    minor.replace(value[0]).is_some()
+                }
+                TAG_CODEC if value.len() == 1 && value[0] != 0 => codec.replace(value[0]).is_some(),
+                TAG_EPOCH => epoch
+                    .replace(u32::from_le_bytes(to_array(value)?))
+                    .is_some(),
+                TAG_VERIFIER => verifier.replace(to_array(value)?).is_some(),
+                // AES-256-GCM-SIV is never written out.
+                TAG_SUITE if value.len() == 1 && value[0] != 0 => {
+                    suite.replace(CipherSuite::from_id(value[0])?).is_some()
+                }
+                // No compression is never written out.
+                TAG_COMPRESSION if value.len() == 1 && value[0] != 0 => compression
+                    .replace(Compression::from_id(value[0])?)
+                    .is_some(),
+                // A field of a newer minor version, it and everything after it is kept as is.
+                tag if number(tag) > LAST_NUMBER && minor > Some(MINOR_VERSION) => {
+                    check_newer_fields(fields)?;
+                    extra = fields.to_vec();
+
+                    break;
+                }
+                _ => return Err(CryptographyError::DecodingFailure),
+            };
+
+            if duplicate {
+                return Err(CryptographyError::DecodingFailure);
+            }
+
+            fields = &fields[FIELD_HEADER_LEN + value_len..];
+        }
+
+        let minor = minor.unwrap_or(0);
+
+        // Fields of a later minor than the header claims.
+        if (minor == 0 && codec.is_some())
+            || (minor < MINOR_EPOCH && epoch.is_some())
+            || (minor < MINOR_VERIFIER && verifier.is_some())
+            || (minor < MINOR_SUITE && suite.is_some())
+            || (minor < MINOR_COMPRESSION && compression.is_some())
+        {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let suite = suite.unwrap_or_default();
+        let nonce = nonce
+            .filter(|nonce| nonce.len() == suite.nonce_len())
+            .ok_or(CryptographyError::DecodingFailure)?;
+
+        let header = Self {
+            version,
+            salt: salt.ok_or(CryptographyError::DecodingFailure)?,
+            nonce,
+            context,
+            created,
+            kdf: kdf.unwrap_or(Kdf::Argon2),
+            params: params.unwrap_or_default(),
+            minor,
+            codec: codec.unwrap_or(0),
+            epoch,
+            verifier,
+            suite,
+            compression: compression.unwrap_or_default(),
+            extra,
+        };
+
+        Ok((header, length))
+    }
+}
+
+/// How likely some leading bytes are the start of a binary tinycrypt blob, see [`probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeResult {
+    /// The bytes can't start a blob, no matter what follows.
+    DefinitelyNot,
+    /// Everything present is valid, but more bytes are needed to tell.
+    Maybe,
+    /// The bytes start with a complete, valid header.
+    Yes,
+}
+
+/// Smallest fields length a header can have, the salt & nonce fields.
+const MIN_FIELDS_LEN: usize = FIELD_HEADER_LEN * 2 + 32 + 12;
+
+/// Function for checking whether the first bytes of some data could be a [`Binary`](crate::envelope::Binary) blob,
+/// without needing the rest of it. Meant for scanning many files while fetching as few bytes as possible.
+///
+/// - `DefinitelyNot` needs as little as 1 byte: any byte that differs from the 4 magic bytes, or a 5th byte that isn't
+///   a supported version, or a fields length too small to hold a salt and nonce (bytes 10 & 11, or 6 & 7 in version 1).
+/// - `Yes` needs the full header, 11 bytes plus the fields length (61 bytes for a blob from [`encrypt`](crate::encrypt)),
+///   and a DefinitelyNot is returned if it doesn't parse. The ciphertext after the header isn't checked.
+/// - `Maybe` is returned for anything in between, including empty input.
+///
+/// Only the versioned binary layout is recognized, legacy and JSON blobs give DefinitelyNot.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, probe, ProbeResult};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// assert_eq!(probe(b"\x89PNG"), ProbeResult::DefinitelyNot);
+/// assert_eq!(probe(&encrypted_data[..4]), ProbeResult::Maybe);
+/// assert_eq!(probe(&encrypted_data), ProbeResult::Yes);
+/// ```
+pub fn probe(first_bytes: &[u8]) -> ProbeResult {
+    let magic_len = first_bytes.len().min(MAGIC.len());
+
+    if first_bytes[..magic_len] != MAGIC[..magic_len] {
+        return ProbeResult::DefinitelyNot;
+    }
+
+    let prefix_len = match first_bytes.get(MAGIC.len()) {
+        None => return ProbeResult::Maybe,
+        Some(&version) if version == VERSION || version == VERSION_NO_CHECKSUM => {
+            prefix_len(version)
+        }
+        Some(_) => return ProbeResult::DefinitelyNot,
+    };
+
+    let Ok(fields_len) = read_u16(first_bytes, prefix_len - 2) else {
+        return ProbeResult::Maybe;
+    };
+
+    if (fields_len as usize) < MIN_FIELDS_LEN {
+        return ProbeResult::DefinitelyNot;
+    }
+
+    if first_bytes.len() < prefix_len + fields_len as usize {
+        return ProbeResult::Maybe;
+    }
+
+    match Header::parse_lenient(first_bytes) {
+        Ok(_)
+        | Err(CryptographyError::UnsupportedKdf(_))
+        | Err(CryptographyError::UnsupportedCipherSuite(_))
+        | Err(CryptographyError::UnsupportedCompression(_))
+        | Err(CryptographyError::UnsupportedField(_)) => ProbeResult::Yes,
+        Err(_) => ProbeResult::DefinitelyNot,
+    }
+}
+
+struct Writer<'a> {
+    out: &'a mut [u8],
+    position: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) -> Result<(), CryptographyError> {
+        let end = self.position + bytes.len();
+
+        self.out
+            .get_mut(self.position..end)
+            .ok_or(CryptographyError::EncodingFailure)?
+            .copy_from_slice(bytes);
+        self.position = end;
+
+        Ok(())
+    }
+
+    fn prefix(&mut self, version: u8, fields_len: u16) -> Result<(), CryptographyError> {
+        self.put(MAGIC)?;
+        self.put(&[version])?;
+
+        // Filled in by write_checksum once the whole blob is known.
+        if version != VERSION_NO_CHECKSUM {
+            self.put(&[0u8; CHECKSUM_LEN])?;
+        }
+
+        self.put(&fields_len.to_le_bytes())
+    }
+
+    fn field(&mut self, tag: u8, value: &[u8]) -> Result<(), CryptographyError> {
+        let value_len =
+            u16::try_from(value.len()).map_err(|_| CryptographyError::EncodingFailure)?;
+
+        self.put(&[tag])?;
+        self.put(&value_len.to_le_bytes())?;
+        self.put(value)
+    }
+}
+
+/// Checks the fields from the first one of a newer minor version on are well formed and only ones this version doesn't know.
+/// Returns UnsupportedField for the first must-understand one, which can't be ignored.
+fn check_newer_fields(mut fields: &[u8]) -> Result<(), CryptographyError> {
+    while !fields.is_empty() {
+        let value_len = read_u16(fields, 1)? as usize;
+
+        if number(fields[0]) <= LAST_NUMBER || fields.len() < FIELD_HEADER_LEN + value_len {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        if fields[0] & MUST_UNDERSTAND != 0 {
+            return Err(CryptographyError::UnsupportedField(fields[0]));
+        }
+
+        fields = &fields[FIELD_HEADER_LEN + value_len..];
+    }
+
+    Ok(())
+}
+
+/// Number of the field a tag belongs to, without its must-understand bit.
+fn number(tag: u8) -> u8 {
+    tag & !MUST_UNDERSTAND
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, CryptographyError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(CryptographyError::DecodingFailure)
+}
+
+/// Converts seconds since the unix epoch into a SystemTime, failing if it can't be represented.
+#[cfg(feature = "std")]
+fn timestamp(seconds: u64) -> Result<SystemTime, CryptographyError> {
+    SystemTime::UNIX_EPOCH
+        .checked_add(Duration::from_secs(seconds))
+        .ok_or(CryptographyError::DecodingFailure)
+}
+
+/// Number of bytes [`write_default_header`] writes.
+pub(crate) fn default_header_len() -> usize {
+    let kdf_len = if Kdf::current().is_argon2() {
+        0
+    } else {
+        FIELD_HEADER_LEN + 1
+    };
+
+    prefix_len(VERSION) + FIELD_HEADER_LEN * 2 + 32 + 12 + kdf_len
+}
+
+/// Writes the header of a blob with the default context, parameters & suite straight into `out`.
+/// Gives the same bytes as [`Header::write`] without building a [`Header`], so nothing is allocated.
+pub(crate) fn write_default_header(
+    out: &mut [u8],
+    salt: &[u8; 32],
+    nonce: &[u8; 12],
+) -> Result<usize, CryptographyError> {
+    let length = default_header_len();
+    let fields_len = u16::try_from(length - prefix_len(VERSION))
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    let mut writer = Writer {
+        out: out
+            .get_mut(..length)
+            .ok_or(CryptographyError::BufferTooSmall)?,
+        position: 0,
+    };
+
+    writer.prefix(VERSION, fields_len)?;
+    writer.field(TAG_SALT, salt)?;
+    writer.field(TAG_NONCE, nonce)?;
+
+    if !Kdf::current().is_argon2() {
+        writer.field(TAG_KDF, &[KDF_INSECURE_SHA256])?;
+    }
+
+    Ok(length)
+}
+
+/// Length of the header before its fields.
+fn prefix_len(version: u8) -> usize {
+    let checksum_len = if version == VERSION_NO_CHECKSUM {
+        0
+    } else {
+        CHECKSUM_LEN
+    };
+
+    MAGIC.len() + 1 + checksum_len + 2
+}
+
+/// Fills in the checksum of a complete binary blob whose checksum is still zeroed, if its version has one.
+pub(crate) fn write_checksum(blob: &mut [u8]) {
+    if blob.get(MAGIC.len()) == Some(&VERSION) && blob.len() >= CHECKSUM_OFFSET + CHECKSUM_LEN {
+        let checksum = crc32(&[blob]);
+        blob[CHECKSUM_OFFSET..CHECKSUM_OFFSET + CHECKSUM_LEN]
+            .copy_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+/// Checks the checksum of a complete binary blob, if its version has one.
+pub(crate) fn verify_checksum(blob: &[u8]) -> Result<(), CryptographyError> {
+    verify_split_checksum(blob, &[])
+}
+
+/// Like [`verify_checksum`], for a blob stored as its header and the ciphertext after it.
+pub(crate) fn verify_split_checksum(
+    header: &[u8],
+    ciphertext: &[u8],
+) -> Result<(), CryptographyError> {
+    if header.get(MAGIC.len()) != Some(&VERSION) {
+        return Ok(());
+    }
+
+    let stored = header
+        .get(CHECKSUM_OFFSET..CHECKSUM_OFFSET + CHECKSUM_LEN)
+        .ok_or(CryptographyError::DecodingFailure)?;
+    let checksum = crc32(&[
+        &header[..CHECKSUM_OFFSET],
+        &[0u8; CHECKSUM_LEN],
+        &header[CHECKSUM_OFFSET + CHECKSUM_LEN..],
+        ciphertext,
+    ]);
+
+    if stored == checksum.to_le_bytes() {
+        Ok(())
+    } else {
+        Err(CryptographyError::DecodingFailure)
+    }
+}
+
+/// CRC-32 (IEEE) lookup table.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// CRC-32 (IEEE) of the concatenation of `parts`.
+pub(crate) fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+
+    for part in parts {
+        for &byte in *part {
+            crc = CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(feature = "json")]
+fn version() -> u8 {
+    VERSION
+}
+
+#[cfg(feature = "json")]
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+#[cfg(feature = "json")]
+fn argon2() -> Kdf {
+    Kdf::Argon2
+}
+
+fn to_array<const N: usize>(value: &[u8]) -> Result<[u8; N], CryptographyError> {
+    value
+        .try_into()
+        .map_err(|_| CryptographyError::DecodingFailure)
+}
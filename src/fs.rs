@@ -0,0 +1,233 @@
+//! File helpers that never leave a half written file behind.
+
+use crate::{
+    decrypt, decrypt_value, encrypt_value, encrypt_with,
+    stream::{decrypt_stream, encrypt_stream, rekey_stream},
+    Argon2Params, CipherSuite, CryptographyError, EncryptOptions,
+};
+use aes_gcm_siv::aead::{rand_core::RngCore, OsRng};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+/// Function for re-encrypting a blob stored in a file under a new password, cipher suite & argon2 parameters.
+///
+/// The blob is re-encrypted with a fresh salt and nonce in memory, written to a temporary file next to `path`
+/// and renamed over the original, so the file always holds either the old or the new blob, never plaintext.
+/// On any failure the original file is left untouched. The plaintext is wiped from memory once re-encrypted.
+///
+/// Errors from the filesystem return an Io error.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt, fs::rekey_file, Argon2Params, CipherSuite};
+///
+/// let path = std::env::temp_dir().join("tinycrypt-doc-rekey-file");
+/// std::fs::write(&path, encrypt(b"Hello, world!", b"old password").unwrap()).unwrap();
+///
+/// rekey_file(&path, b"old password", b"new password", CipherSuite::Aes256GcmSiv, &Argon2Params::default())
+///     .expect("Failed to rekey!");
+///
+/// let decrypted_data = decrypt(&std::fs::read(&path).unwrap(), b"new password").expect("Failed to decrypt data!");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn rekey_file<P: AsRef<Path>>(
+    path: P,
+    old_password: &[u8],
+    new_password: &[u8],
+    new_suite: CipherSuite,
+    new_params: &Argon2Params,
+) -> Result<(), CryptographyError> {
+    let path = path.as_ref();
+
+    let blob = fs::read(path).map_err(|error| CryptographyError::Io(error.into()))?;
+    let plaintext = Zeroizing::new(decrypt(&blob, old_password)?);
+
+    let options = EncryptOptions::new().params(*new_params).suite(new_suite);
+    let reencrypted = encrypt_with(&plaintext, new_password, &options)?;
+    drop(plaintext);
+
+    atomic_write(path, &reencrypted).map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Function for saving any serializable value encrypted to the file at `path`, such as an app's config or state.
+///
+/// The file is replaced atomically like in [`rekey_file`], so a crash leaves either the old or the new vault.
+/// The value is encrypted with [`encrypt_value`](crate::encrypt_value). Errors from the filesystem return an Io error.
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use tinycrypt::fs::{load_vault, save_vault};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Config {
+///     api_token: String,
+///     retries: u32,
+/// }
+///
+/// let path = std::env::temp_dir().join("tinycrypt-doc-vault");
+/// let config = Config { api_token: "secret".into(), retries: 3 };
+///
+/// save_vault(&config, &path, b"password").expect("Failed to save vault!");
+/// let loaded: Config = load_vault(&path, b"password").expect("Failed to load vault!");
+///
+/// assert_eq!(loaded, config);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn save_vault<T: Serialize + ?Sized, P: AsRef<Path>>(
+    value: &T,
+    path: P,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let encrypted = encrypt_value(value, password)?;
+
+    atomic_write(path.as_ref(), &encrypted).map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Function for loading a value saved by [`save_vault`].
+/// Returns an Io error if the file can't be read, and a DecodingFailure if it doesn't hold a `T`.
+pub fn load_vault<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+    password: &[u8],
+) -> Result<T, CryptographyError> {
+    let encrypted = fs::read(path).map_err(|error| CryptographyError::Io(error.into()))?;
+
+    decrypt_value(&encrypted, password)
+}
+
+/// Function for encrypting the file at `src` into `dst` as a chunked stream, see [`stream`](crate::stream).
+///
+/// The file is encrypted a chunk at a time, so it never has to fit in memory. The output goes to a temporary file
+/// next to `dst` which is renamed over it once complete, so `dst` never holds a half written stream.
+/// `src` and `dst` may be the same path to encrypt a file in place. On any failure `dst` is left untouched.
+///
+/// Errors opening, creating or renaming files return an Io error, read and write errors while streaming
+/// return a DecodingFailure or EncodingFailure like [`encrypt_stream`].
+///
+/// ```rust
+/// use tinycrypt::fs::{decrypt_file, encrypt_file};
+///
+/// let path = std::env::temp_dir().join("tinycrypt-doc-encrypt-file");
+/// std::fs::write(&path, b"Hello, world!").unwrap();
+///
+/// encrypt_file(&path, &path, b"password").expect("Failed to encrypt file!");
+/// decrypt_file(&path, &path, b"password").expect("Failed to decrypt file!");
+///
+/// assert_eq!(std::fs::read(&path).unwrap(), b"Hello, world!");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn encrypt_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let source = File::open(src).map_err(|error| CryptographyError::Io(error.into()))?;
+
+    atomic_write_with(dst.as_ref(), |file| {
+        encrypt_stream(source, file, password).map_err(io::Error::other)
+    })
+    .map_err(into_cryptography_error)
+}
+
+/// Function for decrypting a file made by [`encrypt_file`] (or any chunked stream) at `src` into `dst`.
+///
+/// Only a stream that fully authenticated is renamed over `dst`, so a wrong password or a truncated file
+/// never leaves partial plaintext behind. Errors are returned like in [`encrypt_file`].
+pub fn decrypt_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let source = File::open(src).map_err(|error| CryptographyError::Io(error.into()))?;
+
+    atomic_write_with(dst.as_ref(), |file| {
+        decrypt_stream(source, file, password).map_err(io::Error::other)
+    })
+    .map_err(into_cryptography_error)
+}
+
+/// Function for changing the password of a file made by [`encrypt_file`] in place, like [`rekey_file`] does for blobs.
+///
+/// The stream is re-encrypted a chunk at a time with [`rekey_stream`] into a temporary file that's renamed over `path`,
+/// so the file always holds either the old or the new stream. Errors are returned like in [`encrypt_file`].
+pub fn rekey_stream_file<P: AsRef<Path>>(
+    path: P,
+    old_password: &[u8],
+    new_password: &[u8],
+) -> Result<(), CryptographyError> {
+    let path = path.as_ref();
+    let source = File::open(path).map_err(|error| CryptographyError::Io(error.into()))?;
+
+    atomic_write_with(path, |file| {
+        rekey_stream(source, file, old_password, new_password).map_err(io::Error::other)
+    })
+    .map_err(into_cryptography_error)
+}
+
+/// Unwraps a CryptographyError passed through an io::Error, any other error becomes an Io error.
+pub(crate) fn into_cryptography_error(error: io::Error) -> CryptographyError {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<CryptographyError>())
+    {
+        Some(inner) => inner.clone(),
+        None => CryptographyError::Io(error.into()),
+    }
+}
+
+/// Replaces the contents of `path` with `data` through a temporary file and a rename, keeping its permissions.
+pub(crate) fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
+    atomic_write_with(path, |file| file.write_all(data))
+}
+
+/// Like [`atomic_write`], with the temporary file's contents written by `write`.
+fn atomic_write_with<F: FnOnce(&mut File) -> io::Result<()>>(
+    path: &Path,
+    write: F,
+) -> io::Result<()> {
+    let temp = temp_path(path)?;
+
+    let result = write_new(&temp, write).and_then(|_| {
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+
+        fs::rename(&temp, path)
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+
+    // Make the rename itself durable, where the platform allows syncing a directory.
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+fn write_new<F: FnOnce(&mut File) -> io::Result<()>>(path: &Path, write: F) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+
+    write(&mut file)?;
+    file.sync_all()
+}
+
+/// Random hidden file name in the same directory as `path`, so the rename never crosses filesystems.
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or(io::ErrorKind::InvalidInput)?
+        .to_string_lossy();
+
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let suffix: String = suffix.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    Ok(path.with_file_name(format!(".{name}.{suffix}.tmp")))
+}
@@ -0,0 +1,354 @@
+//! Hybrid blobs that can be opened by password or by an X25519 private key.
+//!
+//! The data is encrypted under a random data key (DEK), which is wrapped once for each way of opening the blob:
+//!
+//! ```text
+//! magic           4 bytes   b"TCHY"
+//! version         1 byte    1
+//! flags           1 byte    bit 0 password slot present, bit 1 insecure test KDF
+//! password slot   92 bytes  if present: salt (32), nonce (12), wrapped DEK (48)
+//! recipient slot  92 bytes  ephemeral public key (32), nonce (12), wrapped DEK (48)
+//! nonce           12 bytes  nonce of the data
+//! ciphertext      everything after, ciphertext followed by the 16 byte tag
+//! ```
+//!
+//! The password slot's key is derived with argon2 like [`encrypt`](crate::encrypt) does. The recipient slot's key is
+//! HKDF-SHA256 over the X25519 shared secret of a fresh ephemeral key and the recipient's public key.
+//! Everything before the ciphertext is authenticated as associated data, so no slot can be swapped or stripped.
+//!
+//! The `TCHY` magic tells hybrid blobs apart from password blobs, and the flags byte says whether a password slot is
+//! present. [`encrypt_for`] and [`decrypt_with_secret`] are the key-only shorthands, working with a [`Keypair`].
+
+use crate::{
+    derive_key,
+    multi::{unwrap, wrap},
+    new_cipher, Argon2Params, CipherSuite, CryptographyError, Kdf,
+};
+use aes_gcm_siv::{
+    aead::{rand_core::RngCore, Aead, OsRng, Payload},
+    Nonce,
+};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt::{self, Debug};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::{Zeroize, Zeroizing};
+
+const MAGIC: &[u8; 4] = b"TCHY";
+const VERSION: u8 = 1;
+
+const FLAG_PASSWORD: u8 = 1;
+const FLAG_INSECURE_KDF: u8 = 1 << 1;
+
+const PREFIX_LEN: usize = MAGIC.len() + 1 + 1;
+const WRAPPED_LEN: usize = 32 + 16;
+const SLOT_LEN: usize = 32 + 12 + WRAPPED_LEN;
+
+const PASSWORD_CONTEXT: &[u8] = b"tinycrypt-v1-hybrid-password";
+const RECIPIENT_INFO: &[u8] = b"tinycrypt-v1-hybrid-x25519";
+
+/// Key a hybrid blob is opened with, see [`decrypt_hybrid`].
+#[derive(Clone, Copy)]
+pub enum HybridKey<'a> {
+    /// The password the blob was encrypted with.
+    Password(&'a [u8]),
+    /// The X25519 private key of the recipient the blob was encrypted to.
+    PrivateKey(&'a [u8; 32]),
+}
+
+/// X25519 public key of a recipient, safe to share and store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PublicKey([u8; 32]);
+
+impl PublicKey {
+    /// Returns the raw key bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for PublicKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+/// X25519 secret key of a recipient, wiped on drop. Its Debug output never shows the key.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretKey([u8; 32]);
+
+impl SecretKey {
+    /// Returns the raw key bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns the public key that belongs to this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(X25519PublicKey::from(&StaticSecret::from(self.0)).to_bytes())
+    }
+}
+
+impl From<[u8; 32]> for SecretKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// X25519 key pair to receive blobs made by [`encrypt_for`] with.
+#[derive(Debug, Clone)]
+pub struct Keypair {
+    /// Secret key, kept by the recipient.
+    pub secret: SecretKey,
+    /// Public key, given to anyone encrypting for the recipient.
+    pub public: PublicKey,
+}
+
+impl Keypair {
+    /// Generates a fresh key pair from the OS's randomness.
+    pub fn generate() -> Self {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = X25519PublicKey::from(&secret);
+
+        Self {
+            secret: SecretKey(secret.to_bytes()),
+            public: PublicKey(public.to_bytes()),
+        }
+    }
+}
+
+/// Function for encrypting data only the holder of `recipient`'s secret key can open, no password involved.
+/// Same as [`encrypt_hybrid`] without a password slot.
+///
+/// ```rust
+/// use tinycrypt::{decrypt_with_secret, encrypt_for, Keypair};
+///
+/// let keypair = Keypair::generate();
+///
+/// let encrypted_data = encrypt_for(&keypair.public, b"Hello, world!").expect("Failed to encrypt!");
+/// let decrypted_data = decrypt_with_secret(&keypair.secret, &encrypted_data).expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_for(recipient: &PublicKey, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    encrypt_hybrid(data, None, &recipient.0)
+}
+
+/// Function for decrypting a blob made by [`encrypt_for`] or [`encrypt_hybrid`] with the recipient's secret key.
+pub fn decrypt_with_secret(secret: &SecretKey, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    decrypt_hybrid(data, HybridKey::PrivateKey(&secret.0))
+}
+
+/// Function for encrypting data so it can be opened either with `password` or with the private key of `recipient_public_key`.
+/// Without a password only the recipient can open it.
+///
+/// ```rust
+/// use tinycrypt::{decrypt_hybrid, encrypt_hybrid, HybridKey, Keypair};
+///
+/// let keypair = Keypair::generate();
+///
+/// let encrypted_data = encrypt_hybrid(b"Hello, world!", Some(b"password"), keypair.public.as_bytes())
+///     .expect("Failed to encrypt!");
+///
+/// let by_password = decrypt_hybrid(&encrypted_data, HybridKey::Password(b"password")).expect("Failed to decrypt data!");
+/// let by_key = decrypt_hybrid(&encrypted_data, HybridKey::PrivateKey(keypair.secret.as_bytes()))
+///     .expect("Failed to decrypt data!");
+/// assert_eq!(by_password, by_key);
+/// ```
+pub fn encrypt_hybrid(
+    data: &[u8],
+    password: Option<&[u8]>,
+    recipient_public_key: &[u8; 32],
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut dek = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(dek.as_mut());
+
+    let kdf = Kdf::current();
+    let mut flags = 0;
+
+    if password.is_some() {
+        flags |= FLAG_PASSWORD;
+    }
+
+    if !kdf.is_argon2() {
+        flags |= FLAG_INSECURE_KDF;
+    }
+
+    let mut blob = Vec::with_capacity(PREFIX_LEN + 2 * SLOT_LEN + 12 + data.len() + 16);
+    blob.extend_from_slice(MAGIC);
+    blob.push(VERSION);
+    blob.push(flags);
+    let prefix: [u8; PREFIX_LEN] = to_array(&blob)?;
+
+    if let Some(password) = password {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+
+        let kek = password_key(password, &salt, kdf)?;
+
+        blob.extend_from_slice(&salt);
+        wrap(&mut blob, kek.as_ref(), &dek[..], &prefix)?;
+    }
+
+    let recipient = X25519PublicKey::from(*recipient_public_key);
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = X25519PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&recipient);
+
+    // A low order public key gives a shared secret anyone can compute.
+    if !shared.was_contributory() {
+        return Err(CryptographyError::KeyGenerationFailure);
+    }
+
+    let kek = recipient_key(
+        shared.as_bytes(),
+        ephemeral_public.as_bytes(),
+        recipient.as_bytes(),
+    )?;
+
+    blob.extend_from_slice(ephemeral_public.as_bytes());
+    wrap(&mut blob, kek.as_ref(), &dek[..], &prefix)?;
+
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    blob.extend_from_slice(&nonce);
+
+    let ciphertext = new_cipher(dek.as_ref())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: data,
+                aad: &blob,
+            },
+        )
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+    blob.extend_from_slice(&ciphertext);
+
+    Ok(blob)
+}
+
+/// Function for decrypting a blob made by [`encrypt_hybrid`] with either of its keys.
+/// Returns an IncorrectPassword if the key is wrong or the blob was modified,
+/// or a KeyNotFound when given a password for a blob encrypted without one.
+pub fn decrypt_hybrid(data: &[u8], key: HybridKey) -> Result<Vec<u8>, CryptographyError> {
+    let prefix: [u8; PREFIX_LEN] = to_array(
+        data.get(..PREFIX_LEN)
+            .ok_or(CryptographyError::DecodingFailure)?,
+    )?;
+    let flags = prefix[PREFIX_LEN - 1];
+
+    if !data.starts_with(MAGIC)
+        || prefix[MAGIC.len()] != VERSION
+        || flags & !(FLAG_PASSWORD | FLAG_INSECURE_KDF) != 0
+    {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let has_password = flags & FLAG_PASSWORD != 0;
+    let recipient_offset = PREFIX_LEN + if has_password { SLOT_LEN } else { 0 };
+    let header_len = recipient_offset + SLOT_LEN + 12;
+
+    if data.len() < header_len + 16 {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let dek = match key {
+        HybridKey::Password(password) => {
+            if !has_password {
+                return Err(CryptographyError::KeyNotFound);
+            }
+
+            let kdf = if flags & FLAG_INSECURE_KDF != 0 {
+                Kdf::InsecureSha256
+            } else {
+                Kdf::Argon2
+            };
+
+            let slot = &data[PREFIX_LEN..recipient_offset];
+            let kek = password_key(password, &to_array(&slot[..32])?, kdf)?;
+
+            unwrap(kek.as_ref(), &slot[32..], &prefix)?
+        }
+        HybridKey::PrivateKey(private_key) => {
+            let slot = &data[recipient_offset..recipient_offset + SLOT_LEN];
+            let ephemeral_public = X25519PublicKey::from(to_array::<32>(&slot[..32])?);
+
+            let secret = StaticSecret::from(*private_key);
+            let public = X25519PublicKey::from(&secret);
+            let shared = secret.diffie_hellman(&ephemeral_public);
+
+            if !shared.was_contributory() {
+                return Err(CryptographyError::IncorrectPassword);
+            }
+
+            let kek = recipient_key(
+                shared.as_bytes(),
+                ephemeral_public.as_bytes(),
+                public.as_bytes(),
+            )?;
+
+            unwrap(kek.as_ref(), &slot[32..], &prefix)?
+        }
+    };
+
+    new_cipher(dek.as_ref())
+        .decrypt(
+            Nonce::from_slice(&data[header_len - 12..header_len]),
+            Payload {
+                msg: &data[header_len..],
+                aad: &data[..header_len],
+            },
+        )
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
+
+fn password_key(
+    password: &[u8],
+    salt: &[u8; 32],
+    kdf: Kdf,
+) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+    derive_key(
+        password,
+        salt,
+        PASSWORD_CONTEXT,
+        kdf,
+        &Argon2Params::default(),
+        CipherSuite::Aes256GcmSiv,
+    )
+}
+
+/// HKDF-SHA256 over the shared secret, bound to both public keys.
+fn recipient_key(
+    shared: &[u8; 32],
+    ephemeral_public: &[u8; 32],
+    recipient_public: &[u8; 32],
+) -> Result<Zeroizing<[u8; 32]>, CryptographyError> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public);
+    salt[32..].copy_from_slice(recipient_public);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(RECIPIENT_INFO, key.as_mut())
+        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
+
+    Ok(key)
+}
+
+fn to_array<const N: usize>(value: &[u8]) -> Result<[u8; N], CryptographyError> {
+    value
+        .try_into()
+        .map_err(|_| CryptographyError::DecodingFailure)
+}
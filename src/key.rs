@@ -0,0 +1,601 @@
+//! Derived keys that can be reused across many operations.
+
+use crate::{
+    decrypt, derive_key,
+    envelope::{self, Binary},
+    format::{verify_checksum, write_checksum, VERSION},
+    is_legacy, new_cipher, open_with_cipher, random_nonce, seal_with_cipher, Argon2Params,
+    CipherSuite, Compression, CryptographyError, Envelope, Header, Kdf, DEFAULT_CONTEXT, TAG_SIZE,
+};
+use aes_gcm_siv::{
+    aead::{rand_core::RngCore, Aead, AeadInPlace, OsRng, Payload},
+    Aes256GcmSiv, Nonce, Tag,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use zeroize::{Zeroize, Zeroizing};
+
+/// First 4 bytes of every sequenced message's nonce, followed by the sequence number.
+const SEQ_NONCE_PREFIX: &[u8; 4] = b"TSEQ";
+
+/// Associated data of sequenced messages, followed by the sequence number.
+const SEQ_AAD: &[u8] = b"tinycrypt-v1-seq";
+
+/// Key derived from a password and salt, kept around to skip argon2 on later operations.
+/// The cipher is built once as well, so encrypting many small items under one key costs little more than the AEAD itself.
+/// The key schedule is wiped from memory when dropped.
+///
+/// Blobs encrypted with a derived key are regular blobs, so [`decrypt`](crate::decrypt) opens them with the password as well.
+///
+/// Random 96 bit nonces only stay safely unique for so many messages, so a key refuses to encrypt more than
+/// [`DEFAULT_MAX_ENCRYPTIONS`](Self::DEFAULT_MAX_ENCRYPTIONS) by default, returning KeyUsageExceeded.
+pub struct DerivedKey {
+    cipher: Aes256GcmSiv,
+    salt: [u8; 32],
+    encryptions: AtomicU64,
+    max_encryptions: u64,
+    epoch: Option<u32>,
+    params: Argon2Params,
+}
+
+impl DerivedKey {
+    /// Derives a key from `password` and `salt`, running argon2 once.
+    pub fn from_password(password: &[u8], salt: &[u8; 32]) -> Result<Self, CryptographyError> {
+        Self::from_password_with_params(password, salt, &Argon2Params::default())
+    }
+
+    /// Like [`from_password`](Self::from_password), with custom argon2 cost parameters.
+    /// They're stored in every blob, like with [`encrypt_with_params`](crate::encrypt_with_params).
+    /// Returns a KeyGenerationFailure if argon2 doesn't accept the parameters.
+    ///
+    /// ```rust
+    /// use tinycrypt::{decrypt, Argon2Params, DerivedKey};
+    ///
+    /// let params = Argon2Params { mem_cost: 64 * 1024, time_cost: 3, lanes: 1 };
+    /// let key = DerivedKey::from_password_with_params(b"password", &DerivedKey::random_salt(), &params).unwrap();
+    ///
+    /// let encrypted_data = key.encrypt(b"Hello, world!").unwrap();
+    /// assert_eq!(decrypt(&encrypted_data, b"password").unwrap(), b"Hello, world!");
+    /// ```
+    pub fn from_password_with_params(
+        password: &[u8],
+        salt: &[u8; 32],
+        params: &Argon2Params,
+    ) -> Result<Self, CryptographyError> {
+        params.validate()?;
+
+        let key = derive_key(
+            password,
+            salt,
+            DEFAULT_CONTEXT,
+            Kdf::current(),
+            params,
+            CipherSuite::Aes256GcmSiv,
+        )?;
+
+        Ok(Self::from_key(&key, salt, params))
+    }
+
+    /// A key already derived from `salt` with `params` and the default context.
+    pub(crate) fn from_key(key: &[u8], salt: &[u8; 32], params: &Argon2Params) -> Self {
+        Self {
+            cipher: new_cipher(key),
+            salt: *salt,
+            encryptions: AtomicU64::new(0),
+            max_encryptions: Self::DEFAULT_MAX_ENCRYPTIONS,
+            epoch: None,
+            params: *params,
+        }
+    }
+
+    /// Encryptions a key allows by default, 2^32 as recommended for random nonces by NIST SP 800-38D.
+    /// AES-GCM-SIV tolerates far more, so this is conservative.
+    pub const DEFAULT_MAX_ENCRYPTIONS: u64 = 1 << 32;
+
+    /// Sets how many encryptions this key allows before returning KeyUsageExceeded.
+    ///
+    /// ```rust
+    /// use tinycrypt::{CryptographyError, DerivedKey};
+    ///
+    /// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt())
+    ///     .unwrap()
+    ///     .with_max_encryptions(1);
+    ///
+    /// key.encrypt(b"Hello, world!").unwrap();
+    /// assert_eq!(key.encrypt(b"Hello again!"), Err(CryptographyError::KeyUsageExceeded));
+    /// ```
+    pub fn with_max_encryptions(mut self, max_encryptions: u64) -> Self {
+        self.max_encryptions = max_encryptions;
+        self
+    }
+
+    /// Stamps every blob encrypted with this key with a key rotation `epoch`, readable through [`Header::epoch`].
+    /// [`decrypt`](Self::decrypt) returns UnknownEpoch for blobs stamped with a different epoch,
+    /// so a blob from another rotation is told apart from a tampered one.
+    ///
+    /// ```rust
+    /// use tinycrypt::{CryptographyError, DerivedKey};
+    ///
+    /// let salt = DerivedKey::random_salt();
+    /// let march = DerivedKey::from_password(b"password", &salt).unwrap().with_epoch(3);
+    /// let april = DerivedKey::from_password(b"password", &salt).unwrap().with_epoch(4);
+    ///
+    /// let encrypted_data = march.encrypt(b"Hello, world!").unwrap();
+    /// assert_eq!(april.decrypt(&encrypted_data), Err(CryptographyError::UnknownEpoch(3)));
+    /// ```
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Number of blobs encrypted with this key so far.
+    pub fn encryptions(&self) -> u64 {
+        self.encryptions.load(Ordering::Relaxed)
+    }
+
+    /// Generates a fresh random salt for [`from_password`](Self::from_password).
+    pub fn random_salt() -> [u8; 32] {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+
+        salt
+    }
+
+    /// The salt this key was derived with.
+    pub fn salt(&self) -> &[u8; 32] {
+        &self.salt
+    }
+
+    /// Encrypts `data` under this key, producing the same blob [`encrypt`](crate::encrypt) would with the password and salt.
+    ///
+    /// ```rust
+    /// use tinycrypt::{decrypt, DerivedKey};
+    ///
+    /// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    ///
+    /// let encrypted_data: Vec<Vec<u8>> = (0..3).map(|i| key.encrypt(&[i]).unwrap()).collect();
+    ///
+    /// assert_eq!(decrypt(&encrypted_data[2], b"password").unwrap(), [2]);
+    /// assert_eq!(key.decrypt(&encrypted_data[1]).unwrap(), [1]);
+    /// ```
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        self.seal(data, random_nonce())
+    }
+
+    /// Encrypts `data` under this key with a caller supplied nonce, for protocols that manage nonces themselves.
+    /// `tracker` remembers every nonce used with this key and NonceReused is returned for a repeated one,
+    /// as reusing a nonce reveals when two messages are equal. Use one tracker per key for its whole lifetime.
+    ///
+    /// ```rust
+    /// use tinycrypt::{CryptographyError, DerivedKey, NonceTracker};
+    ///
+    /// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    /// let mut tracker = NonceTracker::new();
+    ///
+    /// let encrypted_data = key.encrypt_with_nonce(b"Hello, world!", &[1; 12], &mut tracker).unwrap();
+    ///
+    /// assert_eq!(
+    ///     key.encrypt_with_nonce(b"Hello again!", &[1; 12], &mut tracker),
+    ///     Err(CryptographyError::NonceReused)
+    /// );
+    /// ```
+    pub fn encrypt_with_nonce(
+        &self,
+        data: &[u8],
+        nonce: &[u8; 12],
+        tracker: &mut NonceTracker,
+    ) -> Result<Vec<u8>, CryptographyError> {
+        tracker.record(nonce)?;
+
+        self.seal(data, *nonce)
+    }
+
+    /// Encrypts `data` as message number `seq` of a sequence, with the nonce derived from `seq` instead of stored.
+    /// Returns just the ciphertext and its 16 byte tag, which [`decrypt_seq`](Self::decrypt_seq) opens with the same key and `seq`.
+    ///
+    /// `seq` must never repeat for a key: reusing one reveals whether two messages are equal.
+    /// These messages aren't regular blobs, whoever decrypts them needs the salt and the sequence number some other way.
+    ///
+    /// ```rust
+    /// use tinycrypt::{CryptographyError, DerivedKey};
+    ///
+    /// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    ///
+    /// let message = key.encrypt_seq(b"Hello, world!", 7).unwrap();
+    /// assert_eq!(message.len(), 13 + 16);
+    ///
+    /// assert_eq!(key.decrypt_seq(&message, 7).unwrap(), b"Hello, world!");
+    /// assert_eq!(key.decrypt_seq(&message, 8), Err(CryptographyError::IncorrectPassword));
+    /// ```
+    pub fn encrypt_seq(&self, data: &[u8], seq: u64) -> Result<Vec<u8>, CryptographyError> {
+        self.count_encryption()?;
+
+        self.cipher
+            .encrypt(
+                &seq_nonce(seq),
+                Payload {
+                    msg: data,
+                    aad: &seq_aad(seq),
+                },
+            )
+            .map_err(|_| CryptographyError::EncodingFailure)
+    }
+
+    /// Decrypts a message made by [`encrypt_seq`](Self::encrypt_seq), which must be message number `expected_seq`.
+    /// Returns IncorrectPassword if it was encrypted under another key or sequence number, or was modified,
+    /// so replayed and reordered messages are rejected like forged ones.
+    pub fn decrypt_seq(
+        &self,
+        data: &[u8],
+        expected_seq: u64,
+    ) -> Result<Vec<u8>, CryptographyError> {
+        self.cipher
+            .decrypt(
+                &seq_nonce(expected_seq),
+                Payload {
+                    msg: data,
+                    aad: &seq_aad(expected_seq),
+                },
+            )
+            .map_err(|_| CryptographyError::IncorrectPassword)
+    }
+
+    /// Counts an encryption, returning KeyUsageExceeded once the limit is reached.
+    fn count_encryption(&self) -> Result<(), CryptographyError> {
+        self.encryptions
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                (count < self.max_encryptions).then_some(count + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| CryptographyError::KeyUsageExceeded)
+    }
+
+    fn seal(&self, data: &[u8], nonce: [u8; 12]) -> Result<Vec<u8>, CryptographyError> {
+        self.count_encryption()?;
+
+        let header = self.header(nonce);
+        let ciphertext = seal_with_cipher(&self.cipher, &header, data, &[])?;
+
+        Binary.serialize(&header, &ciphertext)
+    }
+
+    /// Header of a blob encrypted under this key with `nonce`.
+    fn header(&self, nonce: [u8; 12]) -> Header {
+        let mut header = Header {
+            version: VERSION,
+            salt: self.salt,
+            nonce: nonce.to_vec(),
+            context: None,
+            created: None,
+            kdf: Kdf::current(),
+            params: self.params,
+            minor: 0,
+            codec: 0,
+            epoch: None,
+            verifier: None,
+            suite: CipherSuite::Aes256GcmSiv,
+            compression: Compression::None,
+            extra: Vec::new(),
+        };
+
+        if let Some(epoch) = self.epoch {
+            header.set_epoch(epoch);
+        }
+
+        header
+    }
+
+    /// Decrypts a blob, returning IncorrectPassword if it wasn't encrypted under this key.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        let envelope = envelope::detect(data).ok_or(CryptographyError::IncorrectPassword)?;
+        let (header, ciphertext) = envelope.deserialize(data)?;
+
+        if let Some(epoch) = header.epoch.filter(|&epoch| self.epoch != Some(epoch)) {
+            return Err(CryptographyError::UnknownEpoch(epoch));
+        }
+
+        // Different salt, context label, KDF or params means a different key, no need to try it.
+        // The key only ever encrypts with AES-256-GCM-SIV.
+        if header.salt != self.salt
+            || header.context.is_some()
+            || header.kdf != Kdf::current()
+            || header.params != self.params
+            || !header.suite.is_default()
+        {
+            return Err(CryptographyError::IncorrectPassword);
+        }
+
+        open_with_cipher(&self.cipher, &header, &ciphertext, &[])
+    }
+}
+
+/// Function for encrypting data under an already derived key, skipping argon2. Same as [`DerivedKey::encrypt`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_key, decrypt_with_key, DerivedKey};
+///
+/// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+///
+/// for record in [&b"first"[..], b"second", b"third"] {
+///     let encrypted_data = encrypt_with_key(record, &key).expect("Failed to encrypt!");
+///
+///     assert_eq!(decrypt_with_key(&encrypted_data, &key).unwrap(), record);
+/// }
+/// ```
+pub fn encrypt_with_key(data: &[u8], key: &DerivedKey) -> Result<Vec<u8>, CryptographyError> {
+    key.encrypt(data)
+}
+
+/// Function for decrypting data under an already derived key, skipping argon2. Same as [`DerivedKey::decrypt`].
+pub fn decrypt_with_key(data: &[u8], key: &DerivedKey) -> Result<Vec<u8>, CryptographyError> {
+    key.decrypt(data)
+}
+
+fn seq_nonce(seq: u64) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[..4].copy_from_slice(SEQ_NONCE_PREFIX);
+    nonce[4..].copy_from_slice(&seq.to_be_bytes());
+
+    nonce
+}
+
+fn seq_aad(seq: u64) -> Vec<u8> {
+    [SEQ_AAD, &seq.to_le_bytes()].concat()
+}
+
+/// Record of the nonces already used with one key, see [`DerivedKey::encrypt_with_nonce`].
+/// Only lives in memory, so persist the nonces yourself (or use a counter) if the key outlives the process.
+#[derive(Debug, Default)]
+pub struct NonceTracker {
+    used: HashSet<[u8; 12]>,
+}
+
+impl NonceTracker {
+    /// Creates a tracker with no nonces used yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `nonce` as used, returning NonceReused if it already was.
+    pub fn record(&mut self, nonce: &[u8; 12]) -> Result<(), CryptographyError> {
+        if self.used.insert(*nonce) {
+            Ok(())
+        } else {
+            Err(CryptographyError::NonceReused)
+        }
+    }
+
+    /// Returns true if `nonce` has been used.
+    pub fn contains(&self, nonce: &[u8; 12]) -> bool {
+        self.used.contains(nonce)
+    }
+}
+
+/// Named collection of derived keys for the length of a session, such as the unlocked sub-vaults of a password manager.
+/// Each key is derived once on insertion, and all of them are wiped from memory when the keyring is dropped.
+///
+/// ```rust
+/// use tinycrypt::{DerivedKey, Keyring};
+///
+/// let mut keyring = Keyring::new();
+/// keyring.insert("personal", b"password", &DerivedKey::random_salt()).unwrap();
+///
+/// let encrypted_data = keyring.encrypt("personal", b"Hello, world!").unwrap();
+///
+/// let decrypted_data = keyring.decrypt("personal", &encrypted_data).unwrap();
+/// ```
+#[derive(Default)]
+pub struct Keyring {
+    keys: HashMap<String, DerivedKey>,
+}
+
+impl Keyring {
+    /// Creates an empty keyring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives a key from `password` and `salt` and stores it as `name`, replacing any key already stored under it.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        password: &[u8],
+        salt: &[u8; 32],
+    ) -> Result<(), CryptographyError> {
+        let key = DerivedKey::from_password(password, salt)?;
+        self.insert_key(name, key);
+
+        Ok(())
+    }
+
+    /// Stores an already derived key as `name`, replacing any key already stored under it.
+    pub fn insert_key(&mut self, name: impl Into<String>, key: DerivedKey) {
+        self.keys.insert(name.into(), key);
+    }
+
+    /// Removes and wipes the key stored as `name`, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.keys.remove(name).is_some()
+    }
+
+    /// Returns true if a key is stored as `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.keys.contains_key(name)
+    }
+
+    /// Encrypts `data` with the key stored as `name`.
+    /// Returns KeyNotFound if there's no such key.
+    pub fn encrypt(&self, name: &str, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        self.get(name)?.encrypt(data)
+    }
+
+    /// Decrypts `data` with the key stored as `name`.
+    /// Returns KeyNotFound if there's no such key, or IncorrectPassword if the blob wasn't encrypted under it.
+    pub fn decrypt(&self, name: &str, data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        self.get(name)?.decrypt(data)
+    }
+
+    fn get(&self, name: &str) -> Result<&DerivedKey, CryptographyError> {
+        self.keys.get(name).ok_or(CryptographyError::KeyNotFound)
+    }
+}
+
+/// Most keys a [`CryptoContext`] caches before starting over.
+const MAX_CACHED_KEYS: usize = 64;
+
+/// Everything a blob's key is derived from, besides the password.
+pub(crate) type KeyId = ([u8; 32], Option<Vec<u8>>, Kdf, Argon2Params);
+
+pub(crate) fn key_id(header: &Header) -> KeyId {
+    (
+        header.salt,
+        header.context.clone(),
+        header.kdf,
+        header.params,
+    )
+}
+
+/// Reusable state for encrypting & decrypting many small blobs under one password, such as on a busy server.
+///
+/// Encryption uses a single [`DerivedKey`], and the keys of decrypted blobs are cached by salt & KDF settings,
+/// so argon2 only runs for salts not seen yet. [`encrypt_into`](Self::encrypt_into) & [`decrypt_into`](Self::decrypt_into)
+/// write into caller owned buffers, cleared on every call, so once they've grown a call with a cached key doesn't allocate
+/// at all (`cargo bench --bench context` counts them). Blobs are regular blobs, [`decrypt`] opens them with the password.
+///
+/// ```rust
+/// use tinycrypt::CryptoContext;
+///
+/// let mut context = CryptoContext::new(b"password").unwrap();
+/// let mut encrypted_data = Vec::new();
+/// let mut decrypted_data = Vec::new();
+///
+/// for message in [&b"first"[..], b"second", b"third"] {
+///     context.encrypt_into(message, &mut encrypted_data).unwrap();
+///     context.decrypt_into(&encrypted_data, &mut decrypted_data).unwrap();
+///
+///     assert_eq!(decrypted_data, message);
+/// }
+/// ```
+pub struct CryptoContext {
+    password: Zeroizing<Vec<u8>>,
+    key: DerivedKey,
+    keys: HashMap<KeyId, Aes256GcmSiv>,
+    /// Associated data of the blob being decrypted.
+    aad: Vec<u8>,
+}
+
+impl CryptoContext {
+    /// Creates a context for `password`, deriving its encryption key under a fresh random salt.
+    pub fn new(password: &[u8]) -> Result<Self, CryptographyError> {
+        let key = DerivedKey::from_password(password, &DerivedKey::random_salt())?;
+
+        let mut keys = HashMap::new();
+        keys.insert(key_id(&key.header([0u8; 12])), key.cipher.clone());
+
+        Ok(Self {
+            password: Zeroizing::new(password.to_vec()),
+            key,
+            keys,
+            aad: Vec::new(),
+        })
+    }
+
+    /// Encrypts `data` into `out`, replacing its contents with the blob [`DerivedKey::encrypt`] would return.
+    pub fn encrypt_into(
+        &mut self,
+        data: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), CryptographyError> {
+        self.key.count_encryption()?;
+
+        let header = self.key.header(random_nonce());
+        let header_len = header.encoded_len();
+
+        out.clear();
+        out.reserve(header_len + data.len() + TAG_SIZE);
+        out.resize(header_len, 0);
+        header.write(out)?;
+        out.extend_from_slice(data);
+
+        let (aad, body) = out.split_at_mut(header_len);
+        let tag = self
+            .key
+            .cipher
+            .encrypt_in_place_detached(Nonce::from_slice(&header.nonce), aad, body)
+            .map_err(|_| CryptographyError::EncodingFailure)?;
+
+        out.extend_from_slice(&tag);
+        write_checksum(out);
+
+        Ok(())
+    }
+
+    /// Decrypts `data` into `out`, replacing its contents with the plaintext. `out` is left empty on failure.
+    /// Returns the same errors as [`decrypt`].
+    pub fn decrypt_into(
+        &mut self,
+        data: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), CryptographyError> {
+        out.zeroize();
+
+        // Legacy & JSON blobs take the regular path.
+        if is_legacy(data) || !Header::is_versioned(data) {
+            out.extend_from_slice(&Zeroizing::new(decrypt(data, &self.password)?));
+
+            return Ok(());
+        }
+
+        let (header, header_len) = Header::parse(data)?;
+        verify_checksum(data)?;
+
+        // Only AES-256-GCM-SIV keys are cached, other suites & compressed blobs take the regular path too.
+        if !header.suite.is_default() || !header.compression.is_none() {
+            out.extend_from_slice(&Zeroizing::new(decrypt(data, &self.password)?));
+
+            return Ok(());
+        }
+
+        let ciphertext = &data[header_len..];
+        let body_len = ciphertext
+            .len()
+            .checked_sub(TAG_SIZE)
+            .ok_or(CryptographyError::IncorrectPassword)?;
+
+        let id = key_id(&header);
+        let cipher = match self.keys.get(&id) {
+            Some(cipher) => cipher,
+            None => {
+                let cipher = new_cipher(&header.derive_key(&self.password)?);
+
+                if self.keys.len() >= MAX_CACHED_KEYS {
+                    self.keys.clear();
+                }
+
+                self.keys.entry(id).or_insert(cipher)
+            }
+        };
+
+        self.aad.clear();
+        self.aad.resize(header.encoded_len(), 0);
+        header.write(&mut self.aad)?;
+
+        out.extend_from_slice(&ciphertext[..body_len]);
+
+        let decrypted = cipher.decrypt_in_place_detached(
+            Nonce::from_slice(&header.nonce),
+            &self.aad,
+            out,
+            Tag::from_slice(&ciphertext[body_len..]),
+        );
+
+        if decrypted.is_err() {
+            out.zeroize();
+            return Err(CryptographyError::IncorrectPassword);
+        }
+
+        Ok(())
+    }
+}
@@ -2,15 +2,19 @@
 //! A small & simple encryption library.
 //! 
 //! Exports two functions (encrypt & decrypt) along with an error type (CryptographyError) that implements std::error::Error.
+//! Without the default `std` feature the crate is `no_std` and only needs an allocator: blobs encrypt with
+//! [`encrypt_with_rng`] and decrypt with [`decrypt`] as usual, everything needing the OS or files is left out.
+//! The [`stream`] module provides incremental, chunked encryption for data that shouldn't be held in memory all at once,
+//! which [`encrypt_dir`] builds on to encrypt whole directory trees.
 //! 
 //! Basic usage:
 //! ```rust
-//! use tinycrypt::{Encrypt, Decrypt, CryptographyError};
+//! use tinycrypt::{encrypt, decrypt, CryptographyError};
 //! 
 //! let data = "Hello world!";
-//! let secure_password = "password";
+//! let password = "password";
 //! 
-//! let encrypted_data: Vec<u8> = encrypt(data.as_bytes(), secure_password.as_bytes()).unwrap();
+//! let encrypted_data: Vec<u8> = encrypt(data.as_bytes(), password.as_bytes()).unwrap();
 //! 
 //! println!("Data encrypted!");
 //! 
@@ -23,36 +27,209 @@
 //!     Err(error) => (), //do something with a different error
 //! }
 //! 
-//! println!("{}", String::from_utf8(&decrypted_data).unwrap());
+//! println!("{}", String::from_utf8(decrypted_data).unwrap());
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use aes_gcm_siv::aead::OsRng;
 use aes_gcm_siv::{
-    aead::{generic_array::GenericArray, rand_core::RngCore, Aead, OsRng},
+    aead::{
+        self,
+        generic_array::GenericArray,
+        rand_core::{CryptoRng, RngCore},
+        Aead, AeadInPlace, Payload,
+    },
     Aes256GcmSiv, KeyInit, Nonce,
 };
-use argon2::Config;
-use serde::{Deserialize, Serialize};
-use std::{error::Error, fmt::Display};
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "chacha20poly1305")]
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+use core::fmt::Display;
+use envelope::Binary;
+#[cfg(feature = "std")]
+use key::{key_id, KeyId};
+#[cfg(feature = "std")]
+use sign::add_verifier;
+#[cfg(feature = "std")]
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    io::Read,
+    time::{Duration, Instant, SystemTime},
+};
+use zeroize::Zeroizing;
+
+#[cfg(feature = "std")]
+mod archive;
+mod argon2id;
+#[cfg(feature = "armor")]
+mod armor;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod chunk;
+mod compression;
+#[cfg(feature = "std")]
+mod derivation;
+pub mod envelope;
+#[cfg(feature = "std")]
+mod error;
+mod format;
+#[cfg(feature = "std")]
+pub mod fs;
+#[cfg(feature = "hybrid")]
+mod hybrid;
+#[cfg(feature = "std")]
+mod key;
+#[cfg(feature = "std")]
+mod manifest;
+#[cfg(feature = "std")]
+mod multi;
+mod params;
+#[cfg(feature = "std")]
+mod password;
+#[cfg(feature = "std")]
+mod provider;
+mod random;
+#[cfg(feature = "std")]
+pub mod raw;
+#[cfg(feature = "std")]
+mod rollback;
+#[cfg(feature = "std")]
+mod shard;
+#[cfg(feature = "std")]
+mod sign;
+#[cfg(feature = "std")]
+mod split;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+mod trailer;
+#[cfg(feature = "std")]
+mod value;
+
+#[cfg(feature = "std")]
+pub use archive::{decrypt_dir, encrypt_dir};
+#[cfg(feature = "armor")]
+pub use armor::{armor, dearmor, decrypt_armored, encrypt_armored};
+#[cfg(feature = "std")]
+pub use builder::{
+    decrypt_builder, decrypt_with_aad, encrypt_builder, encrypt_with, encrypt_with_aad,
+    DecryptBuilder, EncryptBuilder, EncryptOptions,
+};
+#[cfg(feature = "std")]
+pub use chunk::{chunk_decrypt, chunk_encrypt};
+pub use compression::{Compression, DEFAULT_MAX_DECOMPRESSED_BYTES};
+#[cfg(feature = "std")]
+pub use derivation::{decrypt_with_progress, encrypt_with_progress, KeyDerivation};
+pub use envelope::Envelope;
+#[cfg(feature = "std")]
+pub use error::{BincodeError, IoError};
+pub use format::{probe, Header, ProbeResult, MAX_CONTEXT_LEN};
+#[cfg(feature = "hybrid")]
+pub use hybrid::{
+    decrypt_hybrid, decrypt_with_secret, encrypt_for, encrypt_hybrid, HybridKey, Keypair,
+    PublicKey, SecretKey,
+};
+#[cfg(feature = "std")]
+pub use key::{
+    decrypt_with_key, encrypt_with_key, CryptoContext, DerivedKey, Keyring, NonceTracker,
+};
+#[cfg(feature = "std")]
+pub use manifest::{build_manifest, verify_manifest};
+#[cfg(feature = "std")]
+pub use multi::{add_password, decrypt_multi, encrypt_multi, remove_password};
+pub use params::{Argon2Params, Argon2Policy, KdfPreset, DEFAULT_MAX_MEMORY_BYTES};
+#[cfg(feature = "std")]
+pub use password::{generate_password, Charset, Password};
+#[cfg(feature = "std")]
+pub use provider::{decrypt_with_provider, encrypt_with_provider, KeyProvider};
+#[cfg(feature = "std")]
+pub use random::OsRandomness;
+pub use random::Randomness;
+#[cfg(feature = "std")]
+pub use rollback::{blob_version, decrypt_min_version, encrypt_versioned};
+#[cfg(feature = "std")]
+pub use shard::{combine_blob, split_blob};
+#[cfg(feature = "std")]
+pub use sign::{sign_detached, verify_detached, verify_header, SIGNATURE_LEN};
+#[cfg(feature = "std")]
+pub use split::{decrypt_parts, encrypt_split};
+#[cfg(feature = "std")]
+pub use trailer::{decrypt_trailer, encrypt_trailer};
+#[cfg(feature = "std")]
+pub use value::{decrypt_value, decrypt_value_bounded, encrypt_value, encrypt_value_with, Codec};
 
 /// Error type for library, handles bincode encoding/decoding errors and key generation errors.
 /// Also provides a unique error for incorrect passwords.
 /// 
 /// Implements Debug, Display, Error, PartialEq, and Clone
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum CryptographyError {
     DecodingFailure,
     EncodingFailure,
     KeyGenerationFailure,
     IncorrectPassword,
+    BufferTooSmall,
+    Cancelled,
+    KeyNotFound,
+    NonceReused,
+    ManifestMismatch,
+    /// Deriving the key needs more memory than allowed, carries the argon2 parameters from the blob's header.
+    InsufficientMemory(Argon2Params),
+    /// A filesystem operation failed, carries the io::Error, also returned by [`source`](Error::source).
+    #[cfg(feature = "std")]
+    Io(IoError),
+    /// The input is larger than the caller allowed.
+    InputTooLarge,
+    /// The blob's header names a key derivation function this version doesn't implement, carries its id.
+    UnsupportedKdf(u8),
+    /// A raw key's length doesn't match the cipher suite it's used with, carries the length given.
+    InvalidKeyLength(usize),
+    /// A derived key reached its encryption limit, derive a new one with a fresh salt.
+    KeyUsageExceeded,
+    /// The blob's key was derived with weaker argon2 parameters than the policy requires, or without argon2.
+    PolicyViolation,
+    /// No key is known for the key rotation epoch the blob was stamped with, carries the epoch.
+    UnknownEpoch(u32),
+    /// A stream ended without its final chunk, so data is missing from its end.
+    Truncated,
+    /// The blob's version is older than the minimum allowed, so it may have been rolled back.
+    RollbackDetected,
+    /// The blob was written in a newer format version than this one understands, carries the version.
+    UnsupportedVersion(u8),
+    /// Argon2 failed to derive the key, carries its error, also returned by [`source`](Error::source).
+    #[cfg(feature = "std")]
+    Argon2(argon2::Error),
+    /// bincode failed to serialize or deserialize a value, carries its error, also returned by [`source`](Error::source).
+    #[cfg(feature = "std")]
+    Bincode(BincodeError),
+    /// Armored text is missing its markers, isn't base64 or fails its checksum.
+    InvalidArmor,
+    /// The blob's header names a cipher suite this build doesn't implement, carries its id.
+    /// The ChaCha20-Poly1305 suites need the `chacha20poly1305` feature.
+    UnsupportedCipherSuite(u8),
+    /// The blob's header names a compression this build doesn't implement, carries its id.
+    /// Each compression needs the cargo feature of the same name.
+    UnsupportedCompression(u8),
+    /// The blob's header has a field of a newer minor version that changes how it's decrypted, carries its tag.
+    /// Unlike other newer fields it can't be ignored, not even by [`decrypt_lenient`].
+    UnsupportedField(u8),
 }
 
 impl Display for CryptographyError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         writeln!(f, "{:?}", self)
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for CryptographyError {
     fn description(&self) -> &str {
         match self {
@@ -60,62 +237,772 @@ impl Error for CryptographyError {
             Self::DecodingFailure => "Data not valid",
             Self::KeyGenerationFailure => "Failed to create key from password",
             Self::IncorrectPassword => "Given password was incorrect",
+            Self::BufferTooSmall => "Output buffer too small",
+            Self::Cancelled => "Operation was cancelled",
+            Self::KeyNotFound => "No key with the given name",
+            Self::NonceReused => "Nonce was already used with this key",
+            Self::ManifestMismatch => "Blobs don't match the manifest",
+            Self::InsufficientMemory(_) => "Not enough memory allowed to derive the key",
+            Self::Io(_) => "Filesystem operation failed",
+            Self::InputTooLarge => "Input larger than allowed",
+            Self::UnsupportedKdf(_) => "Key derivation function not supported by this version",
+            Self::InvalidKeyLength(_) => "Key length doesn't match the cipher",
+            Self::KeyUsageExceeded => "Key encrypted as many messages as allowed",
+            Self::PolicyViolation => "Key derivation parameters weaker than the policy allows",
+            Self::UnknownEpoch(_) => "No key for the blob's key rotation epoch",
+            Self::Truncated => "Stream ended before its final chunk",
+            Self::RollbackDetected => "Blob is older than the minimum version",
+            Self::UnsupportedVersion(_) => "Blob format version not supported by this version",
+            Self::Argon2(_) => "Argon2 failed to derive the key",
+            Self::Bincode(_) => "Failed to serialize or deserialize the value",
+            Self::InvalidArmor => "Armored text not valid",
+            Self::UnsupportedCipherSuite(_) => "Cipher suite not supported by this build",
+            Self::UnsupportedCompression(_) => "Compression not supported by this build",
+            Self::UnsupportedField(_) => "Header field needed to decrypt not known to this version",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Argon2(error) => Some(error),
+            Self::Io(error) => Some(error.get_ref()),
+            Self::Bincode(error) => Some(error.get_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// KDF context label mixed into every key derivation by default.
+/// Keys derived for tinycrypt encryption can never coincide with keys derived from the same password for another purpose.
+pub const DEFAULT_CONTEXT: &[u8] = b"tinycrypt-v1-aead";
+
+/// AEAD a blob is encrypted with, chosen with [`EncryptOptions::suite`] and stored in the blob's header.
+/// The ChaCha20-Poly1305 suites need the `chacha20poly1305` feature, they're faster on platforms without AES instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CipherSuite {
+    /// AES-256-GCM-SIV, nonce misuse resistant. The default, and the only suite of blobs without a suite field.
+    #[default]
+    Aes256GcmSiv,
+    /// ChaCha20-Poly1305 (RFC 8439) with a 96 bit nonce.
+    #[cfg(feature = "chacha20poly1305")]
+    ChaCha20Poly1305,
+    /// XChaCha20-Poly1305 with a 192 bit nonce.
+    #[cfg(feature = "chacha20poly1305")]
+    XChaCha20Poly1305,
+}
+
+impl CipherSuite {
+    /// Every supported cipher suite.
+    #[cfg(not(feature = "chacha20poly1305"))]
+    pub const ALL: &'static [Self] = &[Self::Aes256GcmSiv];
+
+    /// Every supported cipher suite.
+    #[cfg(feature = "chacha20poly1305")]
+    pub const ALL: &'static [Self] = &[
+        Self::Aes256GcmSiv,
+        Self::ChaCha20Poly1305,
+        Self::XChaCha20Poly1305,
+    ];
+
+    /// Key size of the cipher in bytes, the length every key for it is derived with.
+    ///
+    /// ```rust
+    /// use tinycrypt::CipherSuite;
+    ///
+    /// assert_eq!(CipherSuite::Aes256GcmSiv.key_len(), 32);
+    /// ```
+    pub const fn key_len(self) -> usize {
+        match self {
+            Self::Aes256GcmSiv => 32,
+            #[cfg(feature = "chacha20poly1305")]
+            Self::ChaCha20Poly1305 | Self::XChaCha20Poly1305 => 32,
+        }
+    }
+
+    /// Nonce size of the cipher in bytes, the length of the nonce stored in every blob's header.
+    ///
+    /// ```rust
+    /// use tinycrypt::CipherSuite;
+    ///
+    /// assert_eq!(CipherSuite::Aes256GcmSiv.nonce_len(), 12);
+    /// ```
+    pub const fn nonce_len(self) -> usize {
+        match self {
+            Self::Aes256GcmSiv => 12,
+            #[cfg(feature = "chacha20poly1305")]
+            Self::ChaCha20Poly1305 => 12,
+            #[cfg(feature = "chacha20poly1305")]
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// Id of the suite in a header's suite field, AES-256-GCM-SIV's id 0 is never written out.
+    pub(crate) const fn id(self) -> u8 {
+        match self {
+            Self::Aes256GcmSiv => 0,
+            #[cfg(feature = "chacha20poly1305")]
+            Self::ChaCha20Poly1305 => 1,
+            #[cfg(feature = "chacha20poly1305")]
+            Self::XChaCha20Poly1305 => 2,
+        }
+    }
+
+    /// The suite with id `id`, UnsupportedCipherSuite if this build doesn't implement it.
+    pub(crate) fn from_id(id: u8) -> Result<Self, CryptographyError> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|suite| suite.id() == id)
+            .ok_or(CryptographyError::UnsupportedCipherSuite(id))
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::Aes256GcmSiv
+    }
+
+    /// Checks a raw key of `len` bytes fits this suite.
+    #[cfg(feature = "std")]
+    pub(crate) fn check_key_len(self, len: usize) -> Result<(), CryptographyError> {
+        if len == self.key_len() {
+            Ok(())
+        } else {
+            Err(CryptographyError::InvalidKeyLength(len))
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct EncryptedFile {
-    data: Vec<u8>,
+/// Key derivation function a key is derived with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum Kdf {
+    Argon2,
+    /// Plain SHA-256 from the `insecure-fast-kdf` feature, only ever derived in builds with the feature enabled.
+    InsecureSha256,
+    /// Key supplied by a [`KeyProvider`] rather than derived from a password.
+    Provider,
+}
+
+impl Kdf {
+    /// The KDF new keys are derived with in this build.
+    pub(crate) const fn current() -> Self {
+        if cfg!(feature = "insecure-fast-kdf") {
+            Self::InsecureSha256
+        } else {
+            Self::Argon2
+        }
+    }
+
+    pub(crate) fn is_argon2(&self) -> bool {
+        *self == Self::Argon2
+    }
+}
+
+/// Derives a 256 bit key from a password and salt using argon2, with `context` as argon2's associated data.
+/// Keys for the insecure test KDF are refused unless the `insecure-fast-kdf` feature is enabled.
+/// The key is wiped from memory when dropped.
+pub(crate) fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    context: &[u8],
+    kdf: Kdf,
+    params: &Argon2Params,
+    suite: CipherSuite,
+) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+    let key = Zeroizing::new(if kdf == Kdf::Provider {
+        // Only the provider holds the key, no password can unlock the blob.
+        return Err(CryptographyError::KeyNotFound);
+    } else if kdf == Kdf::InsecureSha256 {
+        insecure_fast_kdf(password, salt, context)?
+    } else {
+        hash_argon2(password, salt, context, params, suite.key_len())?
+    });
+
+    // A key of the wrong size would panic when building the cipher.
+    if key.len() != suite.key_len() {
+        return Err(CryptographyError::KeyGenerationFailure);
+    }
+
+    Ok(key)
+}
+
+#[cfg(feature = "std")]
+fn hash_argon2(
+    password: &[u8],
+    salt: &[u8],
+    context: &[u8],
+    params: &Argon2Params,
+    key_len: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    argon2::hash_raw(password, salt, &params.config(context, key_len))
+        .map_err(CryptographyError::Argon2)
+}
+
+/// Without std the argon2 backend isn't available, the same key is derived by [`argon2id`] instead.
+#[cfg(not(feature = "std"))]
+fn hash_argon2(
+    password: &[u8],
+    salt: &[u8],
+    context: &[u8],
+    params: &Argon2Params,
+    key_len: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut key = argon2id::Argon2id::new(password, salt, context, params, key_len)?.finish();
+
+    Ok(core::mem::take(&mut *key))
+}
+
+/// INSECURE: a single SHA-256 makes brute forcing the password trivial. Only exists to speed up test suites.
+#[cfg(feature = "insecure-fast-kdf")]
+fn insecure_fast_kdf(
+    password: &[u8],
+    salt: &[u8],
+    context: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"tinycrypt-insecure-fast-kdf");
+    hasher.update((context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.update(salt);
+    hasher.update(password);
+
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Builds without the feature refuse blobs made with the insecure KDF instead of deriving a key for them.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+fn insecure_fast_kdf(
+    _password: &[u8],
+    _salt: &[u8],
+    _context: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    Err(CryptographyError::DecodingFailure)
+}
+
+pub(crate) const TAG_SIZE: usize = 16;
+
+/// Layout used before the versioned header was introduced, still accepted by [`decrypt`].
+/// Keys for these blobs were derived without a context label.
+struct EncryptedFile<'a> {
+    data: &'a [u8],
     nonce: [u8; 12],
     salt: [u8; 32],
 }
 
+impl<'a> EncryptedFile<'a> {
+    /// Reads the layout bincode wrote: the data's length as a u64 LE, the data, the nonce & the salt.
+    /// Like bincode, anything after the salt is ignored.
+    fn decode(blob: &'a [u8]) -> Result<Self, CryptographyError> {
+        let (len, rest) = blob
+            .split_first_chunk::<8>()
+            .ok_or(CryptographyError::DecodingFailure)?;
+        let len = usize::try_from(u64::from_le_bytes(*len))
+            .map_err(|_| CryptographyError::DecodingFailure)?;
+
+        let (data, rest) = rest
+            .split_at_checked(len)
+            .ok_or(CryptographyError::DecodingFailure)?;
+        let (nonce, rest) = rest
+            .split_first_chunk::<12>()
+            .ok_or(CryptographyError::DecodingFailure)?;
+        let (salt, _) = rest
+            .split_first_chunk::<32>()
+            .ok_or(CryptographyError::DecodingFailure)?;
+
+        Ok(Self {
+            data,
+            nonce: *nonce,
+            salt: *salt,
+        })
+    }
+
+    fn derive_key(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+        derive_key(
+            password,
+            &self.salt,
+            b"",
+            Kdf::Argon2,
+            &Argon2Params::default(),
+            CipherSuite::Aes256GcmSiv,
+        )
+    }
+
+    fn open(&self, key: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        new_cipher(key)
+            .decrypt(Nonce::from_slice(&self.nonce), self.data)
+            .map_err(|_| CryptographyError::IncorrectPassword)
+    }
+}
+
+/// Creates a header with a fresh random salt and nonce.
+#[cfg(feature = "std")]
+pub(crate) fn random_header(context: &[u8]) -> Result<Header, CryptographyError> {
+    random_suite_header(context, CipherSuite::Aes256GcmSiv)
+}
+
+/// Like [`random_header`], for a blob encrypted with `suite`.
+#[cfg(feature = "std")]
+pub(crate) fn random_suite_header(
+    context: &[u8],
+    suite: CipherSuite,
+) -> Result<Header, CryptographyError> {
+    header_with_randomness(context, suite, &mut OsRandomness)
+}
+
+/// Creates a header with a salt and nonce from `randomness`.
+fn header_with_randomness<R: Randomness + ?Sized>(
+    context: &[u8],
+    suite: CipherSuite,
+    randomness: &mut R,
+) -> Result<Header, CryptographyError> {
+    if context.len() > MAX_CONTEXT_LEN {
+        return Err(CryptographyError::EncodingFailure);
+    }
+
+    let mut salt = [0u8; 32];
+    randomness.fill_salt(&mut salt);
+
+    let mut nonce = vec![0u8; suite.nonce_len()];
+    randomness.fill_nonce(&mut nonce);
+
+    let mut header = Header {
+        version: format::VERSION,
+        salt,
+        nonce,
+        context: (context != DEFAULT_CONTEXT).then(|| context.to_vec()),
+        created: None,
+        kdf: Kdf::current(),
+        params: Argon2Params::default(),
+        minor: 0,
+        codec: 0,
+        epoch: None,
+        verifier: None,
+        suite: CipherSuite::Aes256GcmSiv,
+        compression: Compression::None,
+        extra: Vec::new(),
+    };
+    header.set_suite(suite);
+
+    Ok(header)
+}
+
+#[cfg(feature = "std")]
+fn random_nonce() -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    nonce
+}
+
+#[cfg(feature = "std")]
 /// Function for encrypting data.
 /// Takes any data and password input as a slice (&\[T\]) of u8 (bytes) and returns a Result wrapping a vector of u8.
 /// 
 /// ```rust
+/// use tinycrypt::encrypt;
+///
 /// let data = "Hello, world!";
 /// let password = "password";
 /// 
 /// let encrypted_data: Vec<u8> = encrypt(data.as_bytes(), password.as_bytes()).expect("Failed to encrypt!");
 /// ```
 pub fn encrypt(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
-    let mut salt = [0u8; 32];
-    OsRng.fill_bytes(&mut salt);
+    encrypt_with_context(data, password, DEFAULT_CONTEXT)
+}
 
-    let config = Config {
-        hash_length: 32,
-        ..Default::default()
-    };
+#[cfg(feature = "std")]
+/// Function for encrypting data with a custom KDF context label instead of [`DEFAULT_CONTEXT`].
+/// Lets an application domain-separate its keys from other applications using the same password.
+/// Custom labels (up to [`MAX_CONTEXT_LEN`] bytes) are stored in the blob, so [`decrypt`] needs nothing extra.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_context, decrypt};
+///
+/// let encrypted_data = encrypt_with_context(b"Hello, world!", b"password", b"my-app-v1").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_context(
+    data: &[u8],
+    password: &[u8],
+    context: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = random_header(context)?;
+    let ciphertext = seal(&header, data, password)?;
 
-    let password = argon2::hash_raw(password, &salt, &config)
-        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
-    let key = GenericArray::from_slice(&password);
-    let cipher = Aes256GcmSiv::new(key);
+    Binary.serialize(&header, &ciphertext)
+}
 
-    let mut nonce_rand = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_rand);
+/// Function for encrypting data with the salt & nonce taken from `randomness` instead of the operating system's RNG.
+/// Meant for reproducible test vectors, see [`Randomness`] for why fixed values must never be used for real data.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_randomness, decrypt, Randomness};
+///
+/// struct FixedSalt;
+///
+/// impl Randomness for FixedSalt {
+///     fn fill_salt(&mut self, buf: &mut [u8]) {
+///         buf.fill(7);
+///     }
+/// }
+///
+/// let encrypted_data = encrypt_with_randomness(b"Hello, world!", b"password", &mut FixedSalt).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_randomness<R: Randomness + ?Sized>(
+    data: &[u8],
+    password: &[u8],
+    randomness: &mut R,
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = header_with_randomness(DEFAULT_CONTEXT, CipherSuite::Aes256GcmSiv, randomness)?;
+    let ciphertext = seal(&header, data, password)?;
 
-    let nonce = Nonce::from_slice(&nonce_rand);
-    let ciphertext = cipher
-        .encrypt(nonce, data.as_ref())
-        .map_err(|_| CryptographyError::IncorrectPassword)?;
+    Binary.serialize(&header, &ciphertext)
+}
 
-    let file = EncryptedFile {
-        data: ciphertext,
-        nonce: nonce_rand,
-        salt,
+/// Function for encrypting data with the salt & nonce drawn from `rng` instead of the operating system's RNG,
+/// for targets without one or apps that already manage a seeded CSPRNG.
+///
+/// ```rust
+/// use aes_gcm_siv::aead::OsRng;
+/// use tinycrypt::{encrypt_with_rng, decrypt};
+///
+/// let encrypted_data = encrypt_with_rng(b"Hello, world!", b"password", &mut OsRng).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_rng<R: RngCore + CryptoRng>(
+    data: &[u8],
+    password: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptographyError> {
+    struct FromRng<'a, R>(&'a mut R);
+
+    impl<R: RngCore + CryptoRng> Randomness for FromRng<'_, R> {
+        fn fill_salt(&mut self, buf: &mut [u8]) {
+            self.0.fill_bytes(buf);
+        }
+
+        fn fill_nonce(&mut self, buf: &mut [u8]) {
+            self.0.fill_bytes(buf);
+        }
+    }
+
+    encrypt_with_randomness(data, password, &mut FromRng(rng))
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data with a caller provided salt instead of a random one, so the derived key is reproducible.
+/// The salt is stored in the blob as usual and the nonce stays random, so [`decrypt`] works unchanged.
+///
+/// **Only for test vectors and protocols that mandate the salt.** Every blob sharing a salt & password shares a key,
+/// so a single argon2 run cracks all of them at once instead of each needing its own.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_salt, decrypt};
+///
+/// let encrypted_data = encrypt_with_salt(b"Hello, world!", b"password", &[7u8; 32]).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_salt(
+    data: &[u8],
+    password: &[u8],
+    salt: &[u8; 32],
+) -> Result<Vec<u8>, CryptographyError> {
+    struct FixedSalt<'a>(&'a [u8; 32]);
+
+    impl Randomness for FixedSalt<'_> {
+        fn fill_salt(&mut self, buf: &mut [u8]) {
+            buf.copy_from_slice(self.0);
+        }
+    }
+
+    encrypt_with_randomness(data, password, &mut FixedSalt(salt))
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data with custom argon2 cost parameters instead of the defaults.
+/// The parameters are stored in the blob, so [`decrypt`] needs nothing extra.
+/// Returns a KeyGenerationFailure if argon2 doesn't accept the parameters.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_params, decrypt, Argon2Params};
+///
+/// let params = Argon2Params { mem_cost: 64 * 1024, time_cost: 3, lanes: 1 };
+/// assert_eq!(params.estimated_memory_bytes(), 64 * 1024 * 1024);
+///
+/// let encrypted_data = encrypt_with_params(b"Hello, world!", b"password", &params).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_params(
+    data: &[u8],
+    password: &[u8],
+    params: &Argon2Params,
+) -> Result<Vec<u8>, CryptographyError> {
+    params.validate()?;
+
+    let mut header = random_header(DEFAULT_CONTEXT)?;
+    header.params = *params;
+
+    let ciphertext = seal(&header, data, password)?;
+
+    Binary.serialize(&header, &ciphertext)
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data into a specific [`Envelope`] format, such as [`envelope::Json`].
+/// The built-in envelopes are detected automatically by [`decrypt`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_envelope, decrypt, envelope::Binary};
+///
+/// let encrypted_data = encrypt_with_envelope(b"Hello, world!", b"password", &Binary).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_envelope<E: Envelope>(
+    data: &[u8],
+    password: &[u8],
+    envelope: &E,
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = random_header(DEFAULT_CONTEXT)?;
+    let ciphertext = seal(&header, data, password)?;
+
+    envelope.serialize(&header, &ciphertext)
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data with the current time recorded in the authenticated header.
+/// The timestamp can be read back with [`creation_time`] without the password, and [`decrypt`] fails if it was altered.
+/// It only records when the blob was made, nothing is enforced when decrypting.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_timestamped, creation_time, decrypt};
+///
+/// let encrypted_data = encrypt_timestamped(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let created = creation_time(&encrypted_data).expect("Blob has no timestamp!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_timestamped(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let created = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    let mut header = random_header(DEFAULT_CONTEXT)?;
+    header.created = Some(created.as_secs());
+
+    let ciphertext = seal(&header, data, password)?;
+
+    Binary.serialize(&header, &ciphertext)
+}
+
+#[cfg(feature = "std")]
+/// Function for reading the creation time of a blob made by [`encrypt_timestamped`], no password needed.
+/// Returns a DecodingFailure if the blob has no timestamp or isn't valid.
+/// The timestamp is only proven authentic once the blob has been decrypted.
+pub fn creation_time(data: &[u8]) -> Result<SystemTime, CryptographyError> {
+    if is_legacy(data) {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(data)?;
+
+    header.created()?.ok_or(CryptographyError::DecodingFailure)
+}
+
+/// Encrypts `data` under `header`, authenticating the encoded header as associated data.
+pub(crate) fn seal(
+    header: &Header,
+    data: &[u8],
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    // The caller chose the parameters, only decryption is limited.
+    let password = header.derive_key_within(password, usize::MAX)?;
+
+    seal_with_key(&password, header, data, &[])
+}
+
+/// Decrypts a ciphertext sealed by [`seal`] under `header`.
+pub(crate) fn open(
+    header: &Header,
+    ciphertext: &[u8],
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    open_within(header, ciphertext, password, DEFAULT_MAX_MEMORY_BYTES)
+}
+
+/// Like [`open`], with argon2 limited to `max_memory_bytes`.
+fn open_within(
+    header: &Header,
+    ciphertext: &[u8],
+    password: &[u8],
+    max_memory_bytes: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let password = header.derive_key_within(password, max_memory_bytes)?;
+
+    open_with_key(&password, header, ciphertext, &[])
+}
+
+/// Builds the AES-256-GCM-SIV cipher for a derived key.
+pub(crate) fn new_cipher(key: &[u8]) -> Aes256GcmSiv {
+    Aes256GcmSiv::new(GenericArray::from_slice(key))
+}
+
+/// Like [`seal`], with an already derived key, encrypting with the header's cipher suite.
+pub(crate) fn seal_with_key(
+    key: &[u8],
+    header: &Header,
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let key = GenericArray::from_slice(key);
+
+    match header.suite {
+        CipherSuite::Aes256GcmSiv => seal_with_cipher(&Aes256GcmSiv::new(key), header, data, aad),
+        #[cfg(feature = "chacha20poly1305")]
+        CipherSuite::ChaCha20Poly1305 => {
+            seal_with_cipher(&ChaCha20Poly1305::new(key), header, data, aad)
+        }
+        #[cfg(feature = "chacha20poly1305")]
+        CipherSuite::XChaCha20Poly1305 => {
+            seal_with_cipher(&XChaCha20Poly1305::new(key), header, data, aad)
+        }
+    }
+}
+
+/// Like [`open`], with an already derived key, decrypting with the header's cipher suite.
+pub(crate) fn open_with_key(
+    key: &[u8],
+    header: &Header,
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    open_with_key_within(key, header, ciphertext, aad, DEFAULT_MAX_DECOMPRESSED_BYTES)
+}
+
+/// Like [`open_with_key`], decompressing to at most `max_plaintext` bytes.
+fn open_with_key_within(
+    key: &[u8],
+    header: &Header,
+    ciphertext: &[u8],
+    aad: &[u8],
+    max_plaintext: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let key = GenericArray::from_slice(key);
+
+    let plaintext = match header.suite {
+        CipherSuite::Aes256GcmSiv => open_sealed(&Aes256GcmSiv::new(key), header, ciphertext, aad),
+        #[cfg(feature = "chacha20poly1305")]
+        CipherSuite::ChaCha20Poly1305 => {
+            open_sealed(&ChaCha20Poly1305::new(key), header, ciphertext, aad)
+        }
+        #[cfg(feature = "chacha20poly1305")]
+        CipherSuite::XChaCha20Poly1305 => {
+            open_sealed(&XChaCha20Poly1305::new(key), header, ciphertext, aad)
+        }
+    }?;
+
+    decompress(header, plaintext, max_plaintext)
+}
+
+/// Like [`seal`], with a cipher built from an already derived key, compressing first if the header says so.
+/// `aad` is authenticated after the encoded header, empty for regular blobs.
+pub(crate) fn seal_with_cipher<A: Aead>(
+    cipher: &A,
+    header: &Header,
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let nonce = aead::Nonce::<A>::from_exact_iter(header.nonce.iter().copied())
+        .ok_or(CryptographyError::EncodingFailure)?;
+
+    let compressed;
+    let data = if header.compression.is_none() {
+        data
+    } else {
+        compressed = header.compression.compress(data)?;
+        &compressed
     };
 
-    bincode::serialize(&file).map_err(|_| CryptographyError::EncodingFailure)
+    cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: data,
+                aad: &[&header.encode()?, aad].concat(),
+            },
+        )
+        .map_err(|_| CryptographyError::EncodingFailure)
 }
 
+/// Like [`open`], with a cipher built from an already derived key.
+#[cfg(feature = "std")]
+pub(crate) fn open_with_cipher<A: Aead>(
+    cipher: &A,
+    header: &Header,
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let plaintext = open_sealed(cipher, header, ciphertext, aad)?;
+
+    decompress(header, plaintext, DEFAULT_MAX_DECOMPRESSED_BYTES)
+}
+
+/// Authenticates and decrypts a ciphertext, without decompressing it.
+fn open_sealed<A: Aead>(
+    cipher: &A,
+    header: &Header,
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    // A nonce of another suite's size, the header was parsed fine so it names another suite.
+    let nonce = aead::Nonce::<A>::from_exact_iter(header.nonce.iter().copied())
+        .ok_or(CryptographyError::IncorrectPassword)?;
+
+    cipher
+        .decrypt(
+            &nonce,
+            Payload {
+                msg: ciphertext,
+                aad: &[&header.encode()?, aad].concat(),
+            },
+        )
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
+
+/// Decompresses an authenticated plaintext to at most `max_plaintext` bytes, if the header says it's compressed.
+fn decompress(
+    header: &Header,
+    plaintext: Vec<u8>,
+    max_plaintext: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    if header.compression.is_none() {
+        return Ok(plaintext);
+    }
+
+    header
+        .compression
+        .decompress(&Zeroizing::new(plaintext), max_plaintext)
+}
 
 /// Function for decrypting data.
 /// Takes encrypted data and password input as a slice (&\[T\]) of u8 (bytes) and returns a Result wrapping a vector of u8.
+/// With the `armor` feature it also accepts armored text, see [`decrypt_armored`](crate::decrypt_armored).
+/// Returns InsufficientMemory if the blob's header asks for more argon2 memory than [`DEFAULT_MAX_MEMORY_BYTES`].
 /// 
 /// ```rust
+/// use tinycrypt::{encrypt, decrypt};
+///
 /// let data = "Hello, world!";
 /// let password = "password";
 /// 
@@ -124,20 +1011,681 @@ pub fn encrypt(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyErro
 /// let decrypted_data : Vec<u8>= decrypt(&encrypted_data, password.as_bytes()).expect("Failed to decrypt data!");
 /// ```
 pub fn decrypt(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
-    let decoded: EncryptedFile =
-        bincode::deserialize(data).map_err(|_| CryptographyError::DecodingFailure)?;
-    let config = Config {
-        hash_length: 32,
-        ..Default::default()
+    #[cfg(feature = "armor")]
+    if armor::is_armored(data) {
+        let text = std::str::from_utf8(data).map_err(|_| CryptographyError::InvalidArmor)?;
+
+        return decrypt_armored(text, password);
+    }
+
+    if is_legacy(data) {
+        return decrypt_legacy(data, password);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+
+    decrypt_with_envelope(data, password, envelope)
+}
+
+/// Function for decrypting data stored in a specific [`Envelope`] format.
+/// Only needed for custom envelopes, [`decrypt`] detects the built-in ones.
+pub fn decrypt_with_envelope<E: Envelope + ?Sized>(
+    data: &[u8],
+    password: &[u8],
+    envelope: &E,
+) -> Result<Vec<u8>, CryptographyError> {
+    let (header, ciphertext) = envelope.deserialize(data)?;
+
+    open(&header, &ciphertext, password)
+}
+
+/// Function for decrypting data that may have been written by a newer minor version of the format.
+///
+/// The blob format's version byte is its major version, bumped for changes older readers can't skip over.
+/// Purely additive changes bump a minor version stored in the header instead. [`decrypt`] only accepts the minor
+/// versions this build knows, this also accepts newer ones with the same major version. The fields a newer minor adds
+/// are still authenticated but otherwise ignored. Fields marked must-understand, such as a newer compression or cipher
+/// suite would be, change how the blob decrypts and are rejected with UnsupportedField instead of being ignored.
+/// A newer major version is rejected with UnsupportedVersion either way.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt_lenient};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_lenient(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn decrypt_lenient(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    // Legacy and JSON blobs have no minor versions.
+    if is_legacy(data) || !Header::is_versioned(data) {
+        return decrypt(data, password);
+    }
+
+    let (header, header_len) = Header::parse_lenient(data)?;
+    format::verify_checksum(data)?;
+
+    open(&header, &data[header_len..], password)
+}
+
+/// Function for decrypting data only if deriving its key takes at most `max_memory_bytes` of argon2 memory.
+/// Otherwise returns InsufficientMemory with the blob's argon2 parameters, before any memory is allocated,
+/// so the blob can be handed to a host that can afford it.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_params, decrypt_with_memory_limit, Argon2Params, CryptographyError};
+///
+/// let params = Argon2Params { mem_cost: 64 * 1024, time_cost: 1, lanes: 1 };
+/// let encrypted_data = encrypt_with_params(b"Hello, world!", b"password", &params).expect("Failed to encrypt!");
+///
+/// match decrypt_with_memory_limit(&encrypted_data, b"password", 32 * 1024 * 1024) {
+///     Ok(data) => (), //do something with data
+///     Err(CryptographyError::InsufficientMemory(needed)) => assert_eq!(needed, params), //route to a bigger host
+///     Err(error) => (), //do something with a different error
+/// }
+/// ```
+pub fn decrypt_with_memory_limit(
+    data: &[u8],
+    password: &[u8],
+    max_memory_bytes: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let (kdf, params) = blob_kdf(data)?;
+
+    if kdf.is_argon2() && params.estimated_memory_bytes() > max_memory_bytes {
+        return Err(CryptographyError::InsufficientMemory(params));
+    }
+
+    if is_legacy(data) {
+        return decrypt_legacy(data, password);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, ciphertext) = envelope.deserialize(data)?;
+
+    open_within(&header, &ciphertext, password, max_memory_bytes)
+}
+
+/// Function for decrypting data like [`decrypt`], returning InputTooLarge if the plaintext is longer than `max_plaintext` bytes.
+/// Uncompressed blobs are checked before deriving the key. Compressed ones stop decompressing as soon as they pass
+/// the limit, so a small blob can't expand into a huge allocation.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt_bounded, CryptographyError};
+///
+/// let encrypted_data = encrypt(&[0u8; 1024], b"password").expect("Failed to encrypt!");
+///
+/// assert_eq!(decrypt_bounded(&encrypted_data, b"password", 512), Err(CryptographyError::InputTooLarge));
+/// ```
+pub fn decrypt_bounded(
+    data: &[u8],
+    password: &[u8],
+    max_plaintext: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    #[cfg(feature = "armor")]
+    if armor::is_armored(data) {
+        let text = std::str::from_utf8(data).map_err(|_| CryptographyError::InvalidArmor)?;
+
+        return decrypt_bounded(&dearmor(text)?, password, max_plaintext);
+    }
+
+    if is_legacy(data) {
+        let plaintext = Zeroizing::new(decrypt_legacy(data, password)?);
+
+        return Compression::None.decompress(&plaintext, max_plaintext);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, ciphertext) = envelope.deserialize(data)?;
+
+    if header.compression.is_none() && ciphertext.len().saturating_sub(TAG_SIZE) > max_plaintext {
+        return Err(CryptographyError::InputTooLarge);
+    }
+
+    let key = header.derive_key(password)?;
+    open_with_key_within(&key, &header, &ciphertext, &[], max_plaintext)
+}
+
+/// Function for decrypting data only if its key was derived with argon2 parameters at least as strong as `policy`.
+/// Otherwise returns PolicyViolation before deriving the key, so no plaintext is ever exposed.
+/// Blobs keyed without argon2 (e.g. by a [`KeyProvider`]) always violate the policy.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_params, decrypt_with_policy, Argon2Params, Argon2Policy, CryptographyError};
+///
+/// let policy = Argon2Policy { min_mem_cost: 64 * 1024, min_time_cost: 3 };
+///
+/// let weak = Argon2Params { mem_cost: 8 * 1024, time_cost: 1, lanes: 1 };
+/// let encrypted_data = encrypt_with_params(b"Hello, world!", b"password", &weak).expect("Failed to encrypt!");
+///
+/// assert_eq!(decrypt_with_policy(&encrypted_data, b"password", &policy), Err(CryptographyError::PolicyViolation));
+/// ```
+pub fn decrypt_with_policy(
+    data: &[u8],
+    password: &[u8],
+    policy: &Argon2Policy,
+) -> Result<Vec<u8>, CryptographyError> {
+    let (kdf, params) = blob_kdf(data)?;
+
+    if !kdf.is_argon2() || !policy.allows(&params) {
+        return Err(CryptographyError::PolicyViolation);
+    }
+
+    decrypt(data, password)
+}
+
+/// Reads which KDF and argon2 parameters a blob's key is derived with, without deriving it.
+fn blob_kdf(data: &[u8]) -> Result<(Kdf, Argon2Params), CryptographyError> {
+    if is_legacy(data) {
+        return Ok((Kdf::Argon2, Argon2Params::default()));
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(data)?;
+
+    Ok((header.kdf, header.params))
+}
+
+#[cfg(feature = "std")]
+/// Function for decrypting data with a password read from `password_reader`, such as a pipe handed over by a secret manager.
+/// Reads at most `password_len_limit` bytes, returning a DecodingFailure if the reader holds more (or fails).
+/// The password buffer is wiped once the key has been derived.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt_password_reader};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_password_reader(&encrypted_data, &b"password"[..], 64).expect("Failed to decrypt data!");
+/// ```
+pub fn decrypt_password_reader<R: Read>(
+    data: &[u8],
+    password_reader: R,
+    password_len_limit: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let limit = password_len_limit.saturating_add(1);
+
+    // Sized up front so reading never reallocates and leaves stray copies of the password behind.
+    let mut password = Zeroizing::new(Vec::with_capacity(limit));
+
+    password_reader
+        .take(limit as u64)
+        .read_to_end(&mut password)
+        .map_err(|_| CryptographyError::DecodingFailure)?;
+
+    if password.len() > password_len_limit {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    decrypt(data, &password)
+}
+
+/// Function for checking whether `password` opens `data` without keeping the plaintext.
+/// Returns Ok(false) for a wrong password, or an error if the blob itself is malformed.
+///
+/// Timing: every call on a well-formed blob runs the full argon2 derivation and the full AEAD decryption,
+/// whose tag check is constant time, whether the password is right or wrong. Nothing depending on the password
+/// returns early. Malformed blobs fail before key derivation, but that only depends on the blob, never the password.
+/// In practice the argon2 cost dwarfs any remaining difference. The decrypted plaintext is wiped before returning.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, verify_password};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// assert_eq!(verify_password(&encrypted_data, b"password"), Ok(true));
+/// assert_eq!(verify_password(&encrypted_data, b"guess"), Ok(false));
+/// ```
+pub fn verify_password(data: &[u8], password: &[u8]) -> Result<bool, CryptographyError> {
+    match decrypt(data, password) {
+        Ok(plaintext) => {
+            drop(Zeroizing::new(plaintext));
+            Ok(true)
+        }
+        Err(CryptographyError::IncorrectPassword) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Function for scrubbing a blob for corruption: checks its authentication tag under `password` without keeping the plaintext.
+/// Returns IncorrectPassword if the tag doesn't verify, or a DecodingFailure for malformed input.
+/// The header checksum is checked before the key is derived, so most corruption is reported without running argon2.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, check_integrity};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// check_integrity(&encrypted_data, b"password").expect("Blob is corrupted!");
+/// ```
+pub fn check_integrity(data: &[u8], password: &[u8]) -> Result<(), CryptographyError> {
+    decrypt(data, password).map(|plaintext| drop(Zeroizing::new(plaintext)))
+}
+
+#[cfg(feature = "std")]
+/// Function for scrubbing many blobs at once, reporting the result of [`check_integrity`] for each one in order.
+/// A corrupt blob doesn't stop the others from being checked. The key is derived once per distinct salt (and KDF
+/// settings) instead of once per blob, so blobs sharing a salt, such as those made by one [`DerivedKey`], cost one
+/// argon2 run in total. With the `rayon` feature keys are derived and blobs checked in parallel.
+///
+/// ```rust
+/// use tinycrypt::{check_integrity_batch, CryptographyError, DerivedKey};
+///
+/// let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+/// let first = key.encrypt(b"first").unwrap();
+/// let mut second = key.encrypt(b"second").unwrap();
+/// *second.last_mut().unwrap() ^= 1;
+///
+/// let report = check_integrity_batch(&[&first, &second], b"password");
+/// assert_eq!(report, [Ok(()), Err(CryptographyError::DecodingFailure)]);
+/// ```
+pub fn check_integrity_batch(
+    blobs: &[&[u8]],
+    password: &[u8],
+) -> Vec<Result<(), CryptographyError>> {
+    #[cfg(feature = "rayon")]
+    use rayon::prelude::*;
+
+    /// A blob's header & ciphertext, None for legacy blobs which have no header to share a key through.
+    type Parsed<'a> = Option<Result<(Header, Cow<'a, [u8]>), CryptographyError>>;
+
+    let parsed: Vec<Parsed> = blobs
+        .iter()
+        .map(|blob| {
+            if is_legacy(blob) {
+                return None;
+            }
+
+            let envelope = envelope::detect(blob).ok_or(CryptographyError::DecodingFailure);
+            Some(envelope.and_then(|envelope| envelope.deserialize(blob)))
+        })
+        .collect();
+
+    // One header per distinct key to derive it from.
+    let headers: HashMap<KeyId, &Header> = parsed
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|(header, _)| (key_id(header), header))
+        .collect();
+
+    let derive = |(id, header): (KeyId, &Header)| (id, header.derive_key(password));
+
+    #[cfg(feature = "rayon")]
+    let keys: HashMap<_, _> = headers.into_par_iter().map(derive).collect();
+    #[cfg(not(feature = "rayon"))]
+    let keys: HashMap<_, _> = headers.into_iter().map(derive).collect();
+
+    let check = |(blob, parsed): (&&[u8], &Parsed)| match parsed {
+        None => check_integrity(blob, password),
+        Some(Err(error)) => Err(error.clone()),
+        Some(Ok((header, ciphertext))) => {
+            let key = keys[&key_id(header)].as_ref().map_err(Clone::clone)?;
+
+            open_with_key(key, header, ciphertext, &[])
+                .map(|plaintext| drop(Zeroizing::new(plaintext)))
+        }
     };
-    let password = argon2::hash_raw(password, &decoded.salt, &config)
-        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
 
-    let key = GenericArray::from_slice(&password);
-    let cipher = Aes256GcmSiv::new(key);
-    let nonce = Nonce::from_slice(&decoded.nonce);
+    #[cfg(feature = "rayon")]
+    let results = blobs.par_iter().zip(&parsed).map(check).collect();
+    #[cfg(not(feature = "rayon"))]
+    let results = blobs.iter().zip(&parsed).map(check).collect();
 
-    cipher
-        .decrypt(nonce, decoded.data.as_ref())
-        .map_err(|_| CryptographyError::IncorrectPassword)
+    results
+}
+
+#[cfg(feature = "std")]
+/// Function for re-encrypting a blob under a fresh salt & nonce, so two copies of the same file can't be linked on disk.
+///
+/// This needs the password: AES-GCM-SIV can't re-randomize a nonce without the key, so the blob is decrypted
+/// and encrypted again. Every call gives entirely new ciphertext. The envelope format, context label,
+/// creation time & argon2 parameters are kept, legacy blobs come back in the current format.
+/// Blobs with extra associated data from [`encrypt_builder`] can't be re-encrypted this way.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt, reencrypt_envelope};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+/// let copy = reencrypt_envelope(&encrypted_data, b"password").expect("Failed to re-encrypt!");
+///
+/// assert_ne!(copy, encrypted_data);
+/// assert_eq!(decrypt(&copy, b"password"), decrypt(&encrypted_data, b"password"));
+/// ```
+pub fn reencrypt_envelope(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    rekey(data, password, password)
+}
+
+#[cfg(feature = "std")]
+/// Function for changing the password of a blob in one call, re-encrypting it under `new_password`
+/// with a fresh salt & nonce. Returns IncorrectPassword if `old_password` doesn't open the blob.
+/// Everything else is kept like in [`reencrypt_envelope`], and the plaintext is wiped from memory once re-encrypted.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt, rekey, CryptographyError};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"old password").expect("Failed to encrypt!");
+/// let rekeyed = rekey(&encrypted_data, b"old password", b"new password").expect("Failed to rekey!");
+///
+/// let decrypted_data = decrypt(&rekeyed, b"new password").expect("Failed to decrypt data!");
+/// assert_eq!(decrypt(&rekeyed, b"old password"), Err(CryptographyError::IncorrectPassword));
+/// ```
+pub fn rekey(
+    data: &[u8],
+    old_password: &[u8],
+    new_password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    if is_legacy(data) {
+        let plaintext = Zeroizing::new(decrypt_legacy(data, old_password)?);
+
+        return encrypt(&plaintext, new_password);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, ciphertext) = envelope.deserialize(data)?;
+    let plaintext = Zeroizing::new(open(&header, &ciphertext, old_password)?);
+
+    let mut fresh = random_suite_header(header.context_label(), header.suite)?;
+    fresh.created = header.created;
+    fresh.params = header.params;
+    fresh.minor = header.minor;
+    fresh.codec = header.codec;
+    fresh.epoch = header.epoch;
+    fresh.compression = header.compression;
+
+    let key = fresh.derive_key(new_password)?;
+
+    if header.verifier.is_some() {
+        add_verifier(&mut fresh, &key)?;
+    }
+
+    let ciphertext = seal_with_key(&key, &fresh, &plaintext, &[])?;
+
+    envelope.serialize(&fresh, &ciphertext)
+}
+
+/// Returns true if `data` should be parsed with the legacy bincode layout.
+/// A legacy ciphertext length can happen to look like an envelope's format id, so those blobs are told apart by their length prefix,
+/// which describes the whole blob exactly (ciphertext plus 12 byte nonce and 32 byte salt).
+pub(crate) fn is_legacy(data: &[u8]) -> bool {
+    if envelope::detect(data).is_none() {
+        return true;
+    }
+
+    let Some(prefix) = data
+        .get(..8)
+        .and_then(|prefix| <[u8; 8]>::try_from(prefix).ok())
+    else {
+        return false;
+    };
+
+    u64::from_le_bytes(prefix).checked_add(8 + 12 + 32) == Some(data.len() as u64)
+}
+
+fn decrypt_legacy(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let decoded = EncryptedFile::decode(data)?;
+    let key = decoded.derive_key(password)?;
+
+    decoded.open(&key)
+}
+
+#[cfg(feature = "std")]
+fn decrypt_legacy_timed(
+    data: &[u8],
+    password: &[u8],
+) -> Result<(Vec<u8>, Timings), CryptographyError> {
+    let decoded = EncryptedFile::decode(data)?;
+
+    let start = Instant::now();
+    let key = decoded.derive_key(password)?;
+    let kdf = start.elapsed();
+
+    let start = Instant::now();
+    let plaintext = decoded.open(&key)?;
+
+    Ok((
+        plaintext,
+        Timings {
+            kdf,
+            cipher: start.elapsed(),
+        },
+    ))
+}
+
+#[cfg(feature = "std")]
+/// How long each step of an [`encrypt_timed`] or [`decrypt_timed`] call took, for metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Timings {
+    /// Time spent deriving the key.
+    pub kdf: Duration,
+    /// Time spent encrypting or decrypting with the derived key.
+    pub cipher: Duration,
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data like [`encrypt`], also returning how long key derivation & encryption took.
+/// Useful for dashboards that should alert when the argon2 cost drifts.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_timed, decrypt_timed};
+///
+/// let (encrypted_data, timings) = encrypt_timed(b"Hello, world!", b"password").expect("Failed to encrypt!");
+/// println!("argon2 took {:?}, AES-GCM-SIV took {:?}", timings.kdf, timings.cipher);
+///
+/// let (decrypted_data, timings) = decrypt_timed(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_timed(
+    data: &[u8],
+    password: &[u8],
+) -> Result<(Vec<u8>, Timings), CryptographyError> {
+    let header = random_header(DEFAULT_CONTEXT)?;
+
+    let start = Instant::now();
+    let key = header.derive_key(password)?;
+    let kdf = start.elapsed();
+
+    let start = Instant::now();
+    let ciphertext = seal_with_key(&key, &header, data, &[])?;
+    let cipher = start.elapsed();
+
+    Ok((
+        Binary.serialize(&header, &ciphertext)?,
+        Timings { kdf, cipher },
+    ))
+}
+
+#[cfg(feature = "std")]
+/// Function for decrypting data like [`decrypt`], also returning how long key derivation & decryption took.
+pub fn decrypt_timed(
+    data: &[u8],
+    password: &[u8],
+) -> Result<(Vec<u8>, Timings), CryptographyError> {
+    if is_legacy(data) {
+        return decrypt_legacy_timed(data, password);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, ciphertext) = envelope.deserialize(data)?;
+
+    let start = Instant::now();
+    let key = header.derive_key(password)?;
+    let kdf = start.elapsed();
+
+    let start = Instant::now();
+    let plaintext = open_with_key(&key, &header, &ciphertext, &[])?;
+    let cipher = start.elapsed();
+
+    Ok((plaintext, Timings { kdf, cipher }))
+}
+
+
+/// Known block prepended to the plaintext by [`encrypt_committing`], two AES blocks of zeros.
+const COMMITMENT_BLOCK: [u8; 32] = [0u8; 32];
+
+#[cfg(feature = "std")]
+/// Function for encrypting data with practical key commitment.
+/// Works like [`encrypt`] but prepends a fixed block of zeros to the plaintext, which [`decrypt_committing`] checks exactly.
+///
+/// AES-GCM-SIV on its own isn't key-committing: a ciphertext can be crafted that decrypts successfully under two
+/// different passwords. Requiring a known plaintext block makes finding such a ciphertext impractical,
+/// as it would have to decrypt to the same 32 zero bytes under both keys.
+///
+/// This is the cheap "padding fix" transform, not a proven commitment scheme. It doesn't commit to the nonce,
+/// it costs 32 extra bytes per blob, and blobs must be opened with [`decrypt_committing`] (plain [`decrypt`] returns the padded plaintext).
+///
+/// ```rust
+/// use tinycrypt::{encrypt_committing, decrypt_committing};
+///
+/// let encrypted_data = encrypt_committing(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_committing(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_committing(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let mut padded = Vec::with_capacity(COMMITMENT_BLOCK.len() + data.len());
+    padded.extend_from_slice(&COMMITMENT_BLOCK);
+    padded.extend_from_slice(data);
+
+    encrypt(&padded, password)
+}
+
+/// Function for decrypting data encrypted with [`encrypt_committing`].
+/// Returns IncorrectPassword if the decrypted data doesn't start with the expected commitment block.
+pub fn decrypt_committing(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let decrypted = Zeroizing::new(decrypt(data, password)?);
+
+    match decrypted.strip_prefix(&COMMITMENT_BLOCK) {
+        Some(plaintext) => Ok(plaintext.to_vec()),
+        None => Err(CryptographyError::IncorrectPassword),
+    }
+}
+
+
+/// Returns the number of bytes [`encrypt`] produces for `plaintext_len` bytes of input.
+/// Useful for sizing the output buffer of [`encrypt_to_buf`].
+pub fn encrypted_len(plaintext_len: usize) -> usize {
+    format::default_header_len() + plaintext_len + TAG_SIZE
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data into a caller provided buffer without allocating the output.
+/// Writes the same blob [`encrypt`] would return into `out` and returns the number of bytes written.
+/// Returns BufferTooSmall if `out` is shorter than [`encrypted_len`] of the data.
+///
+/// The plaintext is copied into `out` and encrypted in place there, see [`encrypt_to_mmap`] for mapped files.
+/// Note the argon2 key derivation still allocates its working memory internally.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_to_buf, encrypted_len, decrypt};
+///
+/// let data = b"Hello, world!";
+/// let mut buffer = [0u8; 128];
+/// assert!(buffer.len() >= encrypted_len(data.len()));
+///
+/// let written = encrypt_to_buf(data, b"password", &mut buffer).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&buffer[..written], b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_to_buf(
+    data: &[u8],
+    password: &[u8],
+    out: &mut [u8],
+) -> Result<usize, CryptographyError> {
+    encrypt_to_buf_with_rng(data, password, out, &mut OsRng)
+}
+
+/// Like [`encrypt_to_buf`], with the salt & nonce drawn from `rng` as in [`encrypt_with_rng`], so it works without std.
+/// The header is written straight into `out`, the only allocations left are argon2's working memory and the derived key.
+///
+/// ```rust
+/// use aes_gcm_siv::aead::OsRng;
+/// use tinycrypt::{encrypt_to_buf_with_rng, encrypted_len, decrypt};
+///
+/// let data = b"Hello, world!";
+/// let mut buffer = [0u8; 128];
+/// assert!(buffer.len() >= encrypted_len(data.len()));
+///
+/// let written = encrypt_to_buf_with_rng(data, b"password", &mut buffer, &mut OsRng).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&buffer[..written], b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_to_buf_with_rng<R: RngCore + CryptoRng>(
+    data: &[u8],
+    password: &[u8],
+    out: &mut [u8],
+    rng: &mut R,
+) -> Result<usize, CryptographyError> {
+    let length = encrypted_len(data.len());
+
+    if out.len() < length {
+        return Err(CryptographyError::BufferTooSmall);
+    }
+
+    let mut salt = [0u8; 32];
+    rng.fill_bytes(&mut salt);
+
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut nonce);
+
+    let password = derive_key(
+        password,
+        &salt,
+        DEFAULT_CONTEXT,
+        Kdf::current(),
+        &Argon2Params::default(),
+        CipherSuite::Aes256GcmSiv,
+    )?;
+    let cipher = new_cipher(&password);
+
+    let header_len = format::write_default_header(out, &salt, &nonce)?;
+    let (aad, rest) = out[..length].split_at_mut(header_len);
+    let (body, tag_out) = rest.split_at_mut(data.len());
+    body.copy_from_slice(data);
+
+    let tag = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(&nonce), aad, body)
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    tag_out.copy_from_slice(&tag);
+    format::write_checksum(&mut out[..length]);
+
+    Ok(length)
+}
+
+#[cfg(feature = "std")]
+/// Function for encrypting data straight into a memory mapped output file, see [`encrypt_to_buf`].
+/// Size the mapping with [`encrypted_len`] of the input. If it's larger, the bytes after the blob are zeroed
+/// and the file should be truncated to the returned length, as [`decrypt`] rejects anything trailing the blob.
+///
+/// AES-GCM-SIV needs the whole plaintext before encrypting it in place, so unlike [`encrypt_to_buf`] it's encrypted
+/// in a separate buffer that's wiped afterwards and only the finished blob is copied into `out`.
+/// The plaintext never reaches the page cache of the mapped file, at the cost of allocating the blob's size.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_to_mmap, encrypted_len, decrypt};
+///
+/// let data = b"Hello, world!";
+/// // Stands in for a mapping of a file created with set_len(encrypted_len(data.len())).
+/// let mut mapping = vec![0u8; encrypted_len(data.len())];
+///
+/// let written = encrypt_to_mmap(data, b"password", &mut mapping).expect("Failed to encrypt!");
+/// assert_eq!(written, mapping.len());
+///
+/// let decrypted_data = decrypt(&mapping, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_to_mmap(
+    input: &[u8],
+    password: &[u8],
+    out: &mut [u8],
+) -> Result<usize, CryptographyError> {
+    let length = encrypted_len(input.len());
+
+    if out.len() < length {
+        return Err(CryptographyError::BufferTooSmall);
+    }
+
+    let mut blob = Zeroizing::new(vec![0u8; length]);
+    let written = encrypt_to_buf(input, password, &mut blob)?;
+
+    out[..written].copy_from_slice(&blob[..written]);
+    out[written..].fill(0);
+
+    Ok(written)
 }
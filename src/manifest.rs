@@ -0,0 +1,73 @@
+//! Authenticated manifests binding a set of blobs together.
+
+use crate::{decrypt_with_envelope, encrypt_with_context, envelope, CryptographyError};
+use sha2::{Digest, Sha256};
+
+/// KDF context label of manifests, so neither a manifest nor a regular blob can stand in for the other.
+const MANIFEST_CONTEXT: &[u8] = b"tinycrypt-v1-manifest";
+
+const HASH_SIZE: usize = 32;
+
+/// Function for building a manifest of `blobs`, authenticated under a key derived from `password`.
+/// Holds the SHA-256 hash of every blob in order, so [`verify_manifest`] detects any blob that was modified,
+/// swapped, reordered, added or removed. The blobs can be anything, not just tinycrypt blobs.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, build_manifest, verify_manifest};
+///
+/// let first = encrypt(b"first artifact", b"password").expect("Failed to encrypt!");
+/// let second = encrypt(b"second artifact", b"password").expect("Failed to encrypt!");
+///
+/// let manifest = build_manifest(&[&first, &second], b"password").expect("Failed to build manifest!");
+///
+/// verify_manifest(&manifest, &[&first, &second], b"password").expect("Blobs were tampered with!");
+/// assert!(verify_manifest(&manifest, &[&second, &first], b"password").is_err());
+/// ```
+pub fn build_manifest(blobs: &[&[u8]], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let count = u32::try_from(blobs.len()).map_err(|_| CryptographyError::EncodingFailure)?;
+
+    let mut hashes = Vec::with_capacity(4 + blobs.len() * HASH_SIZE);
+    hashes.extend_from_slice(&count.to_le_bytes());
+
+    for blob in blobs {
+        hashes.extend_from_slice(&Sha256::digest(blob));
+    }
+
+    encrypt_with_context(&hashes, password, MANIFEST_CONTEXT)
+}
+
+/// Function for checking `blobs` are exactly the ones a manifest was built from, in the same order.
+/// Returns IncorrectPassword if the manifest itself can't be authenticated, or ManifestMismatch if any blob differs.
+pub fn verify_manifest(
+    manifest: &[u8],
+    blobs: &[&[u8]],
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let envelope = envelope::detect(manifest).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(manifest)?;
+
+    if header.context_label() != MANIFEST_CONTEXT {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let hashes = decrypt_with_envelope(manifest, password, envelope)?;
+    let (count, hashes) = hashes
+        .split_first_chunk::<4>()
+        .ok_or(CryptographyError::DecodingFailure)?;
+
+    if hashes.len() != u32::from_le_bytes(*count) as usize * HASH_SIZE {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let matches = hashes.len() == blobs.len() * HASH_SIZE
+        && hashes
+            .chunks_exact(HASH_SIZE)
+            .zip(blobs)
+            .all(|(hash, blob)| Sha256::digest(blob).as_slice() == hash);
+
+    if matches {
+        Ok(())
+    } else {
+        Err(CryptographyError::ManifestMismatch)
+    }
+}
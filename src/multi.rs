@@ -0,0 +1,366 @@
+//! Blobs that can be opened by any one of several passwords, such as a backup shared by a team.
+//!
+//! The data is encrypted once under a random data key (DEK), which is wrapped separately for every password:
+//!
+//! ```text
+//! magic           4 bytes   b"TCMP"
+//! version         1 byte    2
+//! flags           1 byte    bit 0 insecure test KDF
+//! slot count      1 byte    number of password slots, at least 1
+//! password slots  92 bytes  each: salt (32), nonce (12), wrapped DEK (48)
+//! nonce           12 bytes  nonce of the data
+//! ciphertext      everything after, ciphertext followed by the 16 byte tag
+//! ```
+//!
+//! Every slot's key is derived with argon2 from its own salt, like [`encrypt`](crate::encrypt) does.
+//! Each wrapped DEK is authenticated along with the magic, version & flags. Everything before the ciphertext is
+//! authenticated as the data's associated data, so no slot can be swapped or stripped.
+//!
+//! Version 1 blobs also authenticated each wrapped DEK along with the slot count. They still decrypt, but can't
+//! have passwords added or removed.
+
+use crate::{derive_key, new_cipher, Argon2Params, CipherSuite, CryptographyError, Kdf};
+use aes_gcm_siv::{
+    aead::{rand_core::RngCore, Aead, OsRng, Payload},
+    Nonce,
+};
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 4] = b"TCMP";
+const VERSION: u8 = 2;
+/// Slots authenticated along with the slot count, so their number can't change.
+const VERSION_COUNTED_SLOTS: u8 = 1;
+
+const FLAG_INSECURE_KDF: u8 = 1;
+
+/// Magic, version & flags, the associated data of every slot's wrapped DEK.
+/// The slot count isn't part of it, so existing slots stay valid when [`add_password`] adds one.
+const SLOT_AAD_LEN: usize = MAGIC.len() + 1 + 1;
+const PREFIX_LEN: usize = SLOT_AAD_LEN + 1;
+const WRAPPED_LEN: usize = 32 + 16;
+const SLOT_LEN: usize = 32 + 12 + WRAPPED_LEN;
+
+const PASSWORD_CONTEXT: &[u8] = b"tinycrypt-v1-multi-password";
+
+/// Function for encrypting data so that any one of `passwords` can open it with [`decrypt_multi`].
+/// Returns an EncodingFailure for no passwords or more than 255 of them.
+///
+/// A key is derived for every password, so encrypting takes as long as that many calls to [`encrypt`](crate::encrypt).
+///
+/// ```rust
+/// use tinycrypt::{decrypt_multi, encrypt_multi};
+///
+/// let encrypted_data = encrypt_multi(b"Hello, world!", &[b"alice's password".as_slice(), b"bob's password"])
+///     .expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_multi(&encrypted_data, b"bob's password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_multi(data: &[u8], passwords: &[&[u8]]) -> Result<Vec<u8>, CryptographyError> {
+    seal_multi(data, passwords, Kdf::current())
+}
+
+/// Encrypts `data` under a fresh DEK with a slot for each of `passwords`.
+fn seal_multi(data: &[u8], passwords: &[&[u8]], kdf: Kdf) -> Result<Vec<u8>, CryptographyError> {
+    let count = u8::try_from(passwords.len())
+        .ok()
+        .filter(|&count| count > 0)
+        .ok_or(CryptographyError::EncodingFailure)?;
+
+    let mut dek = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(dek.as_mut());
+
+    let flags = if kdf.is_argon2() {
+        0
+    } else {
+        FLAG_INSECURE_KDF
+    };
+
+    let mut blob =
+        Vec::with_capacity(PREFIX_LEN + passwords.len() * SLOT_LEN + 12 + data.len() + 16);
+    blob.extend_from_slice(MAGIC);
+    blob.push(VERSION);
+    blob.push(flags);
+    blob.push(count);
+
+    for password in passwords {
+        push_slot(&mut blob, password, &dek[..], kdf)?;
+    }
+
+    seal_data(blob, &dek[..], data)
+}
+
+/// Function for decrypting a blob made by [`encrypt_multi`] with any one of its passwords.
+/// Returns an IncorrectPassword if the password opens none of the slots or the blob was modified.
+///
+/// The password is tried against the slots in order, deriving a key for each, so a wrong password
+/// takes as long as trying every slot.
+pub fn decrypt_multi(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let unlocked = unlock(data, password)?;
+
+    open_data(data, &unlocked)
+}
+
+/// Function for giving `new_password` access to a blob made by [`encrypt_multi`], using any password that opens it.
+/// The data key and every existing slot are kept, so nobody else's password is needed.
+/// Returns an EncodingFailure if the blob already has 255 slots or is a version 1 blob, whose slots are bound to their count.
+///
+/// The data is sealed again under the same data key with a fresh nonce, as the slots are part of its associated data.
+///
+/// ```rust
+/// use tinycrypt::{add_password, decrypt_multi, encrypt_multi};
+///
+/// let encrypted_data = encrypt_multi(b"Hello, world!", &[b"alice's password"]).expect("Failed to encrypt!");
+/// let shared = add_password(&encrypted_data, b"alice's password", b"bob's password").expect("Failed to add password!");
+///
+/// let decrypted_data = decrypt_multi(&shared, b"bob's password").expect("Failed to decrypt data!");
+/// ```
+pub fn add_password(
+    data: &[u8],
+    password: &[u8],
+    new_password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let unlocked = unlock(data, password)?;
+
+    if unlocked.version == VERSION_COUNTED_SLOTS {
+        return Err(CryptographyError::EncodingFailure);
+    }
+
+    let plaintext = Zeroizing::new(open_data(data, &unlocked)?);
+
+    let mut blob = data[..unlocked.header_len - 12].to_vec();
+    blob[PREFIX_LEN - 1] = blob[PREFIX_LEN - 1]
+        .checked_add(1)
+        .ok_or(CryptographyError::EncodingFailure)?;
+
+    push_slot(&mut blob, new_password, &unlocked.dek, unlocked.kdf)?;
+
+    seal_data(blob, &unlocked.dek, &plaintext)
+}
+
+/// Function for revoking `removed_password`'s access to a blob made by [`encrypt_multi`].
+/// Every password that keeps access has to be given in `remaining_passwords`, as the data is sealed again under a
+/// fresh data key; the removed password's holder may have kept the old one, so the other slots can't simply stay.
+/// Returns KeyNotFound if `removed_password` opens no slot, IncorrectPassword if one of `remaining_passwords` doesn't
+/// open the blob or opens the removed slot, or an EncodingFailure if no password would remain.
+///
+/// Copies of the blob made before the removal still open with the removed password, only this one is re-keyed.
+///
+/// ```rust
+/// use tinycrypt::{decrypt_multi, encrypt_multi, remove_password, CryptographyError};
+///
+/// let encrypted_data = encrypt_multi(b"Hello, world!", &[b"alice".as_slice(), b"bob"]).expect("Failed to encrypt!");
+/// let revoked = remove_password(&encrypted_data, b"bob", &[b"alice"]).expect("Failed to remove password!");
+///
+/// assert_eq!(decrypt_multi(&revoked, b"bob"), Err(CryptographyError::IncorrectPassword));
+/// ```
+pub fn remove_password(
+    data: &[u8],
+    removed_password: &[u8],
+    remaining_passwords: &[&[u8]],
+) -> Result<Vec<u8>, CryptographyError> {
+    if remaining_passwords.is_empty() {
+        return Err(CryptographyError::EncodingFailure);
+    }
+
+    let removed = match unlock(data, removed_password) {
+        Ok(unlocked) => unlocked,
+        Err(CryptographyError::IncorrectPassword) => return Err(CryptographyError::KeyNotFound),
+        Err(error) => return Err(error),
+    };
+
+    for password in remaining_passwords {
+        if unlock(data, password)?.slot == removed.slot {
+            return Err(CryptographyError::IncorrectPassword);
+        }
+    }
+
+    let plaintext = Zeroizing::new(open_data(data, &removed)?);
+
+    seal_multi(&plaintext, remaining_passwords, removed.kdf)
+}
+
+/// Data key of a blob, unwrapped from the slot a password opened.
+struct Unlocked {
+    dek: Zeroizing<Vec<u8>>,
+    kdf: Kdf,
+    version: u8,
+    /// Index of the slot the password opened.
+    slot: usize,
+    /// Length of everything before the ciphertext.
+    header_len: usize,
+}
+
+/// Tries `password` against every slot of the blob in order.
+fn unlock(data: &[u8], password: &[u8]) -> Result<Unlocked, CryptographyError> {
+    let prefix = data
+        .get(..PREFIX_LEN)
+        .ok_or(CryptographyError::DecodingFailure)?;
+    let version = prefix[MAGIC.len()];
+    let flags = prefix[MAGIC.len() + 1];
+    let count = prefix[MAGIC.len() + 2] as usize;
+
+    if !data.starts_with(MAGIC)
+        || (version != VERSION && version != VERSION_COUNTED_SLOTS)
+        || flags & !FLAG_INSECURE_KDF != 0
+        || count == 0
+    {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let header_len = PREFIX_LEN + count * SLOT_LEN + 12;
+
+    if data.len() < header_len + 16 {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let kdf = if flags & FLAG_INSECURE_KDF != 0 {
+        Kdf::InsecureSha256
+    } else {
+        Kdf::Argon2
+    };
+
+    let slot_aad = if version == VERSION_COUNTED_SLOTS {
+        prefix
+    } else {
+        &prefix[..SLOT_AAD_LEN]
+    };
+
+    for (index, slot) in data[PREFIX_LEN..header_len - 12]
+        .chunks_exact(SLOT_LEN)
+        .enumerate()
+    {
+        let salt = slot[..32]
+            .try_into()
+            .map_err(|_| CryptographyError::DecodingFailure)?;
+        let kek = password_key(password, &salt, kdf)?;
+
+        match unwrap(kek.as_ref(), &slot[32..], slot_aad) {
+            Ok(dek) => {
+                return Ok(Unlocked {
+                    dek,
+                    kdf,
+                    version,
+                    slot: index,
+                    header_len,
+                })
+            }
+            Err(CryptographyError::IncorrectPassword) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Err(CryptographyError::IncorrectPassword)
+}
+
+/// Appends a slot with a fresh salt, wrapping `dek` for `password`.
+fn push_slot(
+    blob: &mut Vec<u8>,
+    password: &[u8],
+    dek: &[u8],
+    kdf: Kdf,
+) -> Result<(), CryptographyError> {
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+
+    let kek = password_key(password, &salt, kdf)?;
+    let aad: [u8; SLOT_AAD_LEN] = blob[..SLOT_AAD_LEN]
+        .try_into()
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    blob.extend_from_slice(&salt);
+    wrap(blob, kek.as_ref(), dek, &aad)
+}
+
+/// Appends a fresh nonce and `data` sealed under `dek`, authenticating everything in `blob` so far.
+fn seal_data(mut blob: Vec<u8>, dek: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    blob.extend_from_slice(&nonce);
+
+    let ciphertext = new_cipher(dek)
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: data,
+                aad: &blob,
+            },
+        )
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+    blob.extend_from_slice(&ciphertext);
+
+    Ok(blob)
+}
+
+/// Decrypts the data of an unlocked blob.
+fn open_data(data: &[u8], unlocked: &Unlocked) -> Result<Vec<u8>, CryptographyError> {
+    let header_len = unlocked.header_len;
+
+    new_cipher(&unlocked.dek)
+        .decrypt(
+            Nonce::from_slice(&data[header_len - 12..header_len]),
+            Payload {
+                msg: &data[header_len..],
+                aad: &data[..header_len],
+            },
+        )
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
+
+fn password_key(
+    password: &[u8],
+    salt: &[u8; 32],
+    kdf: Kdf,
+) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+    derive_key(
+        password,
+        salt,
+        PASSWORD_CONTEXT,
+        kdf,
+        &Argon2Params::default(),
+        CipherSuite::Aes256GcmSiv,
+    )
+}
+
+/// Appends a fresh nonce and the DEK encrypted under `kek`.
+pub(crate) fn wrap(
+    blob: &mut Vec<u8>,
+    kek: &[u8],
+    dek: &[u8],
+    prefix: &[u8],
+) -> Result<(), CryptographyError> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let wrapped = new_cipher(kek)
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: dek,
+                aad: prefix,
+            },
+        )
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&wrapped);
+
+    Ok(())
+}
+
+/// Decrypts a nonce & wrapped DEK pair written by [`wrap`].
+pub(crate) fn unwrap(
+    kek: &[u8],
+    wrapped: &[u8],
+    prefix: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, CryptographyError> {
+    new_cipher(kek)
+        .decrypt(
+            Nonce::from_slice(&wrapped[..12]),
+            Payload {
+                msg: &wrapped[12..],
+                aad: prefix,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
@@ -0,0 +1,184 @@
+//! Argon2 cost parameters.
+
+use crate::CryptographyError;
+#[cfg(feature = "std")]
+use argon2::{Config, Variant, Version};
+
+/// Size of one argon2 memory block in bytes.
+const BLOCK_SIZE: usize = 1024;
+
+/// Argon2 slices every lane into this many segments.
+const SYNC_POINTS: u64 = 4;
+
+/// Most argon2 memory [`decrypt`](crate::decrypt) lets a blob's header ask for, 1 GiB.
+/// Blobs asking for more return InsufficientMemory before anything is allocated, rather than the allocator aborting
+/// the process. Open them with [`decrypt_with_memory_limit`](crate::decrypt_with_memory_limit) on a host that can afford it.
+pub const DEFAULT_MAX_MEMORY_BYTES: usize = 1 << 30;
+
+/// Argon2id cost parameters used to derive a key from a password.
+///
+/// The default matches what [`encrypt`](crate::encrypt) uses (the OWASP recommended 19 MiB, 2 passes, 1 lane).
+/// Non-default parameters are stored in the blob's header, so [`decrypt`](crate::decrypt) needs nothing extra.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Argon2Params {
+    /// Memory cost in KiB.
+    pub mem_cost: u32,
+    /// Number of passes over the memory.
+    pub time_cost: u32,
+    /// Degree of parallelism. Stored in the header like the other costs, so decryption always uses the same value.
+    ///
+    /// The argon2 backend computes the lanes one after another on the calling thread, so more lanes don't derive
+    /// any faster (`cargo bench --bench kdf` compares 1, 4 and 8 lanes). Raise it only to match a policy or another
+    /// implementation, never as a substitute for memory or time cost.
+    pub lanes: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            mem_cost: 19 * 1024,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Returns how many bytes of memory argon2 allocates while deriving a key with these parameters.
+    /// Argon2 rounds the memory cost down to a whole number of segments (and up to its minimum), which is accounted for.
+    /// Besides this working set only a few KiB are used.
+    ///
+    /// ```rust
+    /// use tinycrypt::Argon2Params;
+    ///
+    /// assert_eq!(Argon2Params::default().estimated_memory_bytes(), 19 * 1024 * 1024);
+    /// ```
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let lanes = u64::from(self.lanes.max(1));
+        let blocks = u64::from(self.mem_cost).max(2 * SYNC_POINTS * lanes);
+        let segment_length = blocks / (lanes * SYNC_POINTS);
+
+        (segment_length * lanes * SYNC_POINTS) as usize * BLOCK_SIZE
+    }
+
+    /// Checks the parameters are accepted by argon2.
+    pub(crate) fn validate(&self) -> Result<(), CryptographyError> {
+        let valid = self.time_cost >= 1
+            && (1..1 << 24).contains(&self.lanes)
+            && self.mem_cost >= 8 * self.lanes;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CryptographyError::KeyGenerationFailure)
+        }
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Argon2id configuration for these parameters, with `context` as associated data.
+    /// `key_len` must come from [`CipherSuite::key_len`](crate::CipherSuite::key_len).
+    #[cfg(feature = "std")]
+    pub(crate) fn config<'a>(&self, context: &'a [u8], key_len: usize) -> Config<'a> {
+        Config {
+            ad: context,
+            hash_length: key_len as u32,
+            lanes: self.lanes,
+            mem_cost: self.mem_cost,
+            secret: &[],
+            time_cost: self.time_cost,
+            variant: Variant::Argon2id,
+            version: Version::Version13,
+        }
+    }
+}
+
+/// Named argon2 cost presets, all at least as strong as the OWASP minimum.
+///
+/// A derivation blocks its thread for as long as the preset takes. On a browser's main thread use
+/// [`Interactive`](KdfPreset::Interactive), or derive one slice at a time with [`KeyDerivation`](crate::KeyDerivation).
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with, decrypt, EncryptOptions, KdfPreset};
+///
+/// let options = EncryptOptions::new().params(KdfPreset::Interactive.into());
+///
+/// let encrypted_data = encrypt_with(b"Hello, world!", b"password", &options).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdfPreset {
+    /// 7 MiB, 5 passes. The OWASP minimum with the least memory, for browsers & other constrained hosts.
+    /// Below the memory cost the default [`Argon2Policy`] requires.
+    Interactive,
+    /// 19 MiB, 2 passes. The default [`encrypt`](crate::encrypt) uses.
+    Moderate,
+    /// 64 MiB, 3 passes. For secrets that are rarely unlocked, on hosts that can spare the memory.
+    Sensitive,
+}
+
+impl KdfPreset {
+    /// Returns the argon2 parameters of this preset.
+    pub fn params(self) -> Argon2Params {
+        match self {
+            Self::Interactive => Argon2Params {
+                mem_cost: 7 * 1024,
+                time_cost: 5,
+                lanes: 1,
+            },
+            Self::Moderate => Argon2Params::default(),
+            Self::Sensitive => Argon2Params {
+                mem_cost: 64 * 1024,
+                time_cost: 3,
+                lanes: 1,
+            },
+        }
+    }
+}
+
+impl From<KdfPreset> for Argon2Params {
+    fn from(preset: KdfPreset) -> Self {
+        preset.params()
+    }
+}
+
+/// Minimum argon2 costs a blob's key must have been derived with, enforced by
+/// [`decrypt_with_policy`](crate::decrypt_with_policy).
+///
+/// The default accepts exactly what [`encrypt`](crate::encrypt) uses and anything stronger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Argon2Policy {
+    /// Minimum memory cost in KiB.
+    pub min_mem_cost: u32,
+    /// Minimum number of passes over the memory.
+    pub min_time_cost: u32,
+}
+
+impl Default for Argon2Policy {
+    fn default() -> Self {
+        let params = Argon2Params::default();
+
+        Self {
+            min_mem_cost: params.mem_cost,
+            min_time_cost: params.time_cost,
+        }
+    }
+}
+
+impl Argon2Policy {
+    /// Returns whether keys derived with `params` meet this policy.
+    ///
+    /// ```rust
+    /// use tinycrypt::{Argon2Params, Argon2Policy};
+    ///
+    /// assert!(Argon2Policy::default().allows(&Argon2Params::default()));
+    /// assert!(!Argon2Policy { min_mem_cost: 64 * 1024, min_time_cost: 3 }.allows(&Argon2Params::default()));
+    /// ```
+    pub fn allows(&self, params: &Argon2Params) -> bool {
+        params.mem_cost >= self.min_mem_cost && params.time_cost >= self.min_time_cost
+    }
+}
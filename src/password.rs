@@ -0,0 +1,184 @@
+//! Password handling & secure random password generation.
+
+use aes_gcm_siv::aead::{rand_core::RngCore, OsRng};
+use std::{convert::Infallible, fmt, ops::Deref, str::FromStr};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Password wiped from memory when dropped, for handing to any encrypt or decrypt function.
+///
+/// Dereferences to `[u8]`, so `&password` can be passed wherever a password slice is expected.
+/// Conversions from owned strings & vectors take over their buffer instead of copying it,
+/// and Debug never prints the password. It can also be wiped early with [`Zeroize::zeroize`], leaving it empty.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, decrypt, Password};
+///
+/// let password: Password = "password".parse().unwrap();
+///
+/// let encrypted_data = encrypt(b"Hello, world!", &password).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt(&encrypted_data, &password).expect("Failed to decrypt data!");
+/// ```
+pub struct Password(Zeroizing<Vec<u8>>);
+
+impl Deref for Password {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Password {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<&str> for Password {
+    fn from(password: &str) -> Self {
+        Self(Zeroizing::new(password.as_bytes().to_vec()))
+    }
+}
+
+impl From<&[u8]> for Password {
+    fn from(password: &[u8]) -> Self {
+        Self(Zeroizing::new(password.to_vec()))
+    }
+}
+
+impl From<String> for Password {
+    fn from(password: String) -> Self {
+        Self(Zeroizing::new(password.into_bytes()))
+    }
+}
+
+impl From<Vec<u8>> for Password {
+    fn from(password: Vec<u8>) -> Self {
+        Self(Zeroizing::new(password))
+    }
+}
+
+impl FromStr for Password {
+    type Err = Infallible;
+
+    fn from_str(password: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(password))
+    }
+}
+
+impl Zeroize for Password {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Password {}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(..)")
+    }
+}
+
+/// Character set used by [`generate_password`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// Letters and digits (`a-z`, `A-Z`, `0-9`), about 5.95 bits of entropy per character.
+    Alphanumeric,
+    /// Every printable ASCII character except space, about 6.55 bits of entropy per character.
+    Ascii,
+    /// Words from a built-in list of 256 separated by `-`, length counts words. 8 bits of entropy per word.
+    Passphrase,
+}
+
+const ALPHANUMERIC: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+const WORDS: [&str; 256] = [
+    "acid", "acorn", "actor", "adobe", "agent", "alarm", "album", "alley", "amber", "angle",
+    "ankle", "apple", "apron", "arena", "armor", "arrow", "aspen", "atlas", "attic", "audio",
+    "award", "bacon", "badge", "bagel", "baker", "banjo", "barge", "basil", "batch", "beach",
+    "beard", "bench", "berry", "bison", "blade", "blaze", "bloom", "board", "boost", "bread",
+    "brick", "brook", "brush", "bunny", "cabin", "cable", "cacao", "camel", "candy", "canoe",
+    "cargo", "carol", "cedar", "chalk", "charm", "chess", "chili", "cider", "clamp", "cliff",
+    "cloak", "clock", "cloud", "clove", "coach", "coast", "cobra", "cocoa", "comet", "coral",
+    "couch", "crane", "crumb", "curry", "daisy", "dance", "delta", "denim", "diary", "dingo",
+    "dodge", "donut", "dough", "draft", "dream", "drift", "drum", "dusk", "eagle", "easel",
+    "ebony", "elbow", "elder", "ember", "fable", "ferry", "fiber", "field", "flame", "flask",
+    "fleet", "flint", "flora", "flute", "forge", "fossil", "frost", "fudge", "gadget", "gecko",
+    "genie", "ghost", "giant", "ginger", "glade", "globe", "glove", "gnome", "grape", "guava",
+    "guild", "hammer", "harbor", "haven", "hazel", "heron", "hinge", "hippo", "honey", "hotel",
+    "husky", "igloo", "indigo", "ivory", "jacket", "jelly", "jewel", "jockey", "judge", "juice",
+    "jumbo", "kayak", "kebab", "kettle", "kiosk", "kitten", "koala", "ladder", "lagoon", "lemon",
+    "lever", "lilac", "linen", "llama", "lobby", "locket", "lotus", "lyric", "mango", "maple",
+    "marble", "meadow", "melon", "mint", "mocha", "moose", "mural", "nacho", "nectar", "noodle",
+    "novel", "nutmeg", "oasis", "ocean", "olive", "omega", "onion", "opal", "orbit", "otter",
+    "oyster", "paddle", "panda", "parrot", "pasta", "pearl", "pebble", "pepper", "piano", "pilot",
+    "pixel", "plaza", "plum", "polka", "poppy", "prism", "puzzle", "quail", "quartz", "quill",
+    "radar", "raven", "ribbon", "river", "robin", "rocket", "rodeo", "ruby", "saddle", "salsa",
+    "scarf", "shark", "shelf", "sierra", "silk", "sketch", "slate", "sloth", "spade", "spice",
+    "squid", "stone", "sugar", "swing", "syrup", "tango", "tartan", "teapot", "tiger", "toast",
+    "tofu", "topaz", "torch", "tulip", "tundra", "turtle", "tuxedo", "valley", "velvet", "vinyl",
+    "violin", "viper", "visor", "waffle", "walnut", "walrus", "wasabi", "whale", "wheat", "willow",
+    "wizard", "yacht", "yodel", "yogurt", "zebra", "zinc",
+];
+
+/// Function for generating a strong random password.
+/// Returns `len` characters (or words for [`Charset::Passphrase`]) drawn uniformly from the charset using the OS random number generator.
+/// The password is wiped from memory once the returned wrapper is dropped.
+///
+/// ```rust
+/// use tinycrypt::{generate_password, Charset};
+///
+/// let password = generate_password(24, Charset::Alphanumeric);
+/// assert_eq!(password.len(), 24);
+///
+/// let passphrase = generate_password(10, Charset::Passphrase);
+/// assert_eq!(passphrase.split('-').count(), 10);
+/// ```
+pub fn generate_password(len: usize, charset: Charset) -> Zeroizing<String> {
+    // Reserve everything up front so the buffer never reallocates and leaves copies behind.
+    let capacity = match charset {
+        Charset::Alphanumeric | Charset::Ascii => len,
+        Charset::Passphrase => len * (longest_word() + 1),
+    };
+
+    let mut password = Zeroizing::new(String::with_capacity(capacity));
+
+    for i in 0..len {
+        match charset {
+            Charset::Alphanumeric => {
+                password.push(ALPHANUMERIC[random_index(ALPHANUMERIC.len())] as char)
+            }
+            Charset::Ascii => password.push((b'!' + random_index(94) as u8) as char),
+            Charset::Passphrase => {
+                if i > 0 {
+                    password.push('-');
+                }
+
+                password.push_str(WORDS[random_index(WORDS.len())]);
+            }
+        }
+    }
+
+    password
+}
+
+/// Returns a uniformly distributed index below `n`.
+/// Uses rejection sampling, as reducing a random number modulo `n` would favour the lower indices.
+fn random_index(n: usize) -> usize {
+    let n = n as u32;
+    let limit = u32::MAX - u32::MAX % n;
+
+    loop {
+        let value = OsRng.next_u32();
+
+        if value < limit {
+            return (value % n) as usize;
+        }
+    }
+}
+
+fn longest_word() -> usize {
+    WORDS.iter().map(|word| word.len()).max().unwrap_or(0)
+}
@@ -0,0 +1,108 @@
+//! Encryption under keys held outside of tinycrypt, such as in a hardware token or a KMS.
+//!
+//! Blobs use the regular header with a KDF field marking the key as provider supplied, and the context label
+//! identifying which key the provider should hand out. The salt is still stored but unused.
+//!
+//! Providers that rotate keys also stamp the epoch of the key into the header, so blobs from earlier epochs keep
+//! decrypting after a rotation.
+
+use crate::{
+    envelope::{self, Binary},
+    new_cipher, open_with_key, random_header, seal_with_cipher, CryptographyError, Envelope,
+    Header, Kdf,
+};
+use zeroize::Zeroizing;
+
+/// Source of 256 bit keys that aren't derived from a password, e.g. a hardware security module.
+///
+/// `context` is the label passed to [`encrypt_with_provider`], read back from the blob's header when decrypting,
+/// so a provider holding several keys can use it to pick the right one.
+pub trait KeyProvider {
+    /// Returns the key for `context`, or KeyNotFound if the provider has no key for it.
+    fn provide_key(&self, context: &[u8]) -> Result<[u8; 32], CryptographyError>;
+
+    /// Key rotation epoch new blobs are encrypted under, None (the default) for providers that don't rotate keys.
+    /// When Some, blobs are stamped with it and their keys come from [`provide_epoch_key`](Self::provide_epoch_key).
+    fn current_epoch(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the key of `epoch` for `context`, or UnknownEpoch if the provider has no key for that epoch.
+    /// Only called for blobs stamped with an epoch, the default knows no epochs at all.
+    fn provide_epoch_key(&self, context: &[u8], epoch: u32) -> Result<[u8; 32], CryptographyError> {
+        let _ = context;
+
+        Err(CryptographyError::UnknownEpoch(epoch))
+    }
+}
+
+/// Function for encrypting data under a key from `provider` instead of one derived from a password with argon2.
+/// `context` (up to [`MAX_CONTEXT_LEN`](crate::MAX_CONTEXT_LEN) bytes) is stored in the blob and identifies the key.
+/// Such blobs can only be decrypted with [`decrypt_with_provider`], [`decrypt`](crate::decrypt) returns KeyNotFound.
+/// If the provider has a [`current_epoch`](KeyProvider::current_epoch), the blob is stamped with it.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with_provider, decrypt_with_provider, CryptographyError, KeyProvider};
+///
+/// struct Token;
+///
+/// impl KeyProvider for Token {
+///     fn provide_key(&self, context: &[u8]) -> Result<[u8; 32], CryptographyError> {
+///         match context {
+///             b"backup-key" => Ok([7u8; 32]),
+///             _ => Err(CryptographyError::KeyNotFound),
+///         }
+///     }
+/// }
+///
+/// let encrypted_data = encrypt_with_provider(b"Hello, world!", &Token, b"backup-key").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_with_provider(&encrypted_data, &Token).expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_with_provider<P: KeyProvider + ?Sized>(
+    data: &[u8],
+    provider: &P,
+    context: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut header = random_header(context)?;
+    header.kdf = Kdf::Provider;
+
+    if let Some(epoch) = provider.current_epoch() {
+        header.set_epoch(epoch);
+    }
+
+    let key = Zeroizing::new(provide_key(provider, &header)?);
+    let ciphertext = seal_with_cipher(&new_cipher(key.as_ref()), &header, data, &[])?;
+
+    Binary.serialize(&header, &ciphertext)
+}
+
+/// Function for decrypting data made by [`encrypt_with_provider`], asking `provider` for the key named in its header.
+/// Blobs stamped with a key rotation epoch get the key of that epoch, even after the provider moved on to a newer one.
+/// Returns a DecodingFailure for blobs encrypted under a password, or IncorrectPassword if the provided key is wrong.
+pub fn decrypt_with_provider<P: KeyProvider + ?Sized>(
+    data: &[u8],
+    provider: &P,
+) -> Result<Vec<u8>, CryptographyError> {
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, ciphertext) = envelope.deserialize(data)?;
+
+    if header.kdf != Kdf::Provider {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let key = Zeroizing::new(provide_key(provider, &header)?);
+
+    open_with_key(key.as_ref(), &header, &ciphertext, &[])
+}
+
+/// Asks `provider` for the key of a blob with `header`, from its epoch if it was stamped with one.
+fn provide_key<P: KeyProvider + ?Sized>(
+    provider: &P,
+    header: &Header,
+) -> Result<[u8; 32], CryptographyError> {
+    match header.epoch {
+        Some(epoch) => provider.provide_epoch_key(header.context_label(), epoch),
+        None => provider.provide_key(header.context_label()),
+    }
+}
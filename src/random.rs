@@ -0,0 +1,42 @@
+//! Source of the random salts & nonces blobs are encrypted with.
+
+#[cfg(feature = "std")]
+use aes_gcm_siv::aead::{rand_core::RngCore, OsRng};
+
+/// Generates the salt and nonce of a blob, see [`encrypt_with_randomness`](crate::encrypt_with_randomness).
+///
+/// Both methods default to the operating system's secure RNG, so an implementation only overrides what it needs to fix,
+/// such as a known salt for a test vector while nonces stay random. Without the `std` feature there's no OS RNG
+/// to default to and both have to be implemented, [`encrypt_with_rng`](crate::encrypt_with_rng) does so for any CSPRNG.
+///
+/// Anything but [`OsRandomness`] is meant for tests & reproducible vectors. A fixed salt lets two blobs share a key,
+/// and a fixed nonce on top of that reveals whether they hold the same plaintext.
+pub trait Randomness {
+    /// Fills `buf` with the salt argon2 derives the key with.
+    #[cfg(feature = "std")]
+    fn fill_salt(&mut self, buf: &mut [u8]) {
+        OsRng.fill_bytes(buf);
+    }
+
+    /// Fills `buf` with the salt argon2 derives the key with.
+    #[cfg(not(feature = "std"))]
+    fn fill_salt(&mut self, buf: &mut [u8]);
+
+    /// Fills `buf` with the AEAD nonce.
+    #[cfg(feature = "std")]
+    fn fill_nonce(&mut self, buf: &mut [u8]) {
+        OsRng.fill_bytes(buf);
+    }
+
+    /// Fills `buf` with the AEAD nonce.
+    #[cfg(not(feature = "std"))]
+    fn fill_nonce(&mut self, buf: &mut [u8]);
+}
+
+/// Randomness from the operating system's secure RNG, what [`encrypt`](crate::encrypt) uses.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRandomness;
+
+#[cfg(feature = "std")]
+impl Randomness for OsRandomness {}
@@ -0,0 +1,70 @@
+//! Sealing with the salt & nonce returned separately, for code that stores them in their own fields.
+//!
+//! The cryptography is exactly that of [`encrypt`](crate::encrypt): the ciphertext is what follows the header in
+//! a version 1 blob, only the salt & nonce are handed back instead of being packed in front of it.
+//! There's no blob to checksum, so the header authenticated along with it is the version 1 one.
+
+use crate::{
+    format::VERSION_NO_CHECKSUM, open as open_blob, random_header, seal as seal_blob, Argon2Params,
+    CipherSuite, Compression, CryptographyError, Header, Kdf,
+};
+
+/// Nonce of a sealed ciphertext.
+pub type Nonce = [u8; 12];
+
+/// Salt the key of a sealed ciphertext is derived with.
+pub type Salt = [u8; 32];
+
+/// Function for encrypting data, returning the ciphertext along with its nonce & salt.
+/// All three are needed to [`open`] it again, none of them are secret.
+///
+/// ```rust
+/// use tinycrypt::raw::{seal, open};
+///
+/// let (ciphertext, nonce, salt) = seal(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = open(&ciphertext, &nonce, &salt, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn seal(
+    plaintext: &[u8],
+    password: &[u8],
+) -> Result<(Vec<u8>, Nonce, Salt), CryptographyError> {
+    let mut header = random_header(crate::DEFAULT_CONTEXT)?;
+    header.version = VERSION_NO_CHECKSUM;
+    let ciphertext = seal_blob(&header, plaintext, password)?;
+
+    let nonce = header
+        .nonce
+        .try_into()
+        .map_err(|_| CryptographyError::EncodingFailure)?;
+
+    Ok((ciphertext, nonce, header.salt))
+}
+
+/// Function for decrypting a ciphertext made by [`seal`] with its nonce & salt.
+/// Returns an IncorrectPassword if the password, nonce or salt is wrong, or the ciphertext was modified.
+pub fn open(
+    ciphertext: &[u8],
+    nonce: &Nonce,
+    salt: &Salt,
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let header = Header {
+        version: VERSION_NO_CHECKSUM,
+        salt: *salt,
+        nonce: nonce.to_vec(),
+        context: None,
+        created: None,
+        kdf: Kdf::current(),
+        params: Argon2Params::default(),
+        minor: 0,
+        codec: 0,
+        epoch: None,
+        verifier: None,
+        suite: CipherSuite::Aes256GcmSiv,
+        compression: Compression::None,
+        extra: Vec::new(),
+    };
+
+    open_blob(&header, ciphertext, password)
+}
@@ -0,0 +1,66 @@
+//! Rollback protection through a version counter bound into the blob.
+//!
+//! The version is stored in the KDF context label, so it's part of the authenticated header and the key derivation.
+//! Changing it makes the blob fail to decrypt, and [`decrypt`](crate::decrypt) still opens versioned blobs as usual.
+
+use crate::{decrypt, encrypt_with_context, envelope, is_legacy, CryptographyError};
+
+/// Context label prefix of versioned blobs, followed by the version as a u64 LE.
+const VERSIONED_CONTEXT: &[u8] = b"tinycrypt-v1-versioned";
+
+/// Function for encrypting data bound to a `version` counter, which [`decrypt_min_version`] checks against a minimum.
+/// Bump the version every time the data is replaced, so an older blob can't be passed off as the current one.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_versioned, decrypt_min_version, CryptographyError};
+///
+/// let old = encrypt_versioned(b"retries = 3", b"password", 1).expect("Failed to encrypt!");
+/// let current = encrypt_versioned(b"retries = 5", b"password", 2).expect("Failed to encrypt!");
+///
+/// let config = decrypt_min_version(&current, b"password", 2).expect("Failed to decrypt data!");
+/// assert_eq!(decrypt_min_version(&old, b"password", 2), Err(CryptographyError::RollbackDetected));
+/// ```
+pub fn encrypt_versioned(
+    data: &[u8],
+    password: &[u8],
+    version: u64,
+) -> Result<Vec<u8>, CryptographyError> {
+    let context = [VERSIONED_CONTEXT, &version.to_le_bytes()].concat();
+
+    encrypt_with_context(data, password, &context)
+}
+
+/// Function for decrypting a blob made by [`encrypt_versioned`] only if its version is at least `min_version`.
+/// Returns RollbackDetected for older blobs before deriving the key, or a DecodingFailure for blobs without a version.
+/// Keeping track of the newest version seen, e.g. with [`blob_version`], is up to the caller.
+pub fn decrypt_min_version(
+    data: &[u8],
+    password: &[u8],
+    min_version: u64,
+) -> Result<Vec<u8>, CryptographyError> {
+    if blob_version(data)? < min_version {
+        return Err(CryptographyError::RollbackDetected);
+    }
+
+    decrypt(data, password)
+}
+
+/// Function for reading the version of a blob made by [`encrypt_versioned`], no password needed.
+/// Returns a DecodingFailure if the blob has no version or isn't valid.
+/// The version is only proven authentic once the blob has been decrypted.
+pub fn blob_version(data: &[u8]) -> Result<u64, CryptographyError> {
+    if is_legacy(data) {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(data)?;
+
+    let version = header
+        .context_label()
+        .strip_prefix(VERSIONED_CONTEXT)
+        .and_then(|version| <[u8; 8]>::try_from(version).ok())
+        .ok_or(CryptographyError::DecodingFailure)?;
+
+    Ok(u64::from_le_bytes(version))
+}
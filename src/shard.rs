@@ -0,0 +1,193 @@
+//! Shamir secret sharing of whole blobs.
+//!
+//! ```text
+//! magic      4 bytes   b"TCSH"
+//! version    1 byte    1
+//! set id     8 bytes   random, identical for every shard of one split
+//! threshold  1 byte    k, shards needed to reconstruct
+//! count      1 byte    n, shards created
+//! index      1 byte    x coordinate of this shard, 1..=n
+//! share      the rest, one byte per byte of the blob
+//! ```
+//!
+//! Every byte of the blob is split independently over GF(2^8), so fewer than `k` shards reveal nothing about it.
+
+use crate::CryptographyError;
+use aes_gcm_siv::aead::{rand_core::RngCore, OsRng};
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 4] = b"TCSH";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 3;
+
+/// Function for splitting a blob into `n` shards, any `k` of which reconstruct it with [`combine_blob`].
+/// Each shard is as long as the blob plus a 16 byte header, and fewer than `k` shards reveal nothing about the blob.
+/// Returns an EncodingFailure unless `1 <= k <= n`.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, split_blob, combine_blob};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let shards = split_blob(&encrypted_data, 5, 3).expect("Failed to split!");
+///
+/// let combined = combine_blob(&[&shards[4], &shards[0], &shards[2]]).expect("Failed to combine!");
+/// assert_eq!(combined, encrypted_data);
+/// ```
+pub fn split_blob(blob: &[u8], n: u8, k: u8) -> Result<Vec<Vec<u8>>, CryptographyError> {
+    if k == 0 || k > n {
+        return Err(CryptographyError::EncodingFailure);
+    }
+
+    let mut id = [0u8; 8];
+    OsRng.fill_bytes(&mut id);
+
+    let mut shards: Vec<Vec<u8>> = (1..=n)
+        .map(|x| {
+            let mut shard = Vec::with_capacity(HEADER_LEN + blob.len());
+            shard.extend_from_slice(MAGIC);
+            shard.push(VERSION);
+            shard.extend_from_slice(&id);
+            shard.extend_from_slice(&[k, n, x]);
+
+            shard
+        })
+        .collect();
+
+    // coefficients[0] is the secret byte, the rest are random.
+    let mut coefficients = Zeroizing::new(vec![0u8; k as usize]);
+
+    for &byte in blob {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+
+        for (shard, x) in shards.iter_mut().zip(1..=n) {
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0, |acc, &coefficient| mul(acc, x) ^ coefficient);
+
+            shard.push(y);
+        }
+    }
+
+    Ok(shards)
+}
+
+/// Function for reconstructing a blob from shards made by [`split_blob`].
+/// Any `k` shards of the same split are enough, extra shards are ignored.
+/// Returns a DecodingFailure if there are fewer than `k` distinct shards or they don't belong to the same split.
+pub fn combine_blob<S: AsRef<[u8]>>(shards: &[S]) -> Result<Vec<u8>, CryptographyError> {
+    let first = parse(
+        shards
+            .first()
+            .ok_or(CryptographyError::DecodingFailure)?
+            .as_ref(),
+    )?;
+    let mut chosen: Vec<Shard> = Vec::with_capacity(first.threshold as usize);
+
+    for shard in shards {
+        let shard = parse(shard.as_ref())?;
+
+        if shard.id != first.id
+            || shard.threshold != first.threshold
+            || shard.count != first.count
+            || shard.share.len() != first.share.len()
+        {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        if chosen.len() < first.threshold as usize && chosen.iter().all(|c| c.index != shard.index)
+        {
+            chosen.push(shard);
+        }
+    }
+
+    if chosen.len() < first.threshold as usize {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    // Lagrange basis polynomials evaluated at x = 0.
+    let weights: Vec<u8> = chosen
+        .iter()
+        .map(|i| {
+            chosen
+                .iter()
+                .filter(|j| j.index != i.index)
+                .fold(1, |acc, j| {
+                    mul(acc, mul(j.index, inverse(j.index ^ i.index)))
+                })
+        })
+        .collect();
+
+    let blob = (0..first.share.len())
+        .map(|position| {
+            chosen
+                .iter()
+                .zip(&weights)
+                .fold(0, |acc, (shard, &weight)| {
+                    acc ^ mul(shard.share[position], weight)
+                })
+        })
+        .collect();
+
+    Ok(blob)
+}
+
+struct Shard<'a> {
+    id: &'a [u8],
+    threshold: u8,
+    count: u8,
+    index: u8,
+    share: &'a [u8],
+}
+
+fn parse(data: &[u8]) -> Result<Shard<'_>, CryptographyError> {
+    if data.len() < HEADER_LEN || !data.starts_with(MAGIC) || data[4] != VERSION {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let shard = Shard {
+        id: &data[5..13],
+        threshold: data[13],
+        count: data[14],
+        index: data[15],
+        share: &data[HEADER_LEN..],
+    };
+
+    if shard.threshold == 0
+        || shard.threshold > shard.count
+        || shard.index == 0
+        || shard.index > shard.count
+    {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    Ok(shard)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial, without secret dependent branches.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7));
+        b >>= 1;
+    }
+
+    product
+}
+
+/// Multiplicative inverse in GF(2^8), `a^254`.
+fn inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+
+    for _ in 0..7 {
+        power = mul(power, power);
+        result = mul(result, power);
+    }
+
+    result
+}
@@ -0,0 +1,129 @@
+//! Detached authentication tags over whole blobs, and password verifiers inside headers.
+//!
+//! The tag is an HMAC-SHA256 of the blob under a key derived from the password with the blob's own salt & argon2
+//! parameters but a separate context label, so it can never be mistaken for, or reveal, the blob's encryption key.
+//!
+//! A verifier is an HMAC-SHA256 of the header (without the verifier) under a key expanded from the blob's key
+//! with HKDF-SHA256, so checking it costs the same single key derivation as decrypting.
+
+use crate::{derive_key, envelope, format::MINOR_VERIFIER, CipherSuite, CryptographyError, Header};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// KDF context label of detached tags.
+const SIGN_CONTEXT: &[u8] = b"tinycrypt-v1-sign";
+
+/// HKDF info the verifier key is expanded from the blob's key with.
+const VERIFIER_INFO: &[u8] = b"tinycrypt-v1-verifier";
+
+/// Length of a detached tag in bytes.
+pub const SIGNATURE_LEN: usize = 32;
+
+/// Function for producing a detached tag authenticating an encrypted blob, to be stored or served separately from it.
+/// Anyone holding the password can check the blob is unmodified with [`verify_detached`], without decrypting it.
+///
+/// This is a MAC, not a public key signature: everyone able to verify it can also produce one.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, sign_detached, verify_detached};
+///
+/// let encrypted_data = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+/// let signature = sign_detached(&encrypted_data, b"password").expect("Failed to sign!");
+///
+/// verify_detached(&encrypted_data, &signature, b"password").expect("Blob was modified!");
+/// ```
+pub fn sign_detached(blob: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    Ok(mac(blob, password)?.finalize().into_bytes().to_vec())
+}
+
+/// Function for checking a tag made by [`sign_detached`].
+/// Returns IncorrectPassword if the password is wrong or either the blob or the tag was modified,
+/// or a DecodingFailure if the blob no longer parses (or fails its checksum) at all.
+pub fn verify_detached(
+    blob: &[u8],
+    signature: &[u8],
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    if signature.len() != SIGNATURE_LEN {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    mac(blob, password)?
+        .verify_slice(signature)
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
+
+fn mac(blob: &[u8], password: &[u8]) -> Result<Hmac<Sha256>, CryptographyError> {
+    let envelope = envelope::detect(blob).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(blob)?;
+
+    let key = derive_key(
+        password,
+        &header.salt,
+        SIGN_CONTEXT,
+        header.kdf,
+        &header.params,
+        CipherSuite::Aes256GcmSiv,
+    )?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key)
+        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
+    mac.update(blob);
+
+    Ok(mac)
+}
+
+/// Function for checking `password` against a blob made with [`EncryptOptions::verifier`](crate::EncryptOptions::verifier)
+/// using only its header, such as the first few hundred bytes fetched from object storage.
+/// Returns IncorrectPassword if the password is wrong or the header was modified, or a DecodingFailure if
+/// `header` doesn't start with a complete binary header or the blob has no verifier.
+///
+/// Only the header is checked, the rest of the blob is authenticated once it's decrypted. To check a whole blob see
+/// [`verify_password`](crate::verify_password).
+///
+/// ```rust
+/// use tinycrypt::{encrypt_with, verify_header, CryptographyError, EncryptOptions};
+///
+/// let encrypted_data = encrypt_with(b"Hello, world!", b"password", &EncryptOptions::new().verifier())
+///     .expect("Failed to encrypt!");
+///
+/// verify_header(&encrypted_data[..128], b"password").expect("Wrong password!");
+/// assert_eq!(verify_header(&encrypted_data[..128], b"wrong"), Err(CryptographyError::IncorrectPassword));
+/// ```
+pub fn verify_header(header: &[u8], password: &[u8]) -> Result<(), CryptographyError> {
+    let (header, _) = Header::parse(header)?;
+    let verifier = header.verifier.ok_or(CryptographyError::DecodingFailure)?;
+    let key = header.derive_key(password)?;
+
+    verifier_mac(&header, &key)?
+        .verify_slice(&verifier)
+        .map_err(|_| CryptographyError::IncorrectPassword)
+}
+
+/// Adds a verifier made with the blob's `key` to `header`, raising its minor version as needed.
+pub(crate) fn add_verifier(header: &mut Header, key: &[u8]) -> Result<(), CryptographyError> {
+    header.minor = header.minor.max(MINOR_VERIFIER);
+    header.verifier = Some(verifier_mac(header, key)?.finalize().into_bytes().into());
+
+    Ok(())
+}
+
+fn verifier_mac(header: &Header, key: &[u8]) -> Result<Hmac<Sha256>, CryptographyError> {
+    let mut verifier_key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, key)
+        .expand(VERIFIER_INFO, verifier_key.as_mut())
+        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
+
+    let unverified = Header {
+        verifier: None,
+        ..header.clone()
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(verifier_key.as_ref())
+        .map_err(|_| CryptographyError::KeyGenerationFailure)?;
+    mac.update(&unverified.encode()?);
+
+    Ok(mac)
+}
@@ -0,0 +1,46 @@
+//! Blobs stored as two parts, the header in one place and the ciphertext in another.
+//!
+//! The parts are exactly the two halves of a regular [`Binary`](crate::envelope::Binary) blob, so concatenating them
+//! gives a blob [`decrypt`](crate::decrypt) accepts, and the checksum still covers both.
+
+use crate::{encrypt, format::verify_split_checksum, open, CryptographyError, Header, TAG_SIZE};
+
+/// Function for encrypting data into a header and a ciphertext to be stored separately, e.g. in a metadata database
+/// and an object store. Decrypt them with [`decrypt_parts`].
+///
+/// ```rust
+/// use tinycrypt::{encrypt_split, decrypt_parts};
+///
+/// let (header, ciphertext) = encrypt_split(b"Hello, world!", b"password").expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_parts(&header, &ciphertext, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_split(
+    data: &[u8],
+    password: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), CryptographyError> {
+    let mut header = encrypt(data, password)?;
+    let (_, header_len) = Header::parse(&header)?;
+    let ciphertext = header.split_off(header_len);
+
+    Ok((header, ciphertext))
+}
+
+/// Function for decrypting a blob from its separately stored header and ciphertext, see [`encrypt_split`].
+/// `header` must be exactly the header, and both parts are checked (including the checksum) before the key is derived,
+/// returning a DecodingFailure if either is malformed or the ciphertext is too short to hold a tag.
+pub fn decrypt_parts(
+    header: &[u8],
+    ciphertext: &[u8],
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let (parsed, header_len) = Header::parse(header)?;
+
+    if header_len != header.len() || ciphertext.len() < TAG_SIZE {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    verify_split_checksum(header, ciphertext)?;
+
+    open(&parsed, ciphertext, password)
+}
@@ -0,0 +1,1359 @@
+//! Chunked streaming encryption.
+//!
+//! A stream starts with a small header (salt, nonce prefix & chunk size) followed by a series of frames,
+//! each holding one encrypted chunk:
+//!
+//! ```text
+//! header
+//!   salt           32 bytes
+//!   nonce prefix    7 bytes   STREAM nonce prefix, followed by a u32 BE chunk counter & a last chunk flag
+//!   chunk size      4 bytes   u32 LE, plaintext bytes per chunk, at most MAX_CHUNK_SIZE
+//!   flags           1 byte    bit 0 declared length follows, bit 1 insecure test KDF,
+//!                             bit 2 associated header follows, bit 3 compression follows (all others zero)
+//!   length          8 bytes   u64 LE, only with bit 0 set
+//!   compression     1 byte    compression id as in a blob header, only with bit 3 set (never together with bit 0)
+//!   associated len  4 bytes   u32 LE, at most MAX_ASSOCIATED_HEADER_LEN, only with bit 2 set
+//!   associated      plaintext metadata, only with bit 2 set
+//! frames, repeated
+//!   frame length    4 bytes   u32 LE, at most chunk size + 16, a little more for compressed chunks
+//!   chunk           ciphertext followed by the 16 byte tag
+//! ```
+//!
+//! The last frame is sealed as the final STREAM chunk, and the whole header is the associated data of every chunk.
+//! It's always present, so empty data encrypts to the header and a single empty final chunk, and a stream cut off right after its header is rejected.
+//! Chunks are sealed with the STREAM construction, so reordering,
+//! dropping or truncating chunks is detected on decryption. The header is authenticated along with every chunk.
+//!
+//! # Truncation
+//!
+//! Whether a chunk is the last one is part of its nonce, so it's authenticated and can't be changed without the key.
+//! A stream cut off anywhere past its header, whether between frames or inside one, never decrypts successfully:
+//! [`StreamingDecryptor::finish`] (and everything built on it) returns Truncated, as no final chunk was seen.
+//! Chunks before the cut may already have been returned, they're authentic but the data is only complete once
+//! decryption finishes without an error. A stream cut off inside its header isn't recognised as one at all and
+//! returns a DecodingFailure. `tests/properties.rs` checks this for random streams cut at random offsets.
+//!
+//! The key is derived once per stream, so memory use stays bounded by the chunk size no matter how large the data is.
+//!
+//! If the total plaintext length is declared up front with [`StreamingEncryptor::with_plaintext_len`], it's stored in the
+//! header and every chunk, including the last, is padded to the full chunk size. Decryption strips the padding and checks
+//! the reconstructed length matches exactly.
+//!
+//! [`encrypt_stream`] & [`decrypt_stream`] drive the same machinery from any [`Read`] into any [`Write`],
+//! with cancellable variants for long running jobs. [`EncryptingWriter`] wraps a [`Write`] directly and
+//! [`DecryptingReader`] a [`Read`], while [`decrypt_streaming`] yields the authenticated chunks of a stream one at a time.
+//!
+//! [`StreamingEncryptor::with_associated_header`] puts metadata such as a table of contents in the clear at the front
+//! of the stream. Being part of the header it's authenticated with every chunk, so [`read_associated_header`] only needs
+//! the first chunk to return it verified.
+//!
+//! [`StreamingEncryptor::with_compression`] compresses every chunk on its own before sealing it, so decompressing
+//! never needs more than one chunk and no chunk can expand past the chunk size.
+//!
+//! [`convert_to_streaming`] & [`convert_to_single`] migrate data between regular blobs and streams,
+//! and [`rekey_stream`] changes a stream's password.
+//!
+//! # Async
+//!
+//! [`StreamingEncryptor`] & [`StreamingDecryptor`] never do IO themselves, only [`push`](StreamingEncryptor::push)
+//! and `finish` byte slices, so they can be driven from any async reader & writer. All argon2 work happens in
+//! [`StreamingEncryptor::new`] and in the [`StreamingDecryptor::push`] that completes the header, which are the calls
+//! to run on a blocking pool. Sealing or opening a chunk is cheap enough to do between awaits.
+//!
+//! With the `async` feature, [`async_io`] does this for tokio: async versions of [`encrypt_stream`] & [`decrypt_stream`]
+//! and [`AsyncRead`](::tokio::io::AsyncRead)/[`AsyncWrite`](::tokio::io::AsyncWrite) adapters, deriving keys through
+//! `spawn_blocking`.
+
+use crate::{
+    decrypt, derive_key, encrypt, Argon2Params, CipherSuite, Compression, CryptographyError, Kdf,
+    DEFAULT_CONTEXT,
+};
+use aes_gcm_siv::{
+    aead::{
+        generic_array::GenericArray,
+        rand_core::RngCore,
+        stream::{EncryptorBE32, NewStream, StreamBE32, StreamPrimitive},
+        OsRng, Payload,
+    },
+    Aes256GcmSiv, KeyInit,
+};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use zeroize::{Zeroize, Zeroizing};
+
+#[cfg(feature = "async")]
+pub mod async_io;
+
+/// Default amount of plaintext sealed in a single chunk (64 KiB).
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size accepted when reading a stream header (16 MiB).
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+const TAG_SIZE: usize = 16;
+
+/// Largest associated header a stream can carry (16 MiB).
+pub const MAX_ASSOCIATED_HEADER_LEN: usize = 16 * 1024 * 1024;
+
+/// Size of the little-endian length in front of every frame.
+const FRAME_LEN_SIZE: usize = 4;
+
+struct StreamHeader {
+    salt: [u8; 32],
+    nonce: [u8; 7],
+    chunk_size: u32,
+    plaintext_len: Option<u64>,
+    kdf: Kdf,
+    associated: Option<Vec<u8>>,
+    compression: Compression,
+}
+
+/// Header size without a plaintext length, up to and including the flags.
+const MIN_HEADER_SIZE: usize = 32 + 7 + 4 + 1;
+
+const FLAG_PLAINTEXT_LEN: u8 = 1;
+const FLAG_INSECURE_KDF: u8 = 2;
+const FLAG_ASSOCIATED_HEADER: u8 = 4;
+const FLAG_COMPRESSION: u8 = 8;
+
+impl StreamHeader {
+    fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+
+        if self.plaintext_len.is_some() {
+            flags |= FLAG_PLAINTEXT_LEN;
+        }
+
+        if !self.kdf.is_argon2() {
+            flags |= FLAG_INSECURE_KDF;
+        }
+
+        if self.associated.is_some() {
+            flags |= FLAG_ASSOCIATED_HEADER;
+        }
+
+        if !self.compression.is_none() {
+            flags |= FLAG_COMPRESSION;
+        }
+
+        let mut encoded = Vec::with_capacity(MIN_HEADER_SIZE + 8);
+        encoded.extend_from_slice(&self.salt);
+        encoded.extend_from_slice(&self.nonce);
+        encoded.extend_from_slice(&self.chunk_size.to_le_bytes());
+        encoded.push(flags);
+
+        if let Some(length) = self.plaintext_len {
+            encoded.extend_from_slice(&length.to_le_bytes());
+        }
+
+        if !self.compression.is_none() {
+            encoded.push(self.compression.id());
+        }
+
+        // Its length was checked by with_associated_header.
+        if let Some(associated) = &self.associated {
+            encoded.extend_from_slice(&(associated.len() as u32).to_le_bytes());
+            encoded.extend_from_slice(associated);
+        }
+
+        encoded
+    }
+
+    /// Size of the header starting `data`, None if the flags (or the associated header's length) aren't buffered yet.
+    fn encoded_len(data: &[u8]) -> Result<Option<usize>, CryptographyError> {
+        let Some(&flags) = data.get(MIN_HEADER_SIZE - 1) else {
+            return Ok(None);
+        };
+
+        let known =
+            FLAG_PLAINTEXT_LEN | FLAG_INSECURE_KDF | FLAG_ASSOCIATED_HEADER | FLAG_COMPRESSION;
+        let padded_and_compressed = FLAG_PLAINTEXT_LEN | FLAG_COMPRESSION;
+
+        // Compressed chunks aren't padded, so they can't have a declared length.
+        if flags & !known != 0 || flags & padded_and_compressed == padded_and_compressed {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let mut length = MIN_HEADER_SIZE;
+
+        if flags & FLAG_PLAINTEXT_LEN != 0 {
+            length += 8;
+        }
+
+        if flags & FLAG_COMPRESSION != 0 {
+            length += 1;
+        }
+
+        if flags & FLAG_ASSOCIATED_HEADER != 0 {
+            let Some(associated_len) = data.get(length..length + 4) else {
+                return Ok(None);
+            };
+            let associated_len = u32::from_le_bytes(to_array(associated_len)?) as usize;
+
+            if associated_len > MAX_ASSOCIATED_HEADER_LEN {
+                return Err(CryptographyError::DecodingFailure);
+            }
+
+            length += 4 + associated_len;
+        }
+
+        Ok(Some(length))
+    }
+
+    /// Decodes a header of exactly [`encoded_len`](Self::encoded_len) bytes.
+    fn decode(data: &[u8]) -> Result<Self, CryptographyError> {
+        let array = |range: std::ops::Range<usize>| {
+            data.get(range).ok_or(CryptographyError::DecodingFailure)
+        };
+        let flags = data[MIN_HEADER_SIZE - 1];
+
+        let plaintext_len = if flags & FLAG_PLAINTEXT_LEN != 0 {
+            Some(u64::from_le_bytes(to_array(array(
+                MIN_HEADER_SIZE..MIN_HEADER_SIZE + 8,
+            )?)?))
+        } else {
+            None
+        };
+
+        let compression_at = MIN_HEADER_SIZE + if plaintext_len.is_some() { 8 } else { 0 };
+        let compression = match data.get(compression_at) {
+            _ if flags & FLAG_COMPRESSION == 0 => Compression::None,
+            // No compression is never written out.
+            Some(&id) if id != 0 => Compression::from_id(id)?,
+            _ => return Err(CryptographyError::DecodingFailure),
+        };
+
+        // The associated header takes up the rest, its length was checked by encoded_len.
+        let associated_start = compression_at + if compression.is_none() { 0 } else { 1 } + 4;
+        let associated = if flags & FLAG_ASSOCIATED_HEADER != 0 {
+            Some(array(associated_start..data.len())?.to_vec())
+        } else {
+            None
+        };
+
+        Ok(Self {
+            salt: to_array(array(0..32)?)?,
+            nonce: to_array(array(32..39)?)?,
+            chunk_size: u32::from_le_bytes(to_array(array(39..43)?)?),
+            plaintext_len,
+            kdf: if flags & FLAG_INSECURE_KDF != 0 {
+                Kdf::InsecureSha256
+            } else {
+                Kdf::Argon2
+            },
+            associated,
+            compression,
+        })
+    }
+}
+
+/// Incremental stream encryptor.
+///
+/// Feed plaintext with [`push`](Self::push) and send whatever bytes it returns, then call [`finish`](Self::finish)
+/// for the tail of the stream. Nothing blocks and nothing is read on its own, so it can be driven from any event loop.
+/// At most one chunk of plaintext is buffered internally at any time.
+///
+/// ```rust
+/// use tinycrypt::stream::{StreamingEncryptor, StreamingDecryptor};
+///
+/// let mut encryptor = StreamingEncryptor::new(b"password").unwrap();
+///
+/// let mut encrypted = encryptor.push(b"Hello, ").unwrap();
+/// encrypted.extend(encryptor.push(b"world!").unwrap());
+/// encrypted.extend(encryptor.finish().unwrap());
+///
+/// let mut decryptor = StreamingDecryptor::new(b"password");
+///
+/// let mut decrypted = decryptor.push(&encrypted).unwrap();
+/// decrypted.extend(decryptor.finish().unwrap());
+///
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub struct StreamingEncryptor {
+    encryptor: EncryptorBE32<Aes256GcmSiv>,
+    header: StreamHeader,
+    encoded_header: Vec<u8>,
+    header_sent: bool,
+    /// Plaintext of the next chunk, wiped when the encryptor is dropped.
+    buffer: Zeroizing<Vec<u8>>,
+    chunk_size: usize,
+    pushed: u64,
+}
+
+impl StreamingEncryptor {
+    /// Creates an encryptor using [`DEFAULT_CHUNK_SIZE`].
+    pub fn new(password: &[u8]) -> Result<Self, CryptographyError> {
+        Self::with_chunk_size(password, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates an encryptor sealing `chunk_size` bytes of plaintext per chunk.
+    /// Returns an EncodingFailure if `chunk_size` is zero or larger than [`MAX_CHUNK_SIZE`].
+    pub fn with_chunk_size(password: &[u8], chunk_size: usize) -> Result<Self, CryptographyError> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(CryptographyError::EncodingFailure);
+        }
+
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+
+        let mut nonce = [0u8; 7];
+        OsRng.fill_bytes(&mut nonce);
+
+        let header = StreamHeader {
+            salt,
+            nonce,
+            chunk_size: chunk_size as u32,
+            plaintext_len: None,
+            kdf: Kdf::current(),
+            associated: None,
+            compression: Compression::None,
+        };
+        let encoded_header = header.encode();
+
+        let key = derive_key(
+            password,
+            &salt,
+            DEFAULT_CONTEXT,
+            header.kdf,
+            &Argon2Params::default(),
+            CipherSuite::Aes256GcmSiv,
+        )?;
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key));
+
+        Ok(Self {
+            encryptor: EncryptorBE32::from_aead(cipher, GenericArray::from_slice(&nonce)),
+            header,
+            encoded_header,
+            header_sent: false,
+            buffer: Zeroizing::new(Vec::with_capacity(chunk_size)),
+            chunk_size,
+            pushed: 0,
+        })
+    }
+
+    /// Declares the exact number of plaintext bytes the stream will hold.
+    /// The length is stored in the authenticated header and the final chunk is padded to the full chunk size,
+    /// so chunk sizes reveal nothing about where the data ends.
+    ///
+    /// Must be called before the first [`push`](Self::push) and can't be combined with
+    /// [`with_compression`](Self::with_compression). Pushing more than `plaintext_len` bytes,
+    /// or finishing with fewer, returns an EncodingFailure.
+    ///
+    /// ```rust
+    /// use tinycrypt::stream::{StreamingEncryptor, StreamingDecryptor};
+    ///
+    /// let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", 16)
+    ///     .unwrap()
+    ///     .with_plaintext_len(13)
+    ///     .unwrap();
+    ///
+    /// let mut encrypted = encryptor.push(b"Hello, world!").unwrap();
+    /// encrypted.extend(encryptor.finish().unwrap());
+    ///
+    /// let mut decryptor = StreamingDecryptor::new(b"password");
+    ///
+    /// let mut decrypted = decryptor.push(&encrypted).unwrap();
+    /// decrypted.extend(decryptor.finish().unwrap());
+    ///
+    /// assert_eq!(decrypted, b"Hello, world!");
+    /// ```
+    pub fn with_plaintext_len(mut self, plaintext_len: u64) -> Result<Self, CryptographyError> {
+        if self.header_sent || !self.header.compression.is_none() {
+            return Err(CryptographyError::EncodingFailure);
+        }
+
+        self.header.plaintext_len = Some(plaintext_len);
+        self.encoded_header = self.header.encode();
+
+        Ok(self)
+    }
+
+    /// Stores `associated_header` in the clear at the front of the stream, authenticated along with every chunk,
+    /// so it can be read back with [`read_associated_header`] or [`StreamingDecryptor::associated_header`] but not altered.
+    /// Use it for metadata such as a table of contents, it's readable by anyone without the password.
+    ///
+    /// Must be called before the first [`push`](Self::push). Returns an EncodingFailure if it's been called too late,
+    /// or the header is longer than [`MAX_ASSOCIATED_HEADER_LEN`].
+    ///
+    /// ```rust
+    /// use tinycrypt::stream::{read_associated_header, StreamingEncryptor};
+    ///
+    /// let mut encryptor = StreamingEncryptor::new(b"password")
+    ///     .unwrap()
+    ///     .with_associated_header(b"notes.txt,todo.txt")
+    ///     .unwrap();
+    ///
+    /// let mut encrypted = encryptor.push(b"Hello, world!").unwrap();
+    /// encrypted.extend(encryptor.finish().unwrap());
+    ///
+    /// let table_of_contents = read_associated_header(&encrypted[..], b"password").unwrap();
+    /// assert_eq!(table_of_contents, b"notes.txt,todo.txt");
+    /// ```
+    pub fn with_associated_header(
+        mut self,
+        associated_header: &[u8],
+    ) -> Result<Self, CryptographyError> {
+        if self.header_sent || associated_header.len() > MAX_ASSOCIATED_HEADER_LEN {
+            return Err(CryptographyError::EncodingFailure);
+        }
+
+        self.header.associated = Some(associated_header.to_vec());
+        self.encoded_header = self.header.encode();
+
+        Ok(self)
+    }
+
+    /// Compresses every chunk on its own before sealing it, see [`EncryptOptions::compression`](crate::EncryptOptions::compression).
+    /// The decryptor reads the compression from the stream's header. Chunk sizes then depend on the plaintext's content.
+    ///
+    /// Must be called before the first [`push`](Self::push). Returns an EncodingFailure if it's been called too late,
+    /// or along with [`with_plaintext_len`](Self::with_plaintext_len), whose padding compression would defeat.
+    pub fn with_compression(mut self, compression: Compression) -> Result<Self, CryptographyError> {
+        if self.header_sent || self.header.plaintext_len.is_some() {
+            return Err(CryptographyError::EncodingFailure);
+        }
+
+        self.header.compression = compression;
+        self.encoded_header = self.header.encode();
+
+        Ok(self)
+    }
+
+    /// Number of plaintext bytes buffered and waiting for a full chunk.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Adds plaintext to the stream, returning any encrypted bytes that are ready to be sent.
+    /// The returned vector may be empty if not enough input has accumulated yet.
+    pub fn push(&mut self, mut input: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        self.pushed += input.len() as u64;
+
+        if matches!(self.header.plaintext_len, Some(length) if self.pushed > length) {
+            return Err(CryptographyError::EncodingFailure);
+        }
+
+        let mut output = self.take_header();
+
+        while !input.is_empty() {
+            // More input follows, so a full buffer can never be the last chunk.
+            if self.buffer.len() == self.chunk_size {
+                let compressed = compress_chunk(self.header.compression, &self.buffer)?;
+                let chunk = self
+                    .encryptor
+                    .encrypt_next(Payload {
+                        msg: compressed.as_deref().unwrap_or(&self.buffer),
+                        aad: &self.encoded_header,
+                    })
+                    .map_err(|_| CryptographyError::EncodingFailure)?;
+
+                write_frame(&mut output, &chunk)?;
+                self.buffer.clear();
+            }
+
+            let take = (self.chunk_size - self.buffer.len()).min(input.len());
+            self.buffer.extend_from_slice(&input[..take]);
+            input = &input[take..];
+        }
+
+        Ok(output)
+    }
+
+    /// Seals the remaining buffered plaintext as the final chunk and returns the tail of the stream.
+    pub fn finish(mut self) -> Result<Vec<u8>, CryptographyError> {
+        if let Some(length) = self.header.plaintext_len {
+            if self.pushed != length {
+                return Err(CryptographyError::EncodingFailure);
+            }
+
+            self.buffer.resize(self.chunk_size, 0);
+        }
+
+        let mut output = self.take_header();
+
+        let compressed = compress_chunk(self.header.compression, &self.buffer)?;
+        let chunk = self
+            .encryptor
+            .encrypt_last(Payload {
+                msg: compressed.as_deref().unwrap_or(&self.buffer),
+                aad: &self.encoded_header,
+            })
+            .map_err(|_| CryptographyError::EncodingFailure)?;
+
+        write_frame(&mut output, &chunk)?;
+
+        Ok(output)
+    }
+
+    fn take_header(&mut self) -> Vec<u8> {
+        if self.header_sent {
+            Vec::new()
+        } else {
+            self.header_sent = true;
+            self.encoded_header.clone()
+        }
+    }
+}
+
+/// Incremental stream decryptor, the counterpart of [`StreamingEncryptor`].
+///
+/// Feed encrypted bytes with [`push`](Self::push) as they arrive and call [`finish`](Self::finish) once the input ends.
+/// Plaintext is only ever returned after the chunk holding it has been authenticated.
+/// The final chunk is held back until `finish`, as only then is it known to be the last one.
+pub struct StreamingDecryptor {
+    password: Zeroizing<Vec<u8>>,
+    stream: Option<StreamBE32<Aes256GcmSiv>>,
+    /// STREAM counter of the next chunk.
+    position: u32,
+    header: Vec<u8>,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+    plaintext_len: Option<u64>,
+    decrypted: u64,
+    associated: Option<Vec<u8>>,
+    compression: Compression,
+    /// Whether a chunk, and with it the header, has been authenticated.
+    authenticated: bool,
+}
+
+impl StreamingDecryptor {
+    /// Creates a decryptor, the key is derived once the stream header has been received.
+    pub fn new(password: &[u8]) -> Self {
+        Self {
+            password: Zeroizing::new(password.to_vec()),
+            stream: None,
+            position: 0,
+            header: Vec::new(),
+            buffer: Vec::new(),
+            chunk_size: 0,
+            plaintext_len: None,
+            decrypted: 0,
+            associated: None,
+            compression: Compression::None,
+            authenticated: false,
+        }
+    }
+
+    /// The stream's associated header (see [`StreamingEncryptor::with_associated_header`]), once it's been authenticated
+    /// along with the first chunk. None before that, or if the stream has none.
+    pub fn associated_header(&self) -> Option<&[u8]> {
+        self.associated.as_deref().filter(|_| self.authenticated)
+    }
+
+    /// Adds encrypted bytes to the stream, returning any plaintext that has been authenticated.
+    pub fn push(&mut self, input: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+        self.buffer.extend_from_slice(input);
+
+        let mut output = Vec::new();
+        let mut position = 0;
+
+        while let Some((chunk, end)) = self.next_chunk(position)? {
+            output.extend_from_slice(&chunk);
+            position = end;
+        }
+
+        self.buffer.drain(..position);
+
+        Ok(output)
+    }
+
+    /// Authenticates the buffered frame starting at `position` and returns its plaintext along with the frame's end.
+    /// Returns None until the whole frame, and at least one byte after it, has been buffered.
+    fn next_chunk(
+        &mut self,
+        position: usize,
+    ) -> Result<Option<(Vec<u8>, usize)>, CryptographyError> {
+        if self.stream.is_none() && !self.read_header()? {
+            return Ok(None);
+        }
+
+        // A frame is only known not to be the last one once bytes of the next frame have arrived.
+        let end = match self.frame_end(position)? {
+            Some(end) if end < self.buffer.len() => end,
+            _ => return Ok(None),
+        };
+
+        let stream = self
+            .stream
+            .as_ref()
+            .ok_or(CryptographyError::DecodingFailure)?;
+
+        // The last counter value is left for the final chunk.
+        if self.position == u32::MAX {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let chunk = stream
+            .decrypt(
+                self.position,
+                false,
+                Payload {
+                    msg: &self.buffer[position + FRAME_LEN_SIZE..end],
+                    aad: &self.header,
+                },
+            )
+            .map_err(|_| CryptographyError::IncorrectPassword)?;
+        self.position += 1;
+        self.authenticated = true;
+
+        let mut chunk = self.decompress(chunk)?;
+        let keep = self.plaintext_in(&chunk, false)?;
+        chunk.truncate(keep);
+
+        Ok(Some((chunk, end)))
+    }
+
+    /// Authenticates the final chunk and returns its plaintext.
+    /// Returns Truncated if the stream ends without its final chunk, see the [module docs](self),
+    /// or a DecodingFailure if it doesn't match its declared plaintext length.
+    pub fn finish(mut self) -> Result<Vec<u8>, CryptographyError> {
+        self.finish_last()
+    }
+
+    fn finish_last(&mut self) -> Result<Vec<u8>, CryptographyError> {
+        if self.stream.is_none() && !self.read_header()? {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        // Cut off right after the header or inside a frame.
+        match self.frame_end(0)? {
+            Some(end) if end == self.buffer.len() => {}
+            _ => return Err(CryptographyError::Truncated),
+        }
+
+        let stream = self
+            .stream
+            .take()
+            .ok_or(CryptographyError::DecodingFailure)?;
+        let decrypt = |last| {
+            stream.decrypt(
+                self.position,
+                last,
+                Payload {
+                    msg: &self.buffer[FRAME_LEN_SIZE..],
+                    aad: &self.header,
+                },
+            )
+        };
+
+        let chunk = match decrypt(true) {
+            Ok(chunk) => chunk,
+            // An authentic chunk sealed as not being the last one, so the stream was cut off after it.
+            Err(_) if decrypt(false).is_ok() => return Err(CryptographyError::Truncated),
+            Err(_) => return Err(CryptographyError::IncorrectPassword),
+        };
+        self.authenticated = true;
+
+        let mut chunk = self.decompress(chunk)?;
+        let keep = self.plaintext_in(&chunk, true)?;
+        chunk.truncate(keep);
+
+        Ok(chunk)
+    }
+
+    /// Parses the header once it has been fully buffered, returning false if more bytes are needed.
+    fn read_header(&mut self) -> Result<bool, CryptographyError> {
+        let Some(header_size) = StreamHeader::encoded_len(&self.buffer)? else {
+            return Ok(false);
+        };
+
+        if self.buffer.len() < header_size {
+            return Ok(false);
+        }
+
+        let header = StreamHeader::decode(&self.buffer[..header_size])?;
+
+        let chunk_size = header.chunk_size as usize;
+
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let key = derive_key(
+            &self.password,
+            &header.salt,
+            DEFAULT_CONTEXT,
+            header.kdf,
+            &Argon2Params::default(),
+            CipherSuite::Aes256GcmSiv,
+        )?;
+        let cipher = Aes256GcmSiv::new(GenericArray::from_slice(&key));
+
+        self.password.zeroize();
+        self.stream = Some(StreamBE32::from_aead(
+            cipher,
+            GenericArray::from_slice(&header.nonce),
+        ));
+        self.header = self.buffer.drain(..header_size).collect();
+        self.chunk_size = chunk_size;
+        self.plaintext_len = header.plaintext_len;
+        self.associated = header.associated;
+        self.compression = header.compression;
+
+        Ok(true)
+    }
+
+    /// Decompresses an authenticated chunk if the stream is compressed, no chunk holds more than the chunk size.
+    fn decompress(&self, chunk: Vec<u8>) -> Result<Vec<u8>, CryptographyError> {
+        if self.compression.is_none() {
+            return Ok(chunk);
+        }
+
+        self.compression
+            .decompress(&Zeroizing::new(chunk), self.chunk_size)
+            .map_err(|_| CryptographyError::DecodingFailure)
+    }
+
+    /// Returns how many bytes of a decrypted chunk are plaintext rather than padding.
+    /// Streams with a declared length hold full chunks only, with all the padding in the last one.
+    fn plaintext_in(&mut self, chunk: &[u8], last: bool) -> Result<usize, CryptographyError> {
+        let Some(length) = self.plaintext_len else {
+            return Ok(chunk.len());
+        };
+
+        let remaining = length - self.decrypted;
+
+        // The last chunk must hold the tail of the plaintext, so earlier ones can't reach it.
+        let fits = if last {
+            remaining <= self.chunk_size as u64
+        } else {
+            remaining > self.chunk_size as u64
+        };
+
+        if chunk.len() != self.chunk_size || !fits {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        let keep = remaining.min(self.chunk_size as u64) as usize;
+
+        if chunk[keep..].iter().any(|byte| *byte != 0) {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        self.decrypted += keep as u64;
+
+        Ok(keep)
+    }
+
+    /// Returns the end offset of the frame starting at `position`, or None if its length isn't buffered yet.
+    fn frame_end(&self, position: usize) -> Result<Option<usize>, CryptographyError> {
+        let Some(length) = self.buffer.get(position..position + FRAME_LEN_SIZE) else {
+            return Ok(None);
+        };
+
+        let length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]) as usize;
+
+        if length > max_compressed_len(self.compression, self.chunk_size) + TAG_SIZE {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        Ok(Some(position + FRAME_LEN_SIZE + length))
+    }
+}
+
+fn to_array<const N: usize>(value: &[u8]) -> Result<[u8; N], CryptographyError> {
+    value
+        .try_into()
+        .map_err(|_| CryptographyError::DecodingFailure)
+}
+
+/// Compresses a chunk's plaintext if the stream is compressed, None if it's sealed as is.
+fn compress_chunk(
+    compression: Compression,
+    chunk: &[u8],
+) -> Result<Option<Zeroizing<Vec<u8>>>, CryptographyError> {
+    if compression.is_none() {
+        return Ok(None);
+    }
+
+    let compressed = compression.compress(chunk)?;
+
+    // Frames are limited to this length, which every algorithm stays well within.
+    if compressed.len() > max_compressed_len(compression, chunk.len()) {
+        return Err(CryptographyError::EncodingFailure);
+    }
+
+    Ok(Some(compressed))
+}
+
+/// Longest a chunk of `chunk_size` bytes can be once compressed, covering the framing of incompressible data.
+fn max_compressed_len(compression: Compression, chunk_size: usize) -> usize {
+    if compression.is_none() {
+        chunk_size
+    } else {
+        chunk_size + chunk_size / 128 + 1024
+    }
+}
+
+fn write_frame(output: &mut Vec<u8>, chunk: &[u8]) -> Result<(), CryptographyError> {
+    let length = u32::try_from(chunk.len()).map_err(|_| CryptographyError::EncodingFailure)?;
+
+    output.extend_from_slice(&length.to_le_bytes());
+    output.extend_from_slice(chunk);
+
+    Ok(())
+}
+
+/// [`Write`] adapter encrypting everything written to it into the inner writer.
+///
+/// The final, authenticated chunk is only written by [`finish`](Self::finish), so a writer that is dropped
+/// without finishing leaves a stream that fails to decrypt rather than one that silently looks complete.
+/// Debug builds panic when that happens to catch the mistake early.
+///
+/// ```rust
+/// use std::io::Write;
+/// use tinycrypt::stream::{EncryptingWriter, decrypt_stream};
+///
+/// let mut writer = EncryptingWriter::new(Vec::new(), b"password").unwrap();
+/// writer.write_all(b"Hello, world!").unwrap();
+/// let encrypted = writer.finish().unwrap();
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_stream(&encrypted[..], &mut decrypted, b"password").unwrap();
+///
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub struct EncryptingWriter<W: Write> {
+    inner: Option<W>,
+    encryptor: Option<StreamingEncryptor>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    /// Creates a writer using [`DEFAULT_CHUNK_SIZE`].
+    pub fn new(inner: W, password: &[u8]) -> Result<Self, CryptographyError> {
+        Self::with_chunk_size(inner, password, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a writer sealing `chunk_size` bytes of plaintext per chunk.
+    pub fn with_chunk_size(
+        inner: W,
+        password: &[u8],
+        chunk_size: usize,
+    ) -> Result<Self, CryptographyError> {
+        Ok(Self {
+            inner: Some(inner),
+            encryptor: Some(StreamingEncryptor::with_chunk_size(password, chunk_size)?),
+        })
+    }
+
+    /// Writes the final chunk, flushes and returns the inner writer.
+    /// Returns an EncodingFailure if an earlier write failed, as the stream is incomplete.
+    pub fn finish(mut self) -> Result<W, CryptographyError> {
+        let encryptor = self
+            .encryptor
+            .take()
+            .ok_or(CryptographyError::EncodingFailure)?;
+        let mut inner = self
+            .inner
+            .take()
+            .ok_or(CryptographyError::EncodingFailure)?;
+
+        let output = encryptor.finish()?;
+        write_all(&mut inner, &output)?;
+
+        inner
+            .flush()
+            .map_err(|error| CryptographyError::Io(error.into()))?;
+
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (Some(encryptor), Some(inner)) = (self.encryptor.as_mut(), self.inner.as_mut()) else {
+            return Err(io::Error::other(CryptographyError::EncodingFailure));
+        };
+
+        let result = encryptor
+            .push(buf)
+            .map_err(io::Error::other)
+            .and_then(|output| inner.write_all(&output));
+
+        // A failed write leaves a gap in the stream, so it can never be finished.
+        if let Err(error) = result {
+            self.encryptor = None;
+            return Err(error);
+        }
+
+        Ok(buf.len())
+    }
+
+    /// Flushes the inner writer. This doesn't end the stream, only [`finish`](EncryptingWriter::finish) does.
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for EncryptingWriter<W> {
+    fn drop(&mut self) {
+        if self.encryptor.is_some() && !std::thread::panicking() {
+            debug_assert!(
+                false,
+                "EncryptingWriter dropped without calling finish(), the stream is truncated"
+            );
+        }
+    }
+}
+
+/// Function for encrypting everything read from `reader` into `writer` as a chunked stream.
+/// Read & write errors return an Io error.
+///
+/// ```rust
+/// use tinycrypt::stream::{encrypt_stream, decrypt_stream};
+///
+/// let mut encrypted = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").unwrap();
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_stream(&encrypted[..], &mut decrypted, b"password").unwrap();
+///
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub fn encrypt_stream<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    encrypt_stream_cancellable(reader, writer, password, &AtomicBool::new(false))
+}
+
+/// Like [`encrypt_stream`], but checks `cancel` before every chunk and returns Cancelled as soon as it's set.
+///
+/// A cancelled stream never receives its final chunk, so decrypting the partial output fails
+/// instead of passing it off as the complete data.
+pub fn encrypt_stream_cancellable<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    password: &[u8],
+    cancel: &AtomicBool,
+) -> Result<(), CryptographyError> {
+    let mut encryptor = StreamingEncryptor::new(password)?;
+    let mut buffer = Zeroizing::new(vec![0u8; DEFAULT_CHUNK_SIZE]);
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CryptographyError::Cancelled);
+        }
+
+        let read = read_full(&mut reader, &mut buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        let output = encryptor.push(&buffer[..read])?;
+        write_all(&mut writer, &output)?;
+    }
+
+    let output = encryptor.finish()?;
+    write_all(&mut writer, &output)?;
+
+    writer
+        .flush()
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Function for decrypting a chunked stream read from `reader` into `writer`.
+/// Only authenticated plaintext is ever written. Read & write errors return an Io error.
+pub fn decrypt_stream<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    decrypt_stream_cancellable(reader, writer, password, &AtomicBool::new(false))
+}
+
+/// Like [`decrypt_stream`], but checks `cancel` before every chunk and returns Cancelled as soon as it's set.
+/// Whatever was written before cancelling is authenticated plaintext from the start of the stream.
+pub fn decrypt_stream_cancellable<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    password: &[u8],
+    cancel: &AtomicBool,
+) -> Result<(), CryptographyError> {
+    decrypt_stream_inner(reader, writer, password, cancel, None, |_, _| {})
+}
+
+/// Like [`decrypt_stream`], calling `progress` with the number of encrypted bytes processed so far and `total_len`
+/// every time whole chunks have been decrypted, for showing a progress bar.
+/// Pass the stream's length as `total_len` when it's known (such as a file's length), or None for an unbounded reader.
+/// The callback only ever sees byte counts, never plaintext. On success its last call reports the whole stream.
+///
+/// ```rust
+/// use tinycrypt::stream::{encrypt_stream, decrypt_stream_with_progress};
+///
+/// let mut encrypted = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").unwrap();
+///
+/// let mut decrypted = Vec::new();
+/// let total = encrypted.len() as u64;
+///
+/// decrypt_stream_with_progress(&encrypted[..], &mut decrypted, b"password", Some(total), |done, total| {
+///     println!("{done} of {} bytes", total.unwrap());
+/// })
+/// .expect("Failed to decrypt stream!");
+/// ```
+pub fn decrypt_stream_with_progress<R: Read, W: Write, F: FnMut(u64, Option<u64>)>(
+    reader: R,
+    writer: W,
+    password: &[u8],
+    total_len: Option<u64>,
+    progress: F,
+) -> Result<(), CryptographyError> {
+    decrypt_stream_inner(
+        reader,
+        writer,
+        password,
+        &AtomicBool::new(false),
+        total_len,
+        progress,
+    )
+}
+
+fn decrypt_stream_inner<R: Read, W: Write, F: FnMut(u64, Option<u64>)>(
+    mut reader: R,
+    mut writer: W,
+    password: &[u8],
+    cancel: &AtomicBool,
+    total_len: Option<u64>,
+    mut progress: F,
+) -> Result<(), CryptographyError> {
+    let mut decryptor = StreamingDecryptor::new(password);
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+    let mut read_total = 0u64;
+    let mut reported = 0u64;
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CryptographyError::Cancelled);
+        }
+
+        let read = read_full(&mut reader, &mut buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        let output = Zeroizing::new(decryptor.push(&buffer[..read])?);
+        write_all(&mut writer, &output)?;
+
+        // Bytes still buffered belong to a chunk that hasn't been decrypted yet.
+        read_total += read as u64;
+        let done = read_total - decryptor.buffer.len() as u64;
+
+        if done > reported {
+            reported = done;
+            progress(done, total_len);
+        }
+    }
+
+    let output = Zeroizing::new(decryptor.finish()?);
+    write_all(&mut writer, &output)?;
+
+    writer
+        .flush()
+        .map_err(|error| CryptographyError::Io(error.into()))?;
+
+    if read_total > reported {
+        progress(read_total, total_len);
+    }
+
+    Ok(())
+}
+
+/// Function for decrypting a chunked stream read from `reader` one chunk at a time.
+///
+/// Every chunk is authenticated before its plaintext is yielded, so nothing unverified ever reaches the consumer.
+/// The first error ends the iteration, no chunk after a corrupted one is returned.
+/// The final chunk additionally proves the stream wasn't truncated, so only treat the data as complete once the
+/// iterator ends without an error.
+///
+/// ```rust
+/// use tinycrypt::stream::{encrypt_stream, decrypt_streaming};
+///
+/// let mut encrypted = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").unwrap();
+///
+/// for chunk in decrypt_streaming(&encrypted[..], b"password") {
+///     let chunk = chunk.expect("Stream was corrupted!");
+///     // act on the authenticated chunk
+/// }
+/// ```
+pub fn decrypt_streaming<R: Read>(reader: R, password: &[u8]) -> DecryptedChunks<R> {
+    DecryptedChunks {
+        reader,
+        decryptor: Some(StreamingDecryptor::new(password)),
+        buffer: vec![0u8; DEFAULT_CHUNK_SIZE],
+    }
+}
+
+/// Iterator over the authenticated chunks of a stream, returned by [`decrypt_streaming`].
+pub struct DecryptedChunks<R: Read> {
+    reader: R,
+    decryptor: Option<StreamingDecryptor>,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> Iterator for DecryptedChunks<R> {
+    type Item = Result<Vec<u8>, CryptographyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let decryptor = self.decryptor.as_mut()?;
+
+            let read = match decryptor.next_chunk(0) {
+                Ok(Some((chunk, end))) => {
+                    decryptor.buffer.drain(..end);
+                    return Some(Ok(chunk));
+                }
+                Ok(None) => read_full(&mut self.reader, &mut self.buffer),
+                Err(error) => Err(error),
+            };
+
+            match read {
+                Ok(0) => {
+                    let decryptor = self.decryptor.take()?;
+
+                    // Streams ending on a chunk boundary have an empty final chunk, not worth yielding.
+                    return match decryptor.finish() {
+                        Ok(chunk) if chunk.is_empty() => None,
+                        result => Some(result),
+                    };
+                }
+                Ok(read) => decryptor.buffer.extend_from_slice(&self.buffer[..read]),
+                Err(error) => {
+                    self.decryptor = None;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// [`Read`] adapter decrypting a stream read from the inner reader, the counterpart of [`EncryptingWriter`].
+///
+/// Only authenticated plaintext is ever returned, buffered one chunk at a time. The end of the data is only reported
+/// (`read` returning 0) once the final chunk has been authenticated, a truncated or corrupted stream returns an
+/// [`io::Error`] wrapping the CryptographyError instead, and keeps returning it on every later read.
+///
+/// ```rust
+/// use std::io::Read;
+/// use tinycrypt::stream::{encrypt_stream, DecryptingReader};
+///
+/// let mut encrypted = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").unwrap();
+///
+/// let mut decrypted = Vec::new();
+/// DecryptingReader::new(&encrypted[..], b"password").read_to_end(&mut decrypted).unwrap();
+///
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub struct DecryptingReader<R: Read> {
+    chunks: DecryptedChunks<R>,
+    /// Plaintext of the current chunk, wiped once it's been read.
+    chunk: Zeroizing<Vec<u8>>,
+    position: usize,
+    error: Option<CryptographyError>,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    /// Creates a reader, the key is derived once the stream header has been read.
+    pub fn new(inner: R, password: &[u8]) -> Self {
+        Self {
+            chunks: decrypt_streaming(inner, password),
+            chunk: Zeroizing::new(Vec::new()),
+            position: 0,
+            error: None,
+        }
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.position == self.chunk.len() {
+            if let Some(error) = &self.error {
+                return Err(io::Error::other(error.clone()));
+            }
+
+            match self.chunks.next() {
+                Some(Ok(chunk)) => {
+                    self.chunk = Zeroizing::new(chunk);
+                    self.position = 0;
+                }
+                Some(Err(error)) => self.error = Some(error),
+                None => return Ok(0),
+            }
+        }
+
+        let read = buf.len().min(self.chunk.len() - self.position);
+        buf[..read].copy_from_slice(&self.chunk[self.position..self.position + read]);
+        self.position += read;
+
+        Ok(read)
+    }
+}
+
+/// Function for reading the associated header at the front of a stream (see [`StreamingEncryptor::with_associated_header`]),
+/// authenticated by decrypting as little of the stream as possible, normally just its first chunk. The plaintext is discarded.
+/// Returns a DecodingFailure if the stream has no associated header, or IncorrectPassword if it was altered.
+pub fn read_associated_header<R: Read>(
+    mut reader: R,
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let mut decryptor = StreamingDecryptor::new(password);
+    let mut buffer = vec![0u8; 4096];
+
+    loop {
+        if decryptor.stream.is_some() && decryptor.associated.is_none() {
+            return Err(CryptographyError::DecodingFailure);
+        }
+
+        if let Some(associated) = decryptor.associated_header() {
+            return Ok(associated.to_vec());
+        }
+
+        let read = read_full(&mut reader, &mut buffer)?;
+
+        if read == 0 {
+            // The first chunk is also the last one.
+            decryptor.finish_last().map(Zeroizing::new)?;
+        } else {
+            decryptor.push(&buffer[..read]).map(Zeroizing::new)?;
+        }
+    }
+}
+
+/// Function for converting a regular blob (from [`encrypt`](crate::encrypt) and friends) into a stream of
+/// `chunk_size` byte chunks, encrypted under the same password with a fresh salt and nonce.
+/// Streams always use the default context and argon2 parameters, whatever the blob was encrypted with.
+/// The plaintext is wiped from memory once it's been re-encrypted.
+///
+/// ```rust
+/// use tinycrypt::{encrypt, stream::{convert_to_streaming, decrypt_stream}};
+///
+/// let blob = encrypt(b"Hello, world!", b"password").expect("Failed to encrypt!");
+/// let stream = convert_to_streaming(&blob, b"password", 4).expect("Failed to convert!");
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_stream(&stream[..], &mut decrypted, b"password").expect("Failed to decrypt data!");
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub fn convert_to_streaming(
+    blob: &[u8],
+    password: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<u8>, CryptographyError> {
+    let plaintext = Zeroizing::new(decrypt(blob, password)?);
+
+    let mut encryptor = StreamingEncryptor::with_chunk_size(password, chunk_size)?;
+    let mut stream = encryptor.push(&plaintext)?;
+    stream.extend(encryptor.finish()?);
+
+    Ok(stream)
+}
+
+/// Function for converting a stream back into a regular blob, encrypted under the same password with a fresh salt and nonce.
+/// The whole plaintext is held in memory (and wiped afterwards), so this is only meant for streams that fit.
+///
+/// ```rust
+/// use tinycrypt::{decrypt, stream::{encrypt_stream, convert_to_single}};
+///
+/// let mut stream = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut stream, b"password").expect("Failed to encrypt!");
+///
+/// let blob = convert_to_single(&stream, b"password").expect("Failed to convert!");
+/// assert_eq!(decrypt(&blob, b"password").unwrap(), b"Hello, world!");
+/// ```
+pub fn convert_to_single(stream: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    // Uncompressed streams are never shorter than their plaintext, so this doesn't reallocate and leave stray copies behind.
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(stream.len()));
+
+    for chunk in decrypt_streaming(stream, password) {
+        plaintext.extend_from_slice(&Zeroizing::new(chunk?));
+    }
+
+    encrypt(&plaintext, password)
+}
+
+/// Function for changing the password of a chunked stream read from `reader`, writing it into `writer` re-encrypted
+/// under `new_password` with a fresh salt & nonce. Chunks are re-encrypted one at a time, so memory use stays bounded.
+/// The output uses the default chunk size, a declared length, associated header or compression isn't carried over.
+///
+/// Returns IncorrectPassword if `old_password` doesn't open the stream, or Truncated if it's cut off.
+/// The output never gets its final chunk then, so it doesn't decrypt as complete data.
+/// Read & write errors return an Io error.
+///
+/// ```rust
+/// use tinycrypt::stream::{decrypt_stream, encrypt_stream, rekey_stream};
+///
+/// let mut encrypted = Vec::new();
+/// encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"old password").unwrap();
+///
+/// let mut rekeyed = Vec::new();
+/// rekey_stream(&encrypted[..], &mut rekeyed, b"old password", b"new password").expect("Failed to rekey!");
+///
+/// let mut decrypted = Vec::new();
+/// decrypt_stream(&rekeyed[..], &mut decrypted, b"new password").unwrap();
+/// assert_eq!(decrypted, b"Hello, world!");
+/// ```
+pub fn rekey_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    old_password: &[u8],
+    new_password: &[u8],
+) -> Result<(), CryptographyError> {
+    let mut decryptor = StreamingDecryptor::new(old_password);
+    let mut encryptor = StreamingEncryptor::new(new_password)?;
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+
+    loop {
+        let read = read_full(&mut reader, &mut buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        let plaintext = Zeroizing::new(decryptor.push(&buffer[..read])?);
+        write_all(&mut writer, &encryptor.push(&plaintext)?)?;
+    }
+
+    let plaintext = Zeroizing::new(decryptor.finish()?);
+    write_all(&mut writer, &encryptor.push(&plaintext)?)?;
+    write_all(&mut writer, &encryptor.finish()?)?;
+
+    writer
+        .flush()
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Fills `buffer` from `reader`, returning fewer bytes only at the end of the input.
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, CryptographyError> {
+    let mut filled = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(CryptographyError::Io(error.into())),
+        }
+    }
+
+    Ok(filled)
+}
+
+fn write_all<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), CryptographyError> {
+    writer
+        .write_all(bytes)
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
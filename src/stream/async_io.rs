@@ -0,0 +1,412 @@
+//! Async counterparts of the stream functions & adapters over tokio's [`AsyncRead`] & [`AsyncWrite`].
+//!
+//! Key derivation always runs on tokio's blocking pool through [`spawn_blocking`](task::spawn_blocking), so argon2
+//! never stalls the runtime's workers. [`encrypt_stream`] & [`decrypt_stream`] seal and open every chunk there too
+//! and yield between chunks, while [`AsyncEncryptingWriter`] & [`AsyncDecryptingReader`] do the cheap per chunk work
+//! inline in their poll methods. Everything here needs a tokio runtime with the blocking pool, which the default ones have.
+//!
+//! The streams are the same as the synchronous ones, so either side can be async.
+
+use super::{StreamHeader, StreamingDecryptor, StreamingEncryptor, DEFAULT_CHUNK_SIZE};
+use crate::CryptographyError;
+use std::{
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    task,
+};
+use zeroize::Zeroizing;
+
+/// Function for encrypting everything read from `reader` into `writer` as a chunked stream, see
+/// [`stream::encrypt_stream`](super::encrypt_stream). Read & write errors return an Io error.
+///
+/// ```rust
+/// use tinycrypt::stream::async_io::{encrypt_stream, decrypt_stream};
+///
+/// tokio::runtime::Runtime::new().unwrap().block_on(async {
+///     let mut encrypted = Vec::new();
+///     encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").await.unwrap();
+///
+///     let mut decrypted = Vec::new();
+///     decrypt_stream(&encrypted[..], &mut decrypted, b"password").await.unwrap();
+///
+///     assert_eq!(decrypted, b"Hello, world!");
+/// });
+/// ```
+pub async fn encrypt_stream<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    mut reader: R,
+    mut writer: W,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let password = Zeroizing::new(password.to_vec());
+    let mut encryptor = blocking(move || StreamingEncryptor::new(&password)).await??;
+    let mut buffer = Zeroizing::new(vec![0u8; DEFAULT_CHUNK_SIZE]);
+
+    loop {
+        let read = read_full(&mut reader, &mut buffer).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        let output;
+        (encryptor, buffer, output) = blocking(move || {
+            let output = encryptor.push(&buffer[..read]);
+            (encryptor, buffer, output)
+        })
+        .await?;
+
+        write_all(&mut writer, &output?).await?;
+        task::yield_now().await;
+    }
+
+    let output = blocking(move || encryptor.finish()).await??;
+    write_all(&mut writer, &output).await?;
+
+    writer
+        .flush()
+        .await
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Function for decrypting a chunked stream read from `reader` into `writer`, see
+/// [`stream::decrypt_stream`](super::decrypt_stream). Only authenticated plaintext is ever written.
+pub async fn decrypt_stream<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    mut reader: R,
+    mut writer: W,
+    password: &[u8],
+) -> Result<(), CryptographyError> {
+    let mut decryptor = StreamingDecryptor::new(password);
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+
+    loop {
+        let read = read_full(&mut reader, &mut buffer).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        let output;
+        (decryptor, buffer, output) = blocking(move || {
+            let output = decryptor.push(&buffer[..read]);
+            (decryptor, buffer, output)
+        })
+        .await?;
+
+        write_all(&mut writer, &Zeroizing::new(output?)).await?;
+        task::yield_now().await;
+    }
+
+    let output = Zeroizing::new(blocking(move || decryptor.finish()).await??);
+    write_all(&mut writer, &output).await?;
+
+    writer
+        .flush()
+        .await
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// [`AsyncWrite`] adapter encrypting everything written to it into the inner writer, see
+/// [`EncryptingWriter`](super::EncryptingWriter).
+///
+/// The final, authenticated chunk is written by [`shutdown`](AsyncWriteExt::shutdown), which also shuts the inner
+/// writer down. A writer dropped before that leaves a stream that fails to decrypt rather than one that silently looks complete.
+///
+/// ```rust
+/// use tokio::io::AsyncWriteExt;
+/// use tinycrypt::stream::{async_io::AsyncEncryptingWriter, decrypt_stream};
+///
+/// tokio::runtime::Runtime::new().unwrap().block_on(async {
+///     let mut writer = AsyncEncryptingWriter::new(Vec::new(), b"password").await.unwrap();
+///     writer.write_all(b"Hello, world!").await.unwrap();
+///     writer.shutdown().await.unwrap();
+///
+///     let mut decrypted = Vec::new();
+///     decrypt_stream(&writer.into_inner()[..], &mut decrypted, b"password").unwrap();
+///
+///     assert_eq!(decrypted, b"Hello, world!");
+/// });
+/// ```
+pub struct AsyncEncryptingWriter<W: AsyncWrite + Unpin> {
+    inner: W,
+    /// None once the final chunk has been produced, or after a failed write left a gap in the stream.
+    encryptor: Option<StreamingEncryptor>,
+    finished: bool,
+    /// Encrypted bytes not yet written to the inner writer.
+    pending: Vec<u8>,
+    written: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncryptingWriter<W> {
+    /// Creates a writer using [`DEFAULT_CHUNK_SIZE`], deriving the key on the blocking pool.
+    pub async fn new(inner: W, password: &[u8]) -> Result<Self, CryptographyError> {
+        Self::with_chunk_size(inner, password, DEFAULT_CHUNK_SIZE).await
+    }
+
+    /// Creates a writer sealing `chunk_size` bytes of plaintext per chunk.
+    pub async fn with_chunk_size(
+        inner: W,
+        password: &[u8],
+        chunk_size: usize,
+    ) -> Result<Self, CryptographyError> {
+        let password = Zeroizing::new(password.to_vec());
+        let encryptor =
+            blocking(move || StreamingEncryptor::with_chunk_size(&password, chunk_size)).await??;
+
+        Ok(Self {
+            inner,
+            encryptor: Some(encryptor),
+            finished: false,
+            pending: Vec::new(),
+            written: 0,
+        })
+    }
+
+    /// Returns the inner writer, holding a complete stream once the writer has been shut down.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes out the pending encrypted bytes.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.written < self.pending.len() {
+            let result = match ready!(
+                Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.written..])
+            ) {
+                Ok(0) => Err(ErrorKind::WriteZero.into()),
+                result => result,
+            };
+
+            match result {
+                Ok(written) => self.written += written,
+                // A failed write leaves a gap in the stream, so it can never be finished.
+                Err(error) => {
+                    self.encryptor = None;
+                    return Poll::Ready(Err(error));
+                }
+            }
+        }
+
+        self.pending.clear();
+        self.written = 0;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptingWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+
+        let Some(encryptor) = this.encryptor.as_mut() else {
+            return Poll::Ready(Err(io::Error::other(CryptographyError::EncodingFailure)));
+        };
+
+        match encryptor.push(buf) {
+            Ok(output) => {
+                this.pending = output;
+                Poll::Ready(Ok(buf.len()))
+            }
+            Err(error) => {
+                this.encryptor = None;
+                Poll::Ready(Err(io::Error::other(error)))
+            }
+        }
+    }
+
+    /// Flushes the pending bytes & the inner writer. This doesn't end the stream, only shutting down does.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    /// Writes the final chunk, then shuts the inner writer down.
+    /// Fails with an EncodingFailure if an earlier write failed, as the stream is incomplete.
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+
+        if !this.finished {
+            let encryptor = this
+                .encryptor
+                .take()
+                .ok_or_else(|| io::Error::other(CryptographyError::EncodingFailure))?;
+
+            this.pending = encryptor.finish().map_err(io::Error::other)?;
+            this.finished = true;
+            ready!(this.poll_pending(cx))?;
+        }
+
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// [`AsyncRead`] adapter decrypting a stream read from the inner reader, the counterpart of [`AsyncEncryptingWriter`]
+/// and async version of [`DecryptingReader`](super::DecryptingReader).
+///
+/// Only authenticated plaintext is ever returned. The end of the data is only reported once the final chunk has been
+/// authenticated, a truncated or corrupted stream returns an [`io::Error`] wrapping the CryptographyError instead,
+/// and keeps returning it on every later read.
+///
+/// ```rust
+/// use tokio::io::AsyncReadExt;
+/// use tinycrypt::stream::{async_io::AsyncDecryptingReader, encrypt_stream};
+///
+/// tokio::runtime::Runtime::new().unwrap().block_on(async {
+///     let mut encrypted = Vec::new();
+///     encrypt_stream(&b"Hello, world!"[..], &mut encrypted, b"password").unwrap();
+///
+///     let mut reader = AsyncDecryptingReader::new(&encrypted[..], b"password").await.unwrap();
+///     let mut decrypted = Vec::new();
+///     reader.read_to_end(&mut decrypted).await.unwrap();
+///
+///     assert_eq!(decrypted, b"Hello, world!");
+/// });
+/// ```
+pub struct AsyncDecryptingReader<R: AsyncRead + Unpin> {
+    inner: R,
+    /// None once the final chunk has been authenticated.
+    decryptor: Option<StreamingDecryptor>,
+    buffer: Vec<u8>,
+    /// Plaintext of the current chunk, wiped once it's been read.
+    chunk: Zeroizing<Vec<u8>>,
+    position: usize,
+    error: Option<CryptographyError>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecryptingReader<R> {
+    /// Creates a reader, reading the stream header and deriving the key from it on the blocking pool.
+    /// Returns a DecodingFailure if the inner reader fails or ends before a whole header has been read.
+    pub async fn new(mut inner: R, password: &[u8]) -> Result<Self, CryptographyError> {
+        let mut decryptor = StreamingDecryptor::new(password);
+        let mut header = Vec::new();
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+
+        while !matches!(StreamHeader::encoded_len(&header)?, Some(len) if header.len() >= len) {
+            let read = match inner.read(&mut buffer).await {
+                Ok(0) => return Err(CryptographyError::DecodingFailure),
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(CryptographyError::Io(error.into())),
+            };
+
+            header.extend_from_slice(&buffer[..read]);
+        }
+
+        // Pushing a whole header derives the key, along with decrypting any whole frames read past it.
+        let (decryptor, chunk) = blocking(move || {
+            let chunk = decryptor.push(&header);
+            (decryptor, chunk)
+        })
+        .await?;
+
+        Ok(Self {
+            inner,
+            decryptor: Some(decryptor),
+            buffer,
+            chunk: Zeroizing::new(chunk?),
+            position: 0,
+            error: None,
+        })
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        while this.position == this.chunk.len() {
+            if let Some(error) = &this.error {
+                return Poll::Ready(Err(io::Error::other(error.clone())));
+            }
+
+            let Some(decryptor) = this.decryptor.as_mut() else {
+                return Poll::Ready(Ok(()));
+            };
+
+            let mut read = ReadBuf::new(&mut this.buffer);
+
+            let result = match ready!(Pin::new(&mut this.inner).poll_read(cx, &mut read)) {
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => Err(CryptographyError::DecodingFailure),
+                Ok(()) if read.filled().is_empty() => match this.decryptor.take() {
+                    Some(decryptor) => decryptor.finish(),
+                    None => Ok(Vec::new()),
+                },
+                Ok(()) => decryptor.push(read.filled()),
+            };
+
+            match result {
+                Ok(chunk) => {
+                    this.chunk = Zeroizing::new(chunk);
+                    this.position = 0;
+                }
+                Err(error) => this.error = Some(error),
+            }
+        }
+
+        let read = buf.remaining().min(this.chunk.len() - this.position);
+        buf.put_slice(&this.chunk[this.position..this.position + read]);
+        this.position += read;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Runs `f` on tokio's blocking pool, resuming its panic if it panicked.
+/// Returns Cancelled if the runtime shut down before it ran.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, CryptographyError> {
+    match task::spawn_blocking(f).await {
+        Ok(value) => Ok(value),
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(_) => Err(CryptographyError::Cancelled),
+    }
+}
+
+async fn read_full<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut [u8],
+) -> Result<usize, CryptographyError> {
+    let mut filled = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]).await {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(CryptographyError::Io(error.into())),
+        }
+    }
+
+    Ok(filled)
+}
+
+async fn write_all<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    bytes: &[u8],
+) -> Result<(), CryptographyError> {
+    writer
+        .write_all(bytes)
+        .await
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
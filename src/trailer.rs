@@ -0,0 +1,116 @@
+//! Blobs with the ciphertext first and the header in a trailer at the end.
+//!
+//! ```text
+//! ciphertext  ciphertext followed by the 16 byte tag
+//! header      the binary header a regular blob starts with, version 1 without a checksum
+//! header len  4 bytes   u32 LE, length of the header
+//! magic       4 bytes   b"TCRT"
+//! ```
+//!
+//! The fixed 8 byte footer lets a reader find the header by seeking from the end. The header is authenticated exactly
+//! like in a regular blob, so the same ciphertext & header could be laid out either way.
+
+use crate::{
+    format::VERSION_NO_CHECKSUM, open, random_header, seal, CryptographyError, Header,
+    DEFAULT_CONTEXT,
+};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+const TRAILER_MAGIC: &[u8; 4] = b"TCRT";
+const FOOTER_LEN: u64 = 4 + 4;
+const TAG_SIZE: u64 = 16;
+
+/// Function for encrypting data into `writer` with the ciphertext first and the header appended after it.
+/// Write errors return an Io error.
+///
+/// AES-GCM-SIV computes its tag over the whole plaintext before encrypting, so the data still has to be in memory,
+/// but nothing is written before the ciphertext and the writer never has to seek.
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use tinycrypt::{encrypt_trailer, decrypt_trailer};
+///
+/// let mut encrypted_data = Vec::new();
+/// encrypt_trailer(b"Hello, world!", b"password", &mut encrypted_data).expect("Failed to encrypt!");
+///
+/// let decrypted_data = decrypt_trailer(Cursor::new(encrypted_data), b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_trailer<W: Write>(
+    data: &[u8],
+    password: &[u8],
+    mut writer: W,
+) -> Result<(), CryptographyError> {
+    // The footer frames the header, the blob checksum of the leading layout doesn't apply.
+    let mut header = random_header(DEFAULT_CONTEXT)?;
+    header.version = VERSION_NO_CHECKSUM;
+    let ciphertext = seal(&header, data, password)?;
+
+    let mut trailer = header.encode()?;
+    let header_len =
+        u32::try_from(trailer.len()).map_err(|_| CryptographyError::EncodingFailure)?;
+    trailer.extend_from_slice(&header_len.to_le_bytes());
+    trailer.extend_from_slice(TRAILER_MAGIC);
+
+    writer
+        .write_all(&ciphertext)
+        .and_then(|_| writer.write_all(&trailer))
+        .and_then(|_| writer.flush())
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
+
+/// Function for decrypting a blob made by [`encrypt_trailer`], reading the trailer by seeking from the end of `reader`.
+/// The blob is expected to span the whole reader. Read errors return an Io error.
+pub fn decrypt_trailer<R: Read + Seek>(
+    mut reader: R,
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    let total_len = reader
+        .seek(SeekFrom::End(0))
+        .map_err(|error| CryptographyError::Io(error.into()))?;
+
+    if total_len < FOOTER_LEN {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let mut footer = [0u8; FOOTER_LEN as usize];
+    read_at(&mut reader, total_len - FOOTER_LEN, &mut footer)?;
+
+    if &footer[4..] != TRAILER_MAGIC {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let header_len = u64::from(u32::from_le_bytes([
+        footer[0], footer[1], footer[2], footer[3],
+    ]));
+    let ciphertext_len = total_len
+        .checked_sub(FOOTER_LEN + header_len)
+        .filter(|len| *len >= TAG_SIZE)
+        .ok_or(CryptographyError::DecodingFailure)?;
+
+    let mut encoded_header = vec![0u8; header_len as usize];
+    read_at(&mut reader, ciphertext_len, &mut encoded_header)?;
+
+    let (header, parsed_len) = Header::parse(&encoded_header)?;
+
+    if parsed_len != encoded_header.len() {
+        return Err(CryptographyError::DecodingFailure);
+    }
+
+    let ciphertext_len =
+        usize::try_from(ciphertext_len).map_err(|_| CryptographyError::DecodingFailure)?;
+    let mut ciphertext = vec![0u8; ciphertext_len];
+    read_at(&mut reader, 0, &mut ciphertext)?;
+
+    open(&header, &ciphertext, password)
+}
+
+fn read_at<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<(), CryptographyError> {
+    reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| reader.read_exact(buf))
+        .map_err(|error| CryptographyError::Io(error.into()))
+}
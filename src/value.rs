@@ -0,0 +1,184 @@
+//! Encryption of serializable values.
+//!
+//! Values are serialized with bincode (or CBOR with the `cbor` feature) and encrypted like any other data,
+//! the serialized plaintext is wiped once done. Non-bincode blobs store their [`Codec`] in the header.
+
+use crate::{
+    decrypt, decrypt_bounded,
+    envelope::{self, Binary, Envelope},
+    is_legacy, random_header, seal, CryptographyError, Header, DEFAULT_CONTEXT,
+};
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroizing;
+
+const CODEC_BINCODE: u8 = 0;
+#[cfg(feature = "cbor")]
+const CODEC_CBOR: u8 = 1;
+
+/// Serialization format of an encrypted value's plaintext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Codec {
+    /// bincode 1 with its default options, Rust specific. Used by [`encrypt_value`].
+    #[default]
+    Bincode,
+    /// CBOR (RFC 8949) through ciborium, so services in other languages can parse the decrypted plaintext.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Self::Bincode => CODEC_BINCODE,
+            #[cfg(feature = "cbor")]
+            Self::Cbor => CODEC_CBOR,
+        }
+    }
+
+    /// The codec a header names, a DecodingFailure for codecs this build doesn't support.
+    fn of(header: &Header) -> Result<Self, CryptographyError> {
+        match header.codec {
+            CODEC_BINCODE => Ok(Self::Bincode),
+            #[cfg(feature = "cbor")]
+            CODEC_CBOR => Ok(Self::Cbor),
+            _ => Err(CryptographyError::DecodingFailure),
+        }
+    }
+
+    fn serialize<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>, CryptographyError> {
+        match self {
+            Self::Bincode => {
+                bincode::serialize(value).map_err(|error| CryptographyError::Bincode(error.into()))
+            }
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut serialized = Vec::new();
+                ciborium::into_writer(value, &mut serialized)
+                    .map_err(|_| CryptographyError::EncodingFailure)?;
+
+                Ok(serialized)
+            }
+        }
+    }
+
+    /// Deserializes a `T`, allocating at most about `limit` bytes for bincode.
+    fn deserialize<T: DeserializeOwned>(
+        self,
+        serialized: &[u8],
+        limit: u64,
+    ) -> Result<T, CryptographyError> {
+        match self {
+            Self::Bincode => bincode::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes()
+                .with_limit(limit)
+                .deserialize(serialized)
+                .map_err(|error| CryptographyError::Bincode(error.into())),
+            // ciborium only allocates as it reads, and reads no further than the plaintext.
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                ciborium::from_reader(serialized).map_err(|_| CryptographyError::DecodingFailure)
+            }
+        }
+    }
+}
+
+/// The codec of a blob, legacy blobs are always bincode.
+fn codec_of(data: &[u8]) -> Result<Codec, CryptographyError> {
+    if is_legacy(data) {
+        return Ok(Codec::Bincode);
+    }
+
+    let envelope = envelope::detect(data).ok_or(CryptographyError::DecodingFailure)?;
+    let (header, _) = envelope.deserialize(data)?;
+
+    Codec::of(&header)
+}
+
+/// Function for encrypting any serializable value.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_value, decrypt_value};
+///
+/// let encrypted_data = encrypt_value(&vec![1u32, 2, 3], b"password").expect("Failed to encrypt!");
+///
+/// let value: Vec<u32> = decrypt_value(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_value<T: Serialize + ?Sized>(
+    value: &T,
+    password: &[u8],
+) -> Result<Vec<u8>, CryptographyError> {
+    encrypt_value_with(value, password, Codec::Bincode)
+}
+
+/// Function for encrypting any serializable value with a chosen [`Codec`].
+/// The codec is stored in the blob, so [`decrypt_value`] needs nothing extra, and [`decrypt`](crate::decrypt)
+/// returns the serialized plaintext for other languages to parse.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_value_with, decrypt_value, Codec};
+///
+/// let encrypted_data = encrypt_value_with(&vec![1u32, 2, 3], b"password", Codec::Bincode).expect("Failed to encrypt!");
+///
+/// let value: Vec<u32> = decrypt_value(&encrypted_data, b"password").expect("Failed to decrypt data!");
+/// ```
+pub fn encrypt_value_with<T: Serialize + ?Sized>(
+    value: &T,
+    password: &[u8],
+    codec: Codec,
+) -> Result<Vec<u8>, CryptographyError> {
+    let serialized = Zeroizing::new(codec.serialize(value)?);
+
+    let mut header = random_header(DEFAULT_CONTEXT)?;
+
+    // Bincode blobs stay regular minor 0 blobs.
+    if codec != Codec::Bincode {
+        header.minor = 1;
+        header.codec = codec.id();
+    }
+
+    let ciphertext = seal(&header, &serialized, password)?;
+
+    Binary.serialize(&header, &ciphertext)
+}
+
+/// Function for decrypting a value made by [`encrypt_value`] or [`encrypt_value_with`], in the codec stored in the blob.
+/// Returns a DecodingFailure if the blob doesn't hold a `T`, or its codec isn't enabled in this build.
+///
+/// Only use this on blobs from trusted sources, [`decrypt_value_bounded`] limits how much memory a blob can make it allocate.
+pub fn decrypt_value<T: DeserializeOwned>(
+    data: &[u8],
+    password: &[u8],
+) -> Result<T, CryptographyError> {
+    let codec = codec_of(data)?;
+    let serialized = Zeroizing::new(decrypt(data, password)?);
+
+    codec.deserialize(&serialized, u64::MAX)
+}
+
+/// Like [`decrypt_value`], but returns InputTooLarge if the plaintext is longer than `max_plaintext` bytes.
+/// The length is checked before deriving the key where the header allows it, and always before deserializing,
+/// and deserializing is limited to `max_plaintext` bytes as well, so a crafted length prefix can't trigger a huge allocation.
+///
+/// ```rust
+/// use tinycrypt::{encrypt_value, decrypt_value_bounded, CryptographyError};
+///
+/// let encrypted_data = encrypt_value(&vec![0u8; 1024], b"password").expect("Failed to encrypt!");
+///
+/// assert_eq!(
+///     decrypt_value_bounded::<Vec<u8>>(&encrypted_data, b"password", 512),
+///     Err(CryptographyError::InputTooLarge)
+/// );
+/// ```
+pub fn decrypt_value_bounded<T: DeserializeOwned>(
+    data: &[u8],
+    password: &[u8],
+    max_plaintext: usize,
+) -> Result<T, CryptographyError> {
+    let codec = codec_of(data)?;
+    let serialized = Zeroizing::new(decrypt_bounded(data, password, max_plaintext)?);
+
+    codec.deserialize(&serialized, max_plaintext as u64)
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tinycrypt::{decrypt_dir, encrypt_dir, stream::EncryptingWriter, CryptographyError};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tinycrypt-archive-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// Encrypts a handcrafted archive holding a single file entry at `path`.
+fn archive_with_path(output: &Path, path: &str) {
+    let mut entry = vec![2];
+    entry.extend_from_slice(&(path.len() as u16).to_le_bytes());
+    entry.extend_from_slice(path.as_bytes());
+    entry.extend_from_slice(&0o644u32.to_le_bytes());
+    entry.extend_from_slice(&5u64.to_le_bytes());
+    entry.extend_from_slice(b"owned");
+    entry.push(0);
+
+    let mut writer = EncryptingWriter::new(Vec::new(), b"password").unwrap();
+    writer.write_all(&entry).unwrap();
+    fs::write(output, writer.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn directory_tree_round_trips() {
+    let dir = scratch("round-trip");
+    let root = dir.join("root");
+    fs::create_dir_all(root.join("a/b")).unwrap();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    fs::write(root.join("top.txt"), b"top").unwrap();
+    fs::write(root.join("a/b/deep.bin"), vec![7u8; 100_000]).unwrap();
+
+    encrypt_dir(&root, &dir.join("archive"), b"password").unwrap();
+    decrypt_dir(&dir.join("archive"), &dir.join("out"), b"password").unwrap();
+
+    assert_eq!(fs::read(dir.join("out/top.txt")).unwrap(), b"top");
+    assert_eq!(
+        fs::read(dir.join("out/a/b/deep.bin")).unwrap(),
+        vec![7u8; 100_000]
+    );
+    assert!(dir.join("out/empty").is_dir());
+
+    assert_eq!(
+        decrypt_dir(&dir.join("archive"), &dir.join("wrong"), b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn traversal_paths_are_rejected() {
+    let dir = scratch("traversal");
+
+    for path in [
+        "../escape",
+        "a/../../escape",
+        "/etc/escape",
+        "a//b",
+        "./a",
+        "C:\\escape",
+    ] {
+        archive_with_path(&dir.join("archive"), path);
+
+        assert_eq!(
+            decrypt_dir(&dir.join("archive"), &dir.join("out"), b"password"),
+            Err(CryptographyError::DecodingFailure),
+            "{path}"
+        );
+    }
+
+    assert!(!dir.join("escape").exists());
+    assert!(fs::read_dir(dir.join("out")).unwrap().next().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn existing_files_are_not_overwritten() {
+    let dir = scratch("overwrite");
+    fs::create_dir_all(dir.join("out")).unwrap();
+    fs::write(dir.join("out/file"), b"original").unwrap();
+
+    archive_with_path(&dir.join("archive"), "file");
+
+    assert_eq!(
+        decrypt_dir(&dir.join("archive"), &dir.join("out"), b"password"),
+        Err(CryptographyError::Io(
+            std::io::ErrorKind::AlreadyExists.into()
+        ))
+    );
+    assert_eq!(fs::read(dir.join("out/file")).unwrap(), b"original");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn tampered_entries_are_not_extracted() {
+    let dir = scratch("tamper");
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("first.txt"), b"first").unwrap();
+    fs::write(root.join("second.txt"), b"second").unwrap();
+
+    encrypt_dir(&root, &dir.join("archive"), b"password").unwrap();
+
+    // Somewhere inside the first entry's path, after the stream header & frame length.
+    let mut archive = fs::read(dir.join("archive")).unwrap();
+    archive[52] ^= 1;
+    fs::write(dir.join("archive"), archive).unwrap();
+
+    assert_eq!(
+        decrypt_dir(&dir.join("archive"), &dir.join("out"), b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert!(fs::read_dir(dir.join("out")).unwrap().next().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_directories_keep_their_contents() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = scratch("read-only");
+    let root = dir.join("root");
+    fs::create_dir_all(root.join("locked/inner")).unwrap();
+    fs::write(root.join("locked/inner/file.txt"), b"inside").unwrap();
+
+    let read_only = fs::Permissions::from_mode(0o555);
+    fs::set_permissions(root.join("locked/inner"), read_only.clone()).unwrap();
+    fs::set_permissions(root.join("locked"), read_only).unwrap();
+
+    let result = encrypt_dir(&root, &dir.join("archive"), b"password")
+        .and_then(|_| decrypt_dir(&dir.join("archive"), &dir.join("out"), b"password"));
+
+    let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let restored = result.map(|_| {
+        (
+            fs::read(dir.join("out/locked/inner/file.txt")).unwrap(),
+            mode(dir.join("out/locked")),
+            mode(dir.join("out/locked/inner")),
+        )
+    });
+
+    for path in [
+        "root/locked",
+        "root/locked/inner",
+        "out/locked",
+        "out/locked/inner",
+    ] {
+        let _ = fs::set_permissions(dir.join(path), fs::Permissions::from_mode(0o755));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(restored, Ok((b"inside".to_vec(), 0o555, 0o555)));
+}
+
+#[cfg(unix)]
+#[test]
+fn unextractable_names_are_refused_when_packing() {
+    let dir = scratch("names");
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a:b"), b"colon").unwrap();
+
+    assert_eq!(
+        encrypt_dir(&root, &dir.join("archive"), b"password"),
+        Err(CryptographyError::EncodingFailure)
+    );
+    assert!(!dir.join("archive").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
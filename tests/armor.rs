@@ -0,0 +1,77 @@
+#![cfg(feature = "armor")]
+
+use tinycrypt::{
+    armor, dearmor, decrypt, decrypt_armored, encrypt, encrypt_armored, CryptographyError,
+};
+
+#[test]
+fn armored_round_trip() {
+    let plaintext = [7u8; 1000];
+    let armored = encrypt_armored(&plaintext, b"password").unwrap();
+
+    let lines: Vec<&str> = armored.lines().collect();
+    assert_eq!(lines[0], "-----BEGIN TINYCRYPT MESSAGE-----");
+    assert_eq!(lines[lines.len() - 1], "-----END TINYCRYPT MESSAGE-----");
+    assert!(lines.iter().all(|line| line.len() <= 64));
+
+    assert_eq!(decrypt_armored(&armored, b"password").unwrap(), plaintext);
+    assert_eq!(
+        decrypt_armored(&armored, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn decrypt_detects_armor() {
+    let blob = encrypt(b"Hello, world!", b"password").unwrap();
+
+    // As pasted into an indented config file with CRLF line endings.
+    let pasted = armor(&blob)
+        .lines()
+        .map(|line| format!("    {line}\r\n"))
+        .collect::<String>();
+
+    assert_eq!(dearmor(&pasted).unwrap(), blob);
+    assert_eq!(
+        decrypt(pasted.as_bytes(), b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(decrypt(&blob, b"password").unwrap(), b"Hello, world!");
+}
+
+#[test]
+fn malformed_armor_is_rejected() {
+    let armored = armor(&encrypt(b"Hello, world!", b"password").unwrap());
+    let lines: Vec<&str> = armored.lines().collect();
+    let checksum = lines.len() - 2;
+
+    let without = |skip: usize| {
+        lines
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != skip)
+            .map(|(_, line)| format!("{line}\n"))
+            .collect::<String>()
+    };
+
+    let mut flipped = lines[1].to_string().into_bytes();
+    flipped[0] = if flipped[0] == b'A' { b'B' } else { b'A' };
+    let flipped = armored.replacen(lines[1], std::str::from_utf8(&flipped).unwrap(), 1);
+
+    for text in [
+        without(0),
+        without(lines.len() - 1),
+        without(checksum),
+        armored.replacen(lines[1], &format!("{}!", &lines[1][1..]), 1),
+        flipped,
+    ] {
+        assert_eq!(dearmor(&text), Err(CryptographyError::InvalidArmor));
+        assert!(decrypt(text.as_bytes(), b"password").is_err());
+    }
+
+    // Detected as armor, so a broken block is reported as such.
+    assert_eq!(
+        decrypt(without(checksum).as_bytes(), b"password"),
+        Err(CryptographyError::InvalidArmor)
+    );
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "async")]
+
+use tinycrypt::{
+    stream::{
+        async_io::{decrypt_stream, encrypt_stream, AsyncDecryptingReader, AsyncEncryptingWriter},
+        DEFAULT_CHUNK_SIZE,
+    },
+    CryptographyError,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn data() -> Vec<u8> {
+    (0..DEFAULT_CHUNK_SIZE * 3 + 123)
+        .map(|i| (i % 251) as u8)
+        .collect()
+}
+
+#[tokio::test]
+async fn async_streams_round_trip() {
+    let data = data();
+
+    let mut encrypted = Vec::new();
+    encrypt_stream(&data[..], &mut encrypted, b"password")
+        .await
+        .unwrap();
+
+    let mut decrypted = Vec::new();
+    decrypt_stream(&encrypted[..], &mut decrypted, b"password")
+        .await
+        .unwrap();
+    assert_eq!(decrypted, data);
+
+    // Same streams as the blocking functions.
+    let mut decrypted = Vec::new();
+    tinycrypt::stream::decrypt_stream(&encrypted[..], &mut decrypted, b"password").unwrap();
+    assert_eq!(decrypted, data);
+}
+
+#[tokio::test]
+async fn async_adapters_round_trip() {
+    let data = data();
+
+    let mut writer = AsyncEncryptingWriter::with_chunk_size(Vec::new(), b"password", 1000)
+        .await
+        .unwrap();
+
+    for piece in data.chunks(777) {
+        writer.write_all(piece).await.unwrap();
+    }
+    writer.shutdown().await.unwrap();
+    let encrypted = writer.into_inner();
+
+    let mut reader = AsyncDecryptingReader::new(&encrypted[..], b"password")
+        .await
+        .unwrap();
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).await.unwrap();
+
+    assert_eq!(decrypted, data);
+}
+
+#[tokio::test]
+async fn async_adapters_work_over_pipes() {
+    let data = data();
+    let (client, server) = tokio::io::duplex(4096);
+
+    let expected = data.clone();
+    let reading = tokio::spawn(async move {
+        let mut reader = AsyncDecryptingReader::new(server, b"password")
+            .await
+            .unwrap();
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).await.unwrap();
+
+        assert_eq!(decrypted, expected);
+    });
+
+    let mut writer = AsyncEncryptingWriter::new(client, b"password")
+        .await
+        .unwrap();
+    writer.write_all(&data).await.unwrap();
+    writer.shutdown().await.unwrap();
+
+    reading.await.unwrap();
+}
+
+#[tokio::test]
+async fn async_decryption_rejects_bad_streams() {
+    let mut encrypted = Vec::new();
+    encrypt_stream(&data()[..], &mut encrypted, b"password")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        decrypt_stream(&encrypted[..], &mut Vec::new(), b"wrong password").await,
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_stream(
+            &encrypted[..encrypted.len() - 1],
+            &mut Vec::new(),
+            b"password"
+        )
+        .await,
+        Err(CryptographyError::Truncated)
+    );
+    assert!(AsyncDecryptingReader::new(&encrypted[..20], b"password")
+        .await
+        .is_err());
+
+    let mut reader = AsyncDecryptingReader::new(&encrypted[..encrypted.len() - 1], b"password")
+        .await
+        .unwrap();
+    let error = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+
+    assert_eq!(
+        error
+            .into_inner()
+            .unwrap()
+            .downcast::<CryptographyError>()
+            .ok()
+            .map(|e| *e),
+        Some(CryptographyError::Truncated)
+    );
+}
+
+#[tokio::test]
+async fn unfinished_async_writers_leave_truncated_streams() {
+    let mut writer = AsyncEncryptingWriter::new(Vec::new(), b"password")
+        .await
+        .unwrap();
+    writer.write_all(b"Hello, world!").await.unwrap();
+    writer.flush().await.unwrap();
+
+    let encrypted = writer.into_inner();
+
+    assert!(decrypt_stream(&encrypted[..], &mut Vec::new(), b"password")
+        .await
+        .is_err());
+}
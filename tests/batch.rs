@@ -0,0 +1,52 @@
+use tinycrypt::{check_integrity_batch, encrypt, CryptographyError, DerivedKey};
+
+#[test]
+fn every_blob_gets_a_result() {
+    let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    let shared: Vec<Vec<u8>> = (0..3u8).map(|i| key.encrypt(&[i]).unwrap()).collect();
+    let own_salt = encrypt(b"Hello, world!", b"password").unwrap();
+    let other_password = encrypt(b"Hello, world!", b"other").unwrap();
+
+    // Caught by the checksum, before any key is derived.
+    let mut corrupt = shared[1].clone();
+    *corrupt.last_mut().unwrap() ^= 1;
+
+    let blobs = [
+        &shared[0][..],
+        &corrupt,
+        &own_salt,
+        &other_password,
+        b"not a blob",
+        &shared[2],
+    ];
+
+    assert_eq!(
+        check_integrity_batch(&blobs, b"password"),
+        [
+            Ok(()),
+            Err(CryptographyError::DecodingFailure),
+            Ok(()),
+            Err(CryptographyError::IncorrectPassword),
+            Err(CryptographyError::DecodingFailure),
+            Ok(()),
+        ]
+    );
+    assert!(check_integrity_batch(&[], b"password").is_empty());
+}
+
+#[test]
+fn batch_matches_single_checks() {
+    let blobs: Vec<Vec<u8>> = (0..3u8)
+        .map(|i| tinycrypt::encrypt_with_context(&[i], b"password", &[i]).unwrap())
+        .collect();
+    let blobs: Vec<&[u8]> = blobs.iter().map(Vec::as_slice).collect();
+
+    for password in [&b"password"[..], b"wrong"] {
+        let single: Vec<_> = blobs
+            .iter()
+            .map(|blob| tinycrypt::check_integrity(blob, password))
+            .collect();
+
+        assert_eq!(check_integrity_batch(&blobs, password), single);
+    }
+}
@@ -0,0 +1,80 @@
+use tinycrypt::{decrypt, decrypt_builder, encrypt, encrypt_builder, CryptographyError};
+
+#[test]
+fn matching_aad_decrypts() {
+    let encrypted = encrypt_builder(b"Hello, world!")
+        .aad(b"v1")
+        .aad(b"alice")
+        .run(b"password")
+        .unwrap();
+
+    let decrypted = decrypt_builder(&encrypted)
+        .aad(b"v1")
+        .aad(b"alice")
+        .run(b"password")
+        .unwrap();
+
+    assert_eq!(decrypted, b"Hello, world!");
+}
+
+#[test]
+fn aad_must_match_exactly() {
+    let encrypted = encrypt_builder(b"Hello, world!")
+        .aad(b"ab")
+        .aad(b"c")
+        .run(b"password")
+        .unwrap();
+
+    for pieces in [&[][..], &[&b"ab"[..]], &[b"a", b"bc"], &[b"c", b"ab"]] {
+        let builder = pieces
+            .iter()
+            .fold(decrypt_builder(&encrypted), |builder, piece| {
+                builder.aad(piece)
+            });
+
+        assert_eq!(
+            builder.run(b"password"),
+            Err(CryptographyError::IncorrectPassword)
+        );
+    }
+
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn no_aad_matches_plain_encrypt() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(
+        decrypt_builder(&encrypted).run(b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn swapped_records_dont_decrypt() {
+    use tinycrypt::{decrypt_with_aad, encrypt_with_aad};
+
+    let first = encrypt_with_aad(b"alice's data", b"password", b"user/1").unwrap();
+    let second = encrypt_with_aad(b"bob's data", b"password", b"user/2").unwrap();
+
+    assert_eq!(
+        decrypt_with_aad(&first, b"password", b"user/1").unwrap(),
+        b"alice's data"
+    );
+    // Bob's ciphertext moved into Alice's record.
+    assert_eq!(
+        decrypt_with_aad(&second, b"password", b"user/1"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_builder(&second)
+            .aad(b"user/2")
+            .run(b"password")
+            .unwrap(),
+        b"bob's data"
+    );
+}
@@ -0,0 +1,87 @@
+use tinycrypt::{check_integrity, decrypt, encrypt, CryptographyError};
+
+/// Bytes 5 to 8 of a version 2 blob.
+const CHECKSUM: std::ops::Range<usize> = 5..9;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+fn recompute_checksum(blob: &mut [u8]) {
+    blob[CHECKSUM].fill(0);
+    let checksum = crc32(blob);
+    blob[CHECKSUM].copy_from_slice(&checksum.to_le_bytes());
+}
+
+#[test]
+fn checksum_covers_the_whole_blob() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(encrypted[4], 2);
+
+    let mut unchanged = encrypted.clone();
+    recompute_checksum(&mut unchanged);
+    assert_eq!(unchanged, encrypted);
+
+    // Checksum, salt, nonce and ciphertext.
+    for position in [5, 14, 49, encrypted.len() - 1] {
+        let mut corrupted = encrypted.clone();
+        corrupted[position] ^= 1;
+
+        assert_eq!(
+            decrypt(&corrupted, b"password"),
+            Err(CryptographyError::DecodingFailure),
+            "{position}"
+        );
+    }
+}
+
+#[test]
+fn tampering_with_a_valid_checksum_is_still_caught() {
+    let mut encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+
+    encrypted[20] ^= 1;
+    recompute_checksum(&mut encrypted);
+
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn integrity_check_reports_corruption() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+    assert_eq!(check_integrity(&encrypted, b"password"), Ok(()));
+    assert_eq!(
+        check_integrity(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let mut corrupted = encrypted.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        check_integrity(&corrupted, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+
+    recompute_checksum(&mut corrupted);
+    assert_eq!(
+        check_integrity(&corrupted, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        check_integrity(&encrypted[..20], b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
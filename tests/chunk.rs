@@ -0,0 +1,56 @@
+use tinycrypt::{chunk_decrypt, chunk_encrypt, CryptographyError};
+
+const MASTER_KEY: [u8; 32] = [7u8; 32];
+
+#[test]
+fn identical_chunks_share_ciphertext() {
+    let (hash, first) = chunk_encrypt(b"chunk", &MASTER_KEY).unwrap();
+    let (other_hash, second) = chunk_encrypt(b"chunk", &MASTER_KEY).unwrap();
+
+    assert_eq!(hash, other_hash);
+    assert_eq!(first, second);
+    assert_eq!(chunk_decrypt(&first, &MASTER_KEY, &hash).unwrap(), b"chunk");
+}
+
+#[test]
+fn master_key_changes_ciphertext() {
+    let (_, first) = chunk_encrypt(b"chunk", &MASTER_KEY).unwrap();
+    let (_, second) = chunk_encrypt(b"chunk", &[8u8; 32]).unwrap();
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn wrong_key_hash_or_tampering_fails() {
+    let (hash, mut encrypted) = chunk_encrypt(b"chunk", &MASTER_KEY).unwrap();
+    let (other_hash, _) = chunk_encrypt(b"other chunk", &MASTER_KEY).unwrap();
+
+    assert_eq!(
+        chunk_decrypt(&encrypted, &[8u8; 32], &hash),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        chunk_decrypt(&encrypted, &MASTER_KEY, &other_hash),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    encrypted[0] ^= 1;
+    assert_eq!(
+        chunk_decrypt(&encrypted, &MASTER_KEY, &hash),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn master_key_length_is_checked() {
+    assert_eq!(
+        chunk_encrypt(b"chunk", &[7u8; 16]),
+        Err(CryptographyError::InvalidKeyLength(16))
+    );
+
+    let (hash, encrypted) = chunk_encrypt(b"chunk", &MASTER_KEY).unwrap();
+    assert_eq!(
+        chunk_decrypt(&encrypted, &[7u8; 16], &hash),
+        Err(CryptographyError::InvalidKeyLength(16))
+    );
+}
@@ -0,0 +1,196 @@
+use tinycrypt::{
+    decrypt, decrypt_bounded, encrypt, encrypt_with, Argon2Params, Compression, CryptographyError,
+    EncryptOptions, Header,
+};
+
+const LIGHT: Argon2Params = Argon2Params {
+    mem_cost: 64,
+    time_cost: 1,
+    lanes: 1,
+};
+
+/// Every compression this build supports.
+fn compressions() -> Vec<Compression> {
+    vec![
+        #[cfg(feature = "deflate")]
+        Compression::Deflate,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(19),
+    ]
+}
+
+#[test]
+fn compressed_blobs_round_trip() {
+    let data = b"{\"level\":\"info\",\"message\":\"Hello, world!\"}\n".repeat(100);
+
+    for compression in compressions() {
+        let options = EncryptOptions::new().params(LIGHT).compression(compression);
+        let encrypted = encrypt_with(&data, b"password", &options).unwrap();
+
+        assert!(encrypted.len() < data.len() / 10);
+        assert_ne!(
+            Header::parse(&encrypted).unwrap().0.compression(),
+            Compression::None
+        );
+        assert_eq!(decrypt(&encrypted, b"password").unwrap(), data);
+
+        // The compression field is the last one, marked must-understand so lenient readers can't skip it.
+        let fields_len = u16::from_le_bytes([encrypted[9], encrypted[10]]) as usize;
+        assert_eq!(encrypted[11 + fields_len - 4], 0x8c);
+
+        let rekeyed = tinycrypt::rekey(&encrypted, b"password", b"new password").unwrap();
+        assert_eq!(decrypt(&rekeyed, b"new password").unwrap(), data);
+    }
+}
+
+#[test]
+fn empty_data_compresses() {
+    for compression in compressions() {
+        let options = EncryptOptions::new().params(LIGHT).compression(compression);
+        let encrypted = encrypt_with(b"", b"password", &options).unwrap();
+
+        assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"");
+    }
+}
+
+#[test]
+fn decompression_is_bounded() {
+    let data = vec![0u8; 1024 * 1024];
+
+    for compression in compressions() {
+        let options = EncryptOptions::new().params(LIGHT).compression(compression);
+        let encrypted = encrypt_with(&data, b"password", &options).unwrap();
+        assert!(encrypted.len() < 16 * 1024);
+
+        // Stops decompressing a few KiB of ciphertext once it passes the limit.
+        assert_eq!(
+            decrypt_bounded(&encrypted, b"password", 64 * 1024),
+            Err(CryptographyError::InputTooLarge)
+        );
+        assert_eq!(
+            decrypt_bounded(&encrypted, b"password", data.len()).unwrap(),
+            data
+        );
+    }
+}
+
+#[test]
+fn uncompressed_blobs_are_bounded_too() {
+    let encrypted = encrypt(&[7u8; 1024], b"password").unwrap();
+
+    assert_eq!(
+        decrypt_bounded(&encrypted, b"password", 1023),
+        Err(CryptographyError::InputTooLarge)
+    );
+    assert_eq!(
+        decrypt_bounded(&encrypted, b"password", 1024).unwrap(),
+        [7u8; 1024]
+    );
+}
+
+#[test]
+fn unknown_compressions_are_rejected() {
+    let encrypted = encrypt_with(
+        b"Hello, world!",
+        b"password",
+        &EncryptOptions::new().params(LIGHT),
+    )
+    .unwrap();
+
+    // No compression is never written out.
+    assert_eq!(
+        decrypt(&with_compression_field(&encrypted, 0), b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        decrypt(&with_compression_field(&encrypted, 9), b"password"),
+        Err(CryptographyError::UnsupportedCompression(9))
+    );
+
+    #[cfg(not(feature = "deflate"))]
+    assert_eq!(
+        decrypt(&with_compression_field(&encrypted, 1), b"password"),
+        Err(CryptographyError::UnsupportedCompression(1))
+    );
+    #[cfg(not(feature = "zstd"))]
+    assert_eq!(
+        decrypt(&with_compression_field(&encrypted, 2), b"password"),
+        Err(CryptographyError::UnsupportedCompression(2))
+    );
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+#[test]
+fn compressed_streams_round_trip() {
+    use tinycrypt::stream::{StreamingDecryptor, StreamingEncryptor};
+
+    let data = b"Hello, world! ".repeat(1000);
+
+    for compression in compressions() {
+        let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", 1024)
+            .unwrap()
+            .with_compression(compression)
+            .unwrap();
+
+        let mut encrypted = encryptor.push(&data).unwrap();
+        encrypted.extend(encryptor.finish().unwrap());
+        assert!(encrypted.len() < data.len() / 2);
+
+        let mut decryptor = StreamingDecryptor::new(b"password");
+        let mut decrypted = decryptor.push(&encrypted).unwrap();
+        decrypted.extend(decryptor.finish().unwrap());
+
+        assert_eq!(decrypted, data);
+    }
+}
+
+#[cfg(any(feature = "deflate", feature = "zstd"))]
+#[test]
+fn compressed_streams_have_no_declared_length() {
+    use tinycrypt::stream::StreamingEncryptor;
+
+    let compression = compressions()[0];
+
+    let padded = StreamingEncryptor::new(b"password")
+        .unwrap()
+        .with_plaintext_len(13)
+        .unwrap();
+    assert!(padded.with_compression(compression).is_err());
+
+    let compressed = StreamingEncryptor::new(b"password")
+        .unwrap()
+        .with_compression(compression)
+        .unwrap();
+    assert!(compressed.with_plaintext_len(13).is_err());
+}
+
+/// Appends a minor 5 field and a compression field with `id` to a blob's header, as a build with more compressions would.
+fn with_compression_field(blob: &[u8], id: u8) -> Vec<u8> {
+    let fields_len = u16::from_le_bytes([blob[9], blob[10]]) as usize;
+    let newer_fields = [7, 1, 0, 5, 0x8c, 1, 0, id];
+
+    let mut out = blob[..11 + fields_len].to_vec();
+    out[9..11].copy_from_slice(&((fields_len + newer_fields.len()) as u16).to_le_bytes());
+    out.extend_from_slice(&newer_fields);
+    out.extend_from_slice(&blob[11 + fields_len..]);
+
+    out[5..9].fill(0);
+    let checksum = crc32(&out);
+    out[5..9].copy_from_slice(&checksum.to_le_bytes());
+
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
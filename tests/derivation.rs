@@ -0,0 +1,158 @@
+use tinycrypt::{
+    decrypt, decrypt_with_progress, encrypt_with, encrypt_with_progress, Argon2Params,
+    CryptographyError, DerivedKey, EncryptOptions, KeyDerivation,
+};
+
+const LIGHT: Argon2Params = Argon2Params {
+    mem_cost: 64,
+    time_cost: 1,
+    lanes: 1,
+};
+
+/// Parameters covering several lanes, several passes, memory that isn't a multiple of the segments
+/// and segments longer than one block of addresses.
+const PARAMS: [Argon2Params; 6] = [
+    Argon2Params {
+        mem_cost: 8,
+        time_cost: 1,
+        lanes: 1,
+    },
+    Argon2Params {
+        mem_cost: 64,
+        time_cost: 3,
+        lanes: 1,
+    },
+    Argon2Params {
+        mem_cost: 100,
+        time_cost: 2,
+        lanes: 3,
+    },
+    Argon2Params {
+        mem_cost: 256,
+        time_cost: 2,
+        lanes: 4,
+    },
+    Argon2Params {
+        mem_cost: 2048,
+        time_cost: 1,
+        lanes: 1,
+    },
+    Argon2Params {
+        mem_cost: 4100,
+        time_cost: 2,
+        lanes: 2,
+    },
+];
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn progressive_keys_match_argon2() {
+    for params in PARAMS {
+        for context in [&b"tinycrypt-v1"[..], b"my-app-v1"] {
+            let options = EncryptOptions::new().params(params).context(context);
+
+            // Keyed by the argon2 backend, opened with a key derived slice by slice.
+            let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+            let mut calls = Vec::new();
+            let decrypted = decrypt_with_progress(&encrypted, b"password", |done, total| {
+                calls.push((done, total))
+            })
+            .unwrap();
+            assert_eq!(decrypted, b"Hello, world!", "{params:?}");
+
+            let total = params.time_cost * 4;
+            assert_eq!(
+                calls,
+                (1..=total).map(|done| (done, total)).collect::<Vec<_>>()
+            );
+        }
+    }
+}
+
+#[test]
+fn progressive_blobs_decrypt() {
+    for params in PARAMS {
+        let options = EncryptOptions::new().params(params).context(b"my-app-v1");
+
+        // Keyed slice by slice, opened with a key from the argon2 backend.
+        let mut calls = 0;
+        let encrypted =
+            encrypt_with_progress(b"Hello, world!", b"password", &options, |_, _| calls += 1)
+                .unwrap();
+
+        assert!(calls >= 1);
+        assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+    }
+}
+
+#[test]
+fn derivations_step_to_the_same_key() {
+    let salt = DerivedKey::random_salt();
+
+    for params in &PARAMS[..3] {
+        let mut derivation = KeyDerivation::new(b"password", &salt, params).unwrap();
+        let mut steps = 0;
+
+        while !derivation.step() {
+            steps += 1;
+        }
+
+        assert_eq!(steps + 1, derivation.progress().1);
+
+        let key = derivation.into_key().unwrap();
+        let encrypted = key.encrypt(b"Hello, world!").unwrap();
+
+        let reference = DerivedKey::from_password_with_params(b"password", &salt, params).unwrap();
+        assert_eq!(reference.decrypt(&encrypted).unwrap(), b"Hello, world!");
+        assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+    }
+}
+
+#[test]
+fn derivations_only_open_their_own_blobs() {
+    let options = EncryptOptions::new().params(LIGHT);
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+    let other = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    let derivation = KeyDerivation::for_blob(&encrypted, b"password").unwrap();
+    assert_eq!(
+        derivation.decrypt(&other),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    assert_eq!(
+        decrypt_with_progress(&encrypted, b"wrong password", |_, _| {}),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        KeyDerivation::for_blob(b"not a blob", b"password").err(),
+        Some(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn derivations_of_custom_contexts_are_no_derived_keys() {
+    let options = EncryptOptions::new().params(LIGHT).context(b"my-app-v1");
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    let derivation = KeyDerivation::for_blob(&encrypted, b"password").unwrap();
+    assert!(matches!(
+        derivation.into_key(),
+        Err(CryptographyError::KeyGenerationFailure)
+    ));
+}
+
+#[test]
+fn invalid_params_are_rejected() {
+    let params = Argon2Params {
+        mem_cost: 64,
+        time_cost: 0,
+        lanes: 1,
+    };
+
+    assert!(matches!(
+        KeyDerivation::new(b"password", &DerivedKey::random_salt(), &params),
+        Err(CryptographyError::KeyGenerationFailure)
+    ));
+}
@@ -0,0 +1,60 @@
+use tinycrypt::{
+    decrypt, decrypt_committing, encrypt, encrypt_committing, encrypt_to_buf, encrypted_len,
+    stream::{
+        decrypt_stream, decrypt_streaming, encrypt_stream, EncryptingWriter, StreamingDecryptor,
+    },
+    CryptographyError,
+};
+
+#[test]
+fn empty_blob_round_trips() {
+    let encrypted = encrypt(b"", b"password").unwrap();
+
+    assert_eq!(encrypted.len(), encrypted_len(0));
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), Vec::<u8>::new());
+    assert_eq!(
+        decrypt(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let committed = encrypt_committing(b"", b"password").unwrap();
+    assert_eq!(
+        decrypt_committing(&committed, b"password").unwrap(),
+        Vec::<u8>::new()
+    );
+}
+
+#[test]
+fn empty_buffer_encryption_round_trips() {
+    let mut buffer = vec![0u8; encrypted_len(0)];
+    let written = encrypt_to_buf(b"", b"password", &mut buffer).unwrap();
+
+    assert_eq!(written, buffer.len());
+    assert_eq!(decrypt(&buffer, b"password").unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn empty_stream_has_a_final_chunk() {
+    let mut encrypted = Vec::new();
+    encrypt_stream(&b""[..], &mut encrypted, b"password").unwrap();
+
+    let written = EncryptingWriter::new(Vec::new(), b"password")
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(written.len(), encrypted.len());
+
+    let mut decrypted = Vec::new();
+    decrypt_stream(&encrypted[..], &mut decrypted, b"password").unwrap();
+    assert!(decrypted.is_empty());
+
+    assert_eq!(decrypt_streaming(&encrypted[..], b"password").count(), 0);
+
+    // A header alone, without the empty final chunk, is a truncated stream.
+    let frame_len = 4 + 16;
+    let mut decryptor = StreamingDecryptor::new(b"password");
+    decryptor
+        .push(&encrypted[..encrypted.len() - frame_len])
+        .unwrap();
+    assert!(decryptor.finish().is_err());
+}
@@ -0,0 +1,100 @@
+use std::error::Error;
+use tinycrypt::CryptographyError;
+
+#[test]
+fn argon2_errors_are_the_source() {
+    let error = CryptographyError::Argon2(argon2::Error::MemoryTooMuch);
+
+    assert_eq!(
+        error.source().unwrap().to_string(),
+        argon2::Error::MemoryTooMuch.to_string()
+    );
+    assert!(CryptographyError::IncorrectPassword.source().is_none());
+}
+
+#[test]
+fn io_errors_are_the_source() {
+    let missing = std::env::temp_dir().join(format!("tinycrypt-missing-{}", std::process::id()));
+    let error = tinycrypt::fs::load_vault::<u32, _>(&missing, b"password").unwrap_err();
+
+    let source = error.source().unwrap();
+    let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(
+        error,
+        CryptographyError::Io(std::io::ErrorKind::NotFound.into())
+    );
+}
+
+/// A disk that's full.
+struct Full;
+
+impl std::io::Write for Full {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::StorageFull.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A file that can't be read.
+struct Unreadable;
+
+impl std::io::Read for Unreadable {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::PermissionDenied.into())
+    }
+}
+
+#[test]
+fn reader_and_writer_errors_are_kept() {
+    let full = CryptographyError::Io(std::io::ErrorKind::StorageFull.into());
+    let unreadable = CryptographyError::Io(std::io::ErrorKind::PermissionDenied.into());
+
+    assert_eq!(
+        tinycrypt::stream::encrypt_stream(&b"Hello, world!"[..], Full, b"password"),
+        Err(full.clone())
+    );
+    assert_eq!(
+        tinycrypt::stream::decrypt_stream(Unreadable, Vec::new(), b"password"),
+        Err(unreadable)
+    );
+    assert_eq!(
+        tinycrypt::encrypt_trailer(b"Hello, world!", b"password", Full),
+        Err(full)
+    );
+}
+
+#[test]
+fn bincode_errors_are_the_source() {
+    // Not valid UTF-8, so not a String.
+    let mut serialized = 2u64.to_le_bytes().to_vec();
+    serialized.extend_from_slice(&[0xff, 0xfe]);
+    let encrypted = tinycrypt::encrypt(&serialized, b"password").unwrap();
+
+    let error = tinycrypt::decrypt_value::<String>(&encrypted, b"password").unwrap_err();
+
+    assert!(matches!(error, CryptographyError::Bincode(_)));
+    assert!(error.source().unwrap().to_string().contains("utf-8"));
+}
+
+#[test]
+fn carried_errors_are_reported_once() {
+    // An io::Error around a CryptographyError around an io::Error: two causes below the wrapper.
+    let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+    let inner = std::io::Error::other(CryptographyError::Io(missing.into()));
+    let error = tinycrypt::IoError::from(inner);
+
+    assert_eq!(
+        error.to_string(),
+        CryptographyError::Io(std::io::ErrorKind::NotFound.into()).to_string()
+    );
+    let source = error.source().unwrap();
+    assert_eq!(
+        source.downcast_ref::<std::io::Error>().unwrap().kind(),
+        std::io::ErrorKind::NotFound
+    );
+    assert!(source.source().is_none());
+}
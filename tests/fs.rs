@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::PathBuf};
+use tinycrypt::{
+    decrypt, encrypt,
+    fs::{decrypt_file, encrypt_file, load_vault, rekey_file, rekey_stream_file, save_vault},
+    Argon2Params, CipherSuite, CryptographyError,
+};
+
+const LIGHT: Argon2Params = Argon2Params {
+    mem_cost: 64,
+    time_cost: 1,
+    lanes: 1,
+};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tinycrypt-fs-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+#[test]
+fn rekey_replaces_blob() {
+    let dir = scratch("rekey");
+    let path = dir.join("blob");
+    fs::write(&path, encrypt(b"Hello, world!", b"old").unwrap()).unwrap();
+
+    rekey_file(&path, b"old", b"new", CipherSuite::Aes256GcmSiv, &LIGHT).unwrap();
+
+    let blob = fs::read(&path).unwrap();
+    assert_eq!(decrypt(&blob, b"new").unwrap(), b"Hello, world!");
+    assert_eq!(
+        decrypt(&blob, b"old"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    // Only the blob is left, no temporary files.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_rekey_leaves_file_untouched() {
+    let dir = scratch("untouched");
+    let path = dir.join("blob");
+    let original = encrypt(b"Hello, world!", b"old").unwrap();
+    fs::write(&path, &original).unwrap();
+
+    assert_eq!(
+        rekey_file(&path, b"wrong", b"new", CipherSuite::Aes256GcmSiv, &LIGHT),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let invalid = Argon2Params {
+        time_cost: 0,
+        ..LIGHT
+    };
+    assert_eq!(
+        rekey_file(&path, b"old", b"new", CipherSuite::Aes256GcmSiv, &invalid),
+        Err(CryptographyError::KeyGenerationFailure)
+    );
+
+    assert_eq!(fs::read(&path).unwrap(), original);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct State {
+    name: String,
+    counters: Vec<u64>,
+}
+
+#[test]
+fn vault_round_trip() {
+    let dir = scratch("vault");
+    let path = dir.join("state.vault");
+    let state = State {
+        name: "tinycrypt".into(),
+        counters: vec![1, 2, 3],
+    };
+
+    save_vault(&state, &path, b"password").unwrap();
+    assert_eq!(load_vault::<State, _>(&path, b"password").unwrap(), state);
+    assert_eq!(
+        load_vault::<State, _>(&path, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    // Saving again replaces the vault without leaving temporary files.
+    save_vault(
+        &State {
+            counters: vec![],
+            ..state
+        },
+        &path,
+        b"password",
+    )
+    .unwrap();
+    assert!(load_vault::<State, _>(&path, b"password")
+        .unwrap()
+        .counters
+        .is_empty());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn vault_io_errors() {
+    let dir = scratch("vault-io");
+
+    assert_eq!(
+        load_vault::<State, _>(dir.join("missing"), b"password"),
+        Err(CryptographyError::Io(ErrorKind::NotFound.into()))
+    );
+    assert!(matches!(
+        save_vault(&1u8, dir.join("missing/state.vault"), b"password"),
+        Err(CryptographyError::Io(_))
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn file_round_trip() {
+    let dir = scratch("file");
+    let plain = dir.join("plain");
+    let encrypted = dir.join("encrypted");
+    let data: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+    fs::write(&plain, &data).unwrap();
+
+    encrypt_file(&plain, &encrypted, b"password").unwrap();
+    assert_ne!(fs::read(&encrypted).unwrap(), data);
+
+    // Decrypting in place replaces the ciphertext with the plaintext.
+    decrypt_file(&encrypted, &encrypted, b"password").unwrap();
+    assert_eq!(fs::read(&encrypted).unwrap(), data);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_file_decryption_leaves_nothing_behind() {
+    let dir = scratch("file-failed");
+    let plain = dir.join("plain");
+    let encrypted = dir.join("encrypted");
+    let output = dir.join("output");
+    fs::write(&plain, vec![7u8; 100_000]).unwrap();
+    encrypt_file(&plain, &encrypted, b"password").unwrap();
+
+    assert_eq!(
+        decrypt_file(&encrypted, &output, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let stream = fs::read(&encrypted).unwrap();
+    fs::write(&encrypted, &stream[..stream.len() - 10]).unwrap();
+    assert_eq!(
+        decrypt_file(&encrypted, &output, b"password"),
+        Err(CryptographyError::Truncated)
+    );
+
+    // An existing output is kept as it was.
+    fs::write(&output, b"previous").unwrap();
+    assert!(decrypt_file(&encrypted, &output, b"password").is_err());
+    assert_eq!(fs::read(&output).unwrap(), b"previous");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    assert_eq!(
+        encrypt_file(dir.join("missing"), &output, b"password"),
+        Err(CryptographyError::Io(ErrorKind::NotFound.into()))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rekey_stream_file_replaces_stream() {
+    let dir = scratch("rekey-stream");
+    let path = dir.join("file");
+    fs::write(&path, b"Hello, world!").unwrap();
+    encrypt_file(&path, &path, b"old").unwrap();
+
+    assert_eq!(
+        rekey_stream_file(&path, b"wrong", b"new"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    rekey_stream_file(&path, b"old", b"new").unwrap();
+
+    decrypt_file(&path, &path, b"new").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"Hello, world!");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "hybrid")]
+
+use tinycrypt::{
+    decrypt_hybrid, decrypt_with_secret, encrypt_for, encrypt_hybrid, CryptographyError, HybridKey,
+    Keypair, PublicKey, SecretKey,
+};
+
+#[test]
+fn opens_with_either_key() {
+    let keypair = Keypair::generate();
+    let blob = encrypt_hybrid(
+        b"Hello, world!",
+        Some(b"password"),
+        keypair.public.as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::Password(b"password")).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::PrivateKey(keypair.secret.as_bytes())).unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn rejects_wrong_keys() {
+    let keypair = Keypair::generate();
+    let other = Keypair::generate();
+    let blob = encrypt_hybrid(
+        b"Hello, world!",
+        Some(b"password"),
+        keypair.public.as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::Password(b"wrong")),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::PrivateKey(other.secret.as_bytes())),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn recipient_only_blob_has_no_password_slot() {
+    let keypair = Keypair::generate();
+    let blob = encrypt_hybrid(b"Hello, world!", None, keypair.public.as_bytes()).unwrap();
+
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::Password(b"password")),
+        Err(CryptographyError::KeyNotFound)
+    );
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::PrivateKey(keypair.secret.as_bytes())).unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn stripping_the_password_slot_is_detected() {
+    let keypair = Keypair::generate();
+    let blob = encrypt_hybrid(
+        b"Hello, world!",
+        Some(b"password"),
+        keypair.public.as_bytes(),
+    )
+    .unwrap();
+
+    // Clear the password flag and drop the 92 byte slot.
+    let mut stripped = blob[..6].to_vec();
+    stripped[5] &= !1;
+    stripped.extend_from_slice(&blob[6 + 92..]);
+
+    assert_eq!(
+        decrypt_hybrid(&stripped, HybridKey::PrivateKey(keypair.secret.as_bytes())),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn low_order_public_key_is_refused() {
+    assert_eq!(
+        encrypt_hybrid(b"Hello, world!", None, &[0u8; 32]),
+        Err(CryptographyError::KeyGenerationFailure)
+    );
+}
+
+#[test]
+fn keypair_round_trip() {
+    let keypair = Keypair::generate();
+    let other = Keypair::generate();
+    let blob = encrypt_for(&keypair.public, b"Hello, world!").unwrap();
+
+    assert_eq!(
+        decrypt_with_secret(&keypair.secret, &blob).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_with_secret(&other.secret, &blob),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_hybrid(&blob, HybridKey::Password(b"password")),
+        Err(CryptographyError::KeyNotFound)
+    );
+}
+
+#[test]
+fn keys_serialize() {
+    let keypair = Keypair::generate();
+    assert_eq!(keypair.secret.public_key(), keypair.public);
+    assert_eq!(format!("{:?}", keypair.secret), "SecretKey(..)");
+
+    let public: PublicKey =
+        bincode::deserialize(&bincode::serialize(&keypair.public).unwrap()).unwrap();
+    let secret: SecretKey =
+        bincode::deserialize(&bincode::serialize(&keypair.secret).unwrap()).unwrap();
+
+    let blob = encrypt_for(&public, b"Hello, world!").unwrap();
+    assert_eq!(
+        decrypt_with_secret(&secret, &blob).unwrap(),
+        b"Hello, world!"
+    );
+}
@@ -0,0 +1,58 @@
+use tinycrypt::{decrypt, CryptographyError};
+
+/// `encrypt(b"Hello, world!", b"password")` from a build with the `insecure-fast-kdf` feature.
+const INSECURE_BLOB: &[u8] = &[
+    84, 67, 82, 89, 1, 54, 0, 1, 32, 0, 223, 73, 231, 148, 219, 4, 107, 236, 95, 168, 35, 189, 68,
+    204, 3, 55, 157, 182, 232, 190, 137, 240, 232, 199, 50, 181, 71, 16, 253, 150, 55, 33, 2, 12,
+    0, 82, 70, 176, 113, 93, 48, 251, 60, 245, 33, 7, 112, 5, 1, 0, 1, 97, 122, 14, 105, 176, 100,
+    79, 53, 143, 146, 95, 243, 247, 193, 183, 69, 236, 252, 149, 157, 212, 209, 111, 97, 64, 143,
+    185, 170, 135,
+];
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn regular_builds_refuse_insecure_blobs() {
+    assert_eq!(
+        decrypt(INSECURE_BLOB, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[cfg(feature = "insecure-fast-kdf")]
+#[test]
+fn insecure_blobs_decrypt_with_the_feature() {
+    assert_eq!(
+        decrypt(INSECURE_BLOB, b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt(INSECURE_BLOB, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[cfg(feature = "insecure-fast-kdf")]
+#[test]
+fn new_blobs_are_flagged() {
+    let encrypted = tinycrypt::encrypt(b"Hello, world!", b"password").unwrap();
+
+    // The KDF field (tag 5, length 1, value 1) is what sets these blobs apart from argon2 ones.
+    assert!(encrypted.windows(4).any(|field| field == [5, 1, 0, 1]));
+    // The embedded blob is version 1, version 2 adds the 4 byte checksum.
+    assert_eq!(encrypted.len(), INSECURE_BLOB.len() + 4);
+}
+
+#[test]
+fn unknown_kdf_id_is_reported() {
+    let mut blob = INSECURE_BLOB.to_vec();
+    let position = blob
+        .windows(4)
+        .position(|field| field == [5, 1, 0, 1])
+        .unwrap();
+    blob[position + 3] = 42;
+
+    assert_eq!(
+        decrypt(&blob, b"password"),
+        Err(CryptographyError::UnsupportedKdf(42))
+    );
+}
@@ -0,0 +1,153 @@
+use tinycrypt::{
+    decrypt, encrypt, CryptoContext, CryptographyError, DerivedKey, Keyring, NonceTracker,
+};
+
+#[test]
+fn encryptions_are_limited_per_key() {
+    let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt())
+        .unwrap()
+        .with_max_encryptions(2);
+    let mut tracker = NonceTracker::new();
+
+    key.encrypt(b"first").unwrap();
+    key.encrypt_with_nonce(b"second", &[1; 12], &mut tracker)
+        .unwrap();
+    assert_eq!(key.encryptions(), 2);
+
+    assert_eq!(
+        key.encrypt(b"third"),
+        Err(CryptographyError::KeyUsageExceeded)
+    );
+    assert_eq!(
+        key.encrypt_with_nonce(b"third", &[2; 12], &mut tracker),
+        Err(CryptographyError::KeyUsageExceeded)
+    );
+    assert_eq!(key.encryptions(), 2);
+
+    // Decrypting doesn't count.
+    let mut keyring = Keyring::new();
+    let encrypted = DerivedKey::from_password(b"password", key.salt())
+        .unwrap()
+        .encrypt(b"Hello, world!")
+        .unwrap();
+    keyring.insert_key("limited", key);
+    assert_eq!(
+        keyring.decrypt("limited", &encrypted).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        keyring.encrypt("limited", b"fourth"),
+        Err(CryptographyError::KeyUsageExceeded)
+    );
+}
+
+#[test]
+fn sequenced_messages_check_their_number() {
+    let key = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+    let other = DerivedKey::from_password(b"password", &DerivedKey::random_salt()).unwrap();
+
+    let first = key.encrypt_seq(b"Hello, world!", 0).unwrap();
+    let second = key.encrypt_seq(b"Hello, world!", 1).unwrap();
+
+    assert_ne!(first, second);
+    assert_eq!(first.len(), 13 + 16);
+    assert_eq!(key.decrypt_seq(&second, 1).unwrap(), b"Hello, world!");
+
+    for (key, message, seq) in [(&key, &first, 1), (&key, &second, 0), (&other, &first, 0)] {
+        assert_eq!(
+            key.decrypt_seq(message, seq),
+            Err(CryptographyError::IncorrectPassword)
+        );
+    }
+}
+
+#[test]
+fn context_reuses_buffers() {
+    let mut context = CryptoContext::new(b"password").unwrap();
+    let mut encrypted = Vec::new();
+    let mut decrypted = Vec::new();
+
+    context
+        .encrypt_into(b"Hello, world!", &mut encrypted)
+        .unwrap();
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+
+    context.encrypt_into(b"Hi", &mut encrypted).unwrap();
+    context.decrypt_into(&encrypted, &mut decrypted).unwrap();
+    assert_eq!(decrypted, b"Hi");
+
+    // Blobs from elsewhere under the same password, derived once and then cached.
+    let other = encrypt(b"Hello again!", b"password").unwrap();
+    for _ in 0..2 {
+        context.decrypt_into(&other, &mut decrypted).unwrap();
+        assert_eq!(decrypted, b"Hello again!");
+    }
+
+    let mut wrong = CryptoContext::new(b"wrong").unwrap();
+    assert_eq!(
+        wrong.decrypt_into(&encrypted, &mut decrypted),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn epochs_are_stamped_and_checked() {
+    let salt = DerivedKey::random_salt();
+    let key = DerivedKey::from_password(b"password", &salt)
+        .unwrap()
+        .with_epoch(7);
+    let encrypted = key.encrypt(b"Hello, world!").unwrap();
+
+    assert_eq!(
+        tinycrypt::Header::parse(&encrypted).unwrap().0.epoch(),
+        Some(7)
+    );
+    assert_eq!(key.decrypt(&encrypted).unwrap(), b"Hello, world!");
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+    assert_eq!(
+        DerivedKey::from_password(b"password", &salt)
+            .unwrap()
+            .with_epoch(8)
+            .decrypt(&encrypted),
+        Err(CryptographyError::UnknownEpoch(7))
+    );
+}
+
+#[test]
+fn keys_carry_their_params() {
+    use tinycrypt::{decrypt_with_key, encrypt_with_key, Argon2Params, Header};
+
+    let params = Argon2Params {
+        mem_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+    let salt = DerivedKey::random_salt();
+    let key = DerivedKey::from_password_with_params(b"password", &salt, &params).unwrap();
+
+    let encrypted = encrypt_with_key(b"Hello, world!", &key).unwrap();
+    assert_eq!(Header::parse(&encrypted).unwrap().0.params(), params);
+    assert_eq!(
+        decrypt_with_key(&encrypted, &key).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+
+    // Same password & salt, but the default params derive a different key.
+    let default_key = DerivedKey::from_password(b"password", &salt).unwrap();
+    assert_eq!(
+        decrypt_with_key(&encrypted, &default_key),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    assert_eq!(
+        DerivedKey::from_password_with_params(
+            b"password",
+            &salt,
+            &Argon2Params { lanes: 0, ..params }
+        )
+        .err(),
+        Some(CryptographyError::KeyGenerationFailure)
+    );
+}
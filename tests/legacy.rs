@@ -0,0 +1,46 @@
+//! Blobs written before the versioned header was introduced must keep decrypting.
+
+use tinycrypt::{decrypt, CryptographyError};
+
+/// `encrypt(b"Hello, world!", b"password")` from tinycrypt 0.1.0, before the `TCRY` header existed.
+const LEGACY_BLOB: [u8; 81] = [
+    0x1d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xad, 0x5d, 0x66, 0x69, 0xd5, 0x54, 0xa6,
+    0x8b, 0xbc, 0xc3, 0x2a, 0xcf, 0x1a, 0xb3, 0x05, 0x2b, 0xe9, 0x97, 0xf3, 0x0c, 0xfb, 0xbb, 0xbb,
+    0x00, 0x7f, 0xfe, 0xb9, 0xcf, 0x48, 0x35, 0x8c, 0xd9, 0x38, 0x14, 0x15, 0x7b, 0x7a, 0xca, 0xb5,
+    0x77, 0x6d, 0xdc, 0x23, 0x50, 0xf4, 0x65, 0xb4, 0x5f, 0x2c, 0x64, 0x5b, 0xa7, 0x26, 0xb7, 0x55,
+    0x44, 0x7c, 0xba, 0xba, 0xce, 0x05, 0x3e, 0xd1, 0xbc, 0xd4, 0xe2, 0x22, 0x42, 0xf1, 0x8c, 0x8f,
+    0xb2,
+];
+
+#[test]
+fn legacy_blob_decrypts() {
+    assert_eq!(
+        decrypt(&LEGACY_BLOB, b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn legacy_blob_rejects_wrong_password() {
+    assert_eq!(
+        decrypt(&LEGACY_BLOB, b"wrong password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn truncated_legacy_blob_fails_to_decode() {
+    assert_eq!(
+        decrypt(&LEGACY_BLOB[..40], b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn legacy_blob_ignores_trailing_bytes() {
+    // bincode stopped reading after the salt, whatever followed it was never part of the blob.
+    let mut blob = LEGACY_BLOB.to_vec();
+    blob.extend_from_slice(&[0xff; 7]);
+
+    assert_eq!(decrypt(&blob, b"password").unwrap(), b"Hello, world!");
+}
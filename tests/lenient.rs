@@ -0,0 +1,180 @@
+use tinycrypt::{decrypt_lenient, encrypt, CryptographyError};
+
+#[test]
+fn current_blobs_decrypt_leniently() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(
+        decrypt_lenient(&encrypted, b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_lenient(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn newer_major_versions_are_rejected() {
+    let mut encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+    encrypted[4] = 3;
+
+    assert_eq!(
+        decrypt_lenient(&encrypted, b"password"),
+        Err(CryptographyError::UnsupportedVersion(3))
+    );
+    assert_eq!(
+        tinycrypt::decrypt(&encrypted, b"password"),
+        Err(CryptographyError::UnsupportedVersion(3))
+    );
+
+    // Version 0 never existed, so it's not a newer blob.
+    encrypted[4] = 0;
+    assert_eq!(
+        tinycrypt::decrypt(&encrypted, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn newer_json_versions_are_rejected() {
+    let encrypted =
+        tinycrypt::encrypt_with_envelope(b"Hello, world!", b"password", &tinycrypt::envelope::Json)
+            .unwrap();
+    let encrypted =
+        String::from_utf8(encrypted)
+            .unwrap()
+            .replacen("\"version\":2", "\"version\":3", 1);
+
+    assert_eq!(
+        tinycrypt::decrypt(encrypted.as_bytes(), b"password"),
+        Err(CryptographyError::UnsupportedVersion(3))
+    );
+}
+
+/// Builds a blob as a newer minor version would write it, with `newer_fields` after the current fields.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+fn newer_minor_blob(newer_fields: &[u8]) -> Vec<u8> {
+    use aes_gcm_siv::{
+        aead::{Aead, KeyInit, Payload},
+        Aes256GcmSiv, Nonce,
+    };
+
+    let encrypted = encrypt(b"", b"password").unwrap();
+    let fields_len = u16::from_le_bytes([encrypted[9], encrypted[10]]) as usize;
+    let fields = &encrypted[11..11 + fields_len];
+
+    let mut header = b"TCRY\x02\0\0\0\0".to_vec();
+    header.extend_from_slice(&((fields_len + newer_fields.len()) as u16).to_le_bytes());
+    header.extend_from_slice(fields);
+    header.extend_from_slice(newer_fields);
+
+    let config = argon2::Config {
+        ad: tinycrypt::DEFAULT_CONTEXT,
+        hash_length: 32,
+        lanes: 1,
+        mem_cost: 19 * 1024,
+        secret: &[],
+        time_cost: 2,
+        variant: argon2::Variant::Argon2id,
+        version: argon2::Version::Version13,
+    };
+    let key = argon2::hash_raw(b"password", &encrypted[14..46], &config).unwrap();
+
+    let ciphertext = Aes256GcmSiv::new_from_slice(&key)
+        .unwrap()
+        .encrypt(
+            Nonce::from_slice(&encrypted[49..61]),
+            Payload {
+                msg: b"Hello, world!",
+                aad: &header,
+            },
+        )
+        .unwrap();
+
+    let mut blob = [header, ciphertext].concat();
+    let checksum = crc32(&blob);
+    blob[5..9].copy_from_slice(&checksum.to_le_bytes());
+
+    blob
+}
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn newer_minor_versions_need_the_lenient_path() {
+    // Minor 6, followed by a field this version doesn't know.
+    let blob = newer_minor_blob(b"\x07\x01\x00\x06\x40\x03\x00abc");
+
+    assert_eq!(
+        tinycrypt::decrypt(&blob, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        decrypt_lenient(&blob, b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(tinycrypt::probe(&blob), tinycrypt::ProbeResult::Yes);
+}
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn newer_must_understand_fields_are_rejected() {
+    // Minor 6 with a must-understand field this version doesn't know, like a compressed blob read by a minor 4 reader.
+    // Ignoring it would return the plaintext as stored, so the lenient path refuses the blob.
+    for newer_fields in [
+        &b"\x07\x01\x00\x06\x8d\x01\x00\x01"[..],
+        // Even after a field that could be ignored.
+        b"\x07\x01\x00\x06\x40\x03\x00abc\x8d\x01\x00\x01",
+    ] {
+        let blob = newer_minor_blob(newer_fields);
+
+        assert_eq!(
+            decrypt_lenient(&blob, b"password"),
+            Err(CryptographyError::UnsupportedField(0x8d))
+        );
+        assert_eq!(
+            tinycrypt::decrypt(&blob, b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+        assert_eq!(tinycrypt::probe(&blob), tinycrypt::ProbeResult::Yes);
+    }
+}
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn newer_minor_versions_cant_redefine_known_fields() {
+    // A second salt field after the minor field.
+    let mut newer_fields = b"\x07\x01\x00\x06\x01\x20\x00".to_vec();
+    newer_fields.extend_from_slice(&[0u8; 32]);
+
+    for newer_fields in [
+        &newer_fields[..],
+        // Minor 0 is never written out.
+        b"\x07\x01\x00\x00",
+        // Truncated field.
+        b"\x07\x01\x00\x06\x40\x03\x00ab",
+        // Unknown fields in a minor this version knows.
+        b"\x07\x01\x00\x01\x40\x03\x00abc",
+    ] {
+        assert_eq!(
+            decrypt_lenient(&newer_minor_blob(newer_fields), b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use tinycrypt::{decrypt, decrypt_lenient, encrypt, probe, CryptographyError, Header, ProbeResult};
+
+/// Rebuilds a freshly encrypted blob with the salt and nonce fields replaced, fixing up the fields length & checksum.
+fn blob_with(salt: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+    let fields_len = u16::from_le_bytes([encrypted[9], encrypted[10]]) as usize;
+
+    let mut fields = Vec::new();
+
+    for (tag, value) in [(1u8, salt), (2, nonce)] {
+        fields.push(tag);
+        fields.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        fields.extend_from_slice(value);
+    }
+
+    // Any fields after the salt & nonce are kept as they were.
+    fields.extend_from_slice(&encrypted[61..11 + fields_len]);
+
+    let mut blob = b"TCRY\x02\0\0\0\0".to_vec();
+    blob.extend_from_slice(&(fields.len() as u16).to_le_bytes());
+    blob.extend_from_slice(&fields);
+    blob.extend_from_slice(&encrypted[11 + fields_len..]);
+
+    let checksum = crc32(&blob);
+    blob[5..9].copy_from_slice(&checksum.to_le_bytes());
+
+    blob
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[test]
+fn unchanged_fields_still_decrypt() {
+    let blob = blob_with(&[0u8; 32], &[0u8; 12]);
+
+    assert_eq!(
+        decrypt(&blob, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert!(Header::parse(&blob).is_ok());
+}
+
+#[test]
+fn wrong_length_salts_and_nonces_are_rejected() {
+    for (salt, nonce) in [
+        (&[0u8; 31][..], &[0u8; 12][..]),
+        (&[0u8; 33], &[0u8; 12]),
+        (&[0u8; 32], &[0u8; 11]),
+        (&[0u8; 32], &[0u8; 13]),
+        (&[], &[]),
+    ] {
+        let blob = blob_with(salt, nonce);
+
+        assert_eq!(
+            decrypt(&blob, b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+        assert_eq!(
+            decrypt_lenient(&blob, b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+        assert_eq!(
+            Header::parse(&blob).err(),
+            Some(CryptographyError::DecodingFailure)
+        );
+        assert_eq!(probe(&blob), ProbeResult::DefinitelyNot);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn wrong_length_json_fields_are_rejected() {
+    let encrypted =
+        tinycrypt::encrypt_with_envelope(b"Hello, world!", b"password", &tinycrypt::envelope::Json)
+            .unwrap();
+    let encrypted = String::from_utf8(encrypted).unwrap();
+
+    for field in ["\"salt\":[", "\"nonce\":["] {
+        assert!(encrypted.contains(field));
+
+        let start = encrypted.find(field).unwrap() + field.len();
+        let first = start + encrypted[start..].find(',').unwrap() + 1;
+
+        let longer = format!("{}0,{}", &encrypted[..start], &encrypted[start..]);
+        let shorter = format!("{}{}", &encrypted[..start], &encrypted[first..]);
+
+        for blob in [longer, shorter] {
+            assert_eq!(
+                decrypt(blob.as_bytes(), b"password"),
+                Err(CryptographyError::DecodingFailure)
+            );
+        }
+    }
+}
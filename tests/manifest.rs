@@ -0,0 +1,36 @@
+use tinycrypt::{build_manifest, encrypt, verify_manifest, CryptographyError};
+
+#[test]
+fn manifest_detects_any_change_to_the_set() {
+    let blobs: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 10]).collect();
+    let blobs: Vec<&[u8]> = blobs.iter().map(Vec::as_slice).collect();
+    let manifest = build_manifest(&blobs, b"password").unwrap();
+
+    assert_eq!(verify_manifest(&manifest, &blobs, b"password"), Ok(()));
+
+    let tampered = [blobs[0], &[9u8; 10][..], blobs[2]];
+    let swapped = [blobs[1], blobs[0], blobs[2]];
+    let extra = [blobs[0], blobs[1], blobs[2], blobs[2]];
+
+    for changed in [&tampered[..], &swapped, &blobs[..2], &extra] {
+        assert_eq!(
+            verify_manifest(&manifest, changed, b"password"),
+            Err(CryptographyError::ManifestMismatch)
+        );
+    }
+
+    assert_eq!(
+        verify_manifest(&manifest, &blobs, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn regular_blob_is_not_a_manifest() {
+    let blob = encrypt(b"not a manifest", b"password").unwrap();
+
+    assert_eq!(
+        verify_manifest(&blob, &[], b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
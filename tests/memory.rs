@@ -0,0 +1,53 @@
+//! Measures argon2's real allocations, kept in its own test binary so nothing else allocates concurrently.
+//! Needs argon2 itself, so it only runs without the `insecure-fast-kdf` feature.
+#![cfg(not(feature = "insecure-fast-kdf"))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tinycrypt::{encrypt_with_params, Argon2Params};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(current, Ordering::SeqCst);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn estimate_matches_argon2_allocation() {
+    let params = Argon2Params {
+        mem_cost: 4099,
+        time_cost: 1,
+        lanes: 2,
+    };
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+
+    encrypt_with_params(b"Hello, world!", b"password", &params).unwrap();
+
+    let used = PEAK.load(Ordering::SeqCst) - baseline;
+    let estimate = params.estimated_memory_bytes();
+
+    assert!(
+        used >= estimate && used <= estimate + 64 * 1024,
+        "estimated {estimate}, used {used}"
+    );
+}
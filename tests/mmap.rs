@@ -0,0 +1,40 @@
+use tinycrypt::{decrypt, encrypt_to_mmap, encrypted_len, CryptographyError};
+
+#[test]
+fn oversized_mappings_are_zeroed_past_the_blob() {
+    let data = vec![7u8; 1000];
+    let mut mapping = vec![0xaau8; encrypted_len(data.len()) + 100];
+
+    let written = encrypt_to_mmap(&data, b"password", &mut mapping).unwrap();
+
+    assert_eq!(written, encrypted_len(data.len()));
+    assert!(mapping[written..].iter().all(|&byte| byte == 0));
+    assert_eq!(decrypt(&mapping[..written], b"password").unwrap(), data);
+    // The file has to be truncated to the blob.
+    assert!(decrypt(&mapping, b"password").is_err());
+}
+
+#[test]
+fn undersized_mappings_are_refused() {
+    let mut mapping = vec![0xaau8; encrypted_len(1000) - 1];
+
+    assert_eq!(
+        encrypt_to_mmap(&[7u8; 1000], b"password", &mut mapping),
+        Err(CryptographyError::BufferTooSmall)
+    );
+    assert!(mapping.iter().all(|&byte| byte == 0xaa));
+}
+
+#[test]
+fn plaintext_is_not_written_to_the_mapping() {
+    let data = b"a secret that must never reach the page cache".repeat(20);
+    let mut mapping = vec![0u8; encrypted_len(data.len())];
+
+    encrypt_to_mmap(&data, b"password", &mut mapping).unwrap();
+
+    // Only the finished blob is copied in, nothing of the plaintext is left behind.
+    assert!(!mapping
+        .windows(16)
+        .any(|window| data.windows(16).any(|part| part == window)));
+    assert_eq!(decrypt(&mapping, b"password").unwrap(), data);
+}
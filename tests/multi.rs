@@ -0,0 +1,142 @@
+use tinycrypt::{add_password, decrypt_multi, encrypt_multi, remove_password, CryptographyError};
+
+/// Written by the first release of the format, version 1, for the passwords alice & bob.
+const VERSION_1_BLOB: &str = "54434d50010002c0a788f97c9798a23eaaf237cec957324ee38e997a4ed4cd147c5076bef73dc981a8d5b2d703b5986773a57315390f032662e83de94cdf9ea2efaaab17b0f0272a3b1a13f2fcc9d757657a8499b0aa1491c915e3530ad3b96d94817affc6de904cc3011496e9dadede117cf68d0bf1fc3a3fc21dd6d0af633c145d8495d6b77ecf97b09ae17feeaf7a2ee1daee5ca3ac95a1efa91b41a9430d7541373ef76ef310a9c011f4957e0c6d9c75c91dad4fff11cbae95759361e34be6ef654e931a206a4bb75c94fa7d1ee1dae29eb166dcd696c81d53a770e48e1dbc0f9ab6ee75b865";
+
+#[test]
+fn any_password_opens_the_blob() {
+    let passwords: [&[u8]; 3] = [b"alice", b"bob", b"carol"];
+    let blob = encrypt_multi(b"Hello, world!", &passwords).unwrap();
+
+    for password in passwords {
+        assert_eq!(decrypt_multi(&blob, password).unwrap(), b"Hello, world!");
+    }
+
+    assert_eq!(
+        decrypt_multi(&blob, b"mallory"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn needs_one_to_255_passwords() {
+    assert_eq!(
+        encrypt_multi(b"Hello, world!", &[]),
+        Err(CryptographyError::EncodingFailure)
+    );
+    assert_eq!(
+        encrypt_multi(b"Hello, world!", &[b"password".as_slice(); 256]),
+        Err(CryptographyError::EncodingFailure)
+    );
+}
+
+#[test]
+fn stripping_a_slot_is_detected() {
+    let blob = encrypt_multi(b"Hello, world!", &[b"alice", b"bob"]).unwrap();
+
+    // Drop bob's 92 byte slot and fix up the slot count.
+    let mut stripped = blob[..7 + 92].to_vec();
+    stripped[6] = 1;
+    stripped.extend_from_slice(&blob[7 + 2 * 92..]);
+
+    assert_eq!(
+        decrypt_multi(&stripped, b"alice"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_multi(&stripped, b"bob"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn rejects_malformed_blobs() {
+    let blob = encrypt_multi(b"Hello, world!", &[b"alice"]).unwrap();
+
+    for malformed in [&blob[..7], &blob[..blob.len() - 20], b"TCMP\x01\0\0"] {
+        assert_eq!(
+            decrypt_multi(malformed, b"alice"),
+            Err(CryptographyError::DecodingFailure)
+        );
+    }
+}
+
+#[test]
+fn added_password_opens_the_blob() {
+    let blob = encrypt_multi(b"Hello, world!", &[b"alice"]).unwrap();
+
+    assert_eq!(
+        add_password(&blob, b"mallory", b"bob"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let shared = add_password(&blob, b"alice", b"bob").unwrap();
+    assert_eq!(shared.len(), blob.len() + 92);
+
+    for password in [&b"alice"[..], b"bob"] {
+        assert_eq!(decrypt_multi(&shared, password).unwrap(), b"Hello, world!");
+    }
+}
+
+#[test]
+fn removed_password_no_longer_opens_the_blob() {
+    let blob = encrypt_multi(b"Hello, world!", &[b"alice", b"bob", b"carol"]).unwrap();
+    let revoked = remove_password(&blob, b"bob", &[b"alice", b"carol"]).unwrap();
+
+    assert_eq!(revoked.len(), blob.len() - 92);
+    assert_eq!(
+        decrypt_multi(&revoked, b"bob"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    for password in [&b"alice"[..], b"carol"] {
+        assert_eq!(decrypt_multi(&revoked, password).unwrap(), b"Hello, world!");
+    }
+
+    // The data was sealed again under a fresh data key.
+    assert_ne!(blob[blob.len() - 29..], revoked[revoked.len() - 29..]);
+}
+
+#[test]
+fn removal_needs_every_remaining_password() {
+    let blob = encrypt_multi(b"Hello, world!", &[b"alice", b"bob"]).unwrap();
+
+    for (removed, remaining, error) in [
+        (
+            &b"mallory"[..],
+            &[&b"alice"[..]][..],
+            CryptographyError::KeyNotFound,
+        ),
+        (b"bob", &[b"mallory"], CryptographyError::IncorrectPassword),
+        (b"bob", &[b"bob"], CryptographyError::IncorrectPassword),
+        (b"bob", &[], CryptographyError::EncodingFailure),
+    ] {
+        assert_eq!(remove_password(&blob, removed, remaining), Err(error));
+    }
+}
+
+#[test]
+fn version_1_blobs_still_decrypt() {
+    let blob: Vec<u8> = (0..VERSION_1_BLOB.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&VERSION_1_BLOB[i..i + 2], 16).unwrap())
+        .collect();
+
+    for password in [&b"alice"[..], b"bob"] {
+        assert_eq!(decrypt_multi(&blob, password).unwrap(), b"Hello, world!");
+    }
+
+    // Its slots are bound to the slot count.
+    assert_eq!(
+        add_password(&blob, b"alice", b"carol"),
+        Err(CryptographyError::EncodingFailure)
+    );
+    assert_eq!(
+        decrypt_multi(
+            &remove_password(&blob, b"bob", &[b"alice"]).unwrap(),
+            b"alice"
+        )
+        .unwrap(),
+        b"Hello, world!"
+    );
+}
@@ -0,0 +1,266 @@
+use aes_gcm_siv::{aead::KeySizeUser, Aes256GcmSiv};
+use tinycrypt::{
+    decrypt, encrypt_with_params, fs::rekey_file, Argon2Params, CipherSuite, CryptographyError,
+};
+
+const LIGHT: Argon2Params = Argon2Params {
+    mem_cost: 64,
+    time_cost: 1,
+    lanes: 2,
+};
+
+#[test]
+fn custom_params_round_trip() {
+    let encrypted = encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap();
+
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+    assert_eq!(
+        decrypt(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn invalid_params_are_rejected() {
+    for params in [
+        Argon2Params {
+            time_cost: 0,
+            ..LIGHT
+        },
+        Argon2Params { lanes: 0, ..LIGHT },
+        Argon2Params {
+            mem_cost: 15,
+            ..LIGHT
+        },
+    ] {
+        assert_eq!(
+            encrypt_with_params(b"Hello, world!", b"password", &params),
+            Err(CryptographyError::KeyGenerationFailure)
+        );
+    }
+}
+
+#[test]
+fn estimate_follows_argon2_rounding() {
+    let params = |mem_cost, lanes| Argon2Params {
+        mem_cost,
+        time_cost: 1,
+        lanes,
+    };
+
+    // Rounded down to whole segments of 4 blocks per lane, and up to the 8 blocks per lane minimum.
+    assert_eq!(params(1000, 1).estimated_memory_bytes(), 1000 * 1024);
+    assert_eq!(params(1001, 1).estimated_memory_bytes(), 1000 * 1024);
+    assert_eq!(params(1001, 3).estimated_memory_bytes(), 996 * 1024);
+    assert_eq!(params(8, 4).estimated_memory_bytes(), 32 * 1024);
+}
+
+#[test]
+fn derived_key_length_matches_each_cipher() {
+    let path = std::env::temp_dir().join(format!("tinycrypt-key-len-{}", std::process::id()));
+    std::fs::write(
+        &path,
+        encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap(),
+    )
+    .unwrap();
+
+    for &suite in CipherSuite::ALL {
+        let cipher_key_len = match suite {
+            CipherSuite::Aes256GcmSiv => Aes256GcmSiv::key_size(),
+            #[cfg(feature = "chacha20poly1305")]
+            CipherSuite::ChaCha20Poly1305 => chacha20poly1305::ChaCha20Poly1305::key_size(),
+            #[cfg(feature = "chacha20poly1305")]
+            CipherSuite::XChaCha20Poly1305 => chacha20poly1305::XChaCha20Poly1305::key_size(),
+            _ => unreachable!("no cipher key size for {suite:?}"),
+        };
+        assert_eq!(suite.key_len(), cipher_key_len);
+
+        // A key derived with the wrong length would panic or fail here.
+        rekey_file(&path, b"password", b"password", suite, &LIGHT).unwrap();
+        assert_eq!(
+            decrypt(&std::fs::read(&path).unwrap(), b"password").unwrap(),
+            b"Hello, world!"
+        );
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// The insecure test KDF needs no argon2 memory at all.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn memory_limit_reports_the_blob_params() {
+    use tinycrypt::decrypt_with_memory_limit;
+
+    let encrypted = encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap();
+    let needed = LIGHT.estimated_memory_bytes();
+
+    assert_eq!(
+        decrypt_with_memory_limit(&encrypted, b"password", needed - 1),
+        Err(CryptographyError::InsufficientMemory(LIGHT))
+    );
+    assert_eq!(
+        decrypt_with_memory_limit(&encrypted, b"password", needed).unwrap(),
+        b"Hello, world!"
+    );
+}
+
+// The insecure test KDF needs no argon2 memory at all.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn decrypt_refuses_huge_memory_costs() {
+    use tinycrypt::DEFAULT_MAX_MEMORY_BYTES;
+
+    let mut encrypted = encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap();
+
+    // Raise the memory cost in the params field to 16 GiB and fix up the checksum.
+    let field = encrypted
+        .windows(7)
+        .position(|window| window == [6, 12, 0, 64, 0, 0, 0])
+        .unwrap();
+    let huge = Argon2Params {
+        mem_cost: 16 * 1024 * 1024,
+        ..LIGHT
+    };
+    encrypted[field + 3..field + 7].copy_from_slice(&huge.mem_cost.to_le_bytes());
+    encrypted[5..9].fill(0);
+    let checksum = crc32(&encrypted);
+    encrypted[5..9].copy_from_slice(&checksum.to_le_bytes());
+
+    assert!(huge.estimated_memory_bytes() > DEFAULT_MAX_MEMORY_BYTES);
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::InsufficientMemory(huge))
+    );
+}
+
+#[cfg(not(feature = "insecure-fast-kdf"))]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[test]
+fn policy_rejects_weak_params_before_deriving() {
+    use tinycrypt::{decrypt_with_policy, Argon2Policy};
+
+    let encrypted = encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap();
+
+    for policy in [
+        Argon2Policy::default(),
+        Argon2Policy {
+            min_mem_cost: 65,
+            min_time_cost: 1,
+        },
+        Argon2Policy {
+            min_mem_cost: 64,
+            min_time_cost: 2,
+        },
+    ] {
+        // Even the wrong password gets the policy error, nothing was derived.
+        for password in [&b"password"[..], b"wrong"] {
+            assert_eq!(
+                decrypt_with_policy(&encrypted, password, &policy),
+                Err(CryptographyError::PolicyViolation)
+            );
+        }
+    }
+}
+
+// Keys from the insecure test KDF never meet a policy.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn policy_accepts_params_meeting_it() {
+    use tinycrypt::{decrypt_with_policy, Argon2Policy};
+
+    let encrypted = encrypt_with_params(b"Hello, world!", b"password", &LIGHT).unwrap();
+    let policy = Argon2Policy {
+        min_mem_cost: 64,
+        min_time_cost: 1,
+    };
+
+    assert_eq!(
+        decrypt_with_policy(&encrypted, b"password", &policy).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_with_policy(&encrypted, b"wrong", &policy),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt_with_policy(
+            &tinycrypt::encrypt(b"Hello, world!", b"password").unwrap(),
+            b"password",
+            &Argon2Policy::default()
+        )
+        .unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn options_are_stored_in_the_header() {
+    use tinycrypt::{creation_time, encrypt_with, EncryptOptions, Header};
+
+    let options = EncryptOptions::new()
+        .params(LIGHT)
+        .context(b"my-app-v1")
+        .timestamped();
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    let (header, _) = Header::parse(&encrypted).unwrap();
+    assert_eq!(header.params(), LIGHT);
+    assert!(creation_time(&encrypted).is_ok());
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+
+    assert!(encrypted
+        .windows(b"my-app-v1".len())
+        .any(|window| window == b"my-app-v1"));
+
+    let plain = encrypt_with(b"Hello, world!", b"password", &EncryptOptions::new()).unwrap();
+    assert_eq!(
+        Header::parse(&plain).unwrap().0.params(),
+        Argon2Params::default()
+    );
+    assert!(creation_time(&plain).is_err());
+
+    assert_eq!(
+        encrypt_with(
+            b"Hello, world!",
+            b"password",
+            &EncryptOptions::new().params(Argon2Params { lanes: 0, ..LIGHT })
+        ),
+        Err(CryptographyError::KeyGenerationFailure)
+    );
+}
+
+#[test]
+fn presets_are_ordered_by_memory() {
+    use tinycrypt::{EncryptOptions, Header, KdfPreset};
+
+    let [interactive, moderate, sensitive] = [
+        KdfPreset::Interactive,
+        KdfPreset::Moderate,
+        KdfPreset::Sensitive,
+    ]
+    .map(KdfPreset::params);
+
+    assert_eq!(moderate, Argon2Params::default());
+    assert!(interactive.mem_cost < moderate.mem_cost && interactive.time_cost > moderate.time_cost);
+    assert!(sensitive.mem_cost > moderate.mem_cost && sensitive.time_cost > moderate.time_cost);
+
+    let options = EncryptOptions::new().params(KdfPreset::Interactive.into());
+    let encrypted = tinycrypt::encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    assert_eq!(Header::parse(&encrypted).unwrap().0.params(), interactive);
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+}
@@ -0,0 +1,12 @@
+use tinycrypt::{decrypt, encrypt, Password};
+use zeroize::Zeroize;
+
+#[test]
+fn password_can_be_wiped_early() {
+    let mut password = Password::from(String::from("password"));
+    let blob = encrypt(b"Hello, world!", &password).unwrap();
+    assert_eq!(decrypt(&blob, &password).unwrap(), b"Hello, world!");
+
+    password.zeroize();
+    assert!(password.is_empty());
+}
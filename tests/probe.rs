@@ -0,0 +1,35 @@
+use tinycrypt::{encrypt, encrypt_with_context, probe, ProbeResult};
+
+#[test]
+fn every_prefix_of_a_blob_is_maybe_until_the_header_is_complete() {
+    let blob = encrypt_with_context(b"Hello, world!", b"password", b"probe context").unwrap();
+    let header_len = 11 + u16::from_le_bytes([blob[9], blob[10]]) as usize;
+
+    for length in 0..header_len {
+        assert_eq!(probe(&blob[..length]), ProbeResult::Maybe, "{length}");
+    }
+
+    assert_eq!(probe(&blob[..header_len]), ProbeResult::Yes);
+    assert_eq!(probe(&blob), ProbeResult::Yes);
+}
+
+#[test]
+fn rejects_early() {
+    let blob = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(probe(b"x"), ProbeResult::DefinitelyNot);
+    assert_eq!(probe(b"TCRX"), ProbeResult::DefinitelyNot);
+    assert_eq!(probe(b"TCRY\x03"), ProbeResult::DefinitelyNot);
+    assert_eq!(probe(b"TCRY\x01\x10\x00"), ProbeResult::DefinitelyNot);
+    assert_eq!(
+        probe(b"TCRY\x02\0\0\0\0\x10\x00"),
+        ProbeResult::DefinitelyNot
+    );
+    assert_eq!(probe(b"{\"format\""), ProbeResult::DefinitelyNot);
+
+    // A complete header with an unknown field.
+    let header_len = 11 + u16::from_le_bytes([blob[9], blob[10]]) as usize;
+    let mut corrupted = blob[..header_len].to_vec();
+    corrupted[11] = 0xff;
+    assert_eq!(probe(&corrupted), ProbeResult::DefinitelyNot);
+}
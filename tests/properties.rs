@@ -0,0 +1,106 @@
+//! Property based checks of the one-shot and streaming paths.
+
+use proptest::{collection::vec, prelude::*};
+use tinycrypt::{
+    decrypt, encrypt,
+    stream::{StreamingDecryptor, StreamingEncryptor},
+    CryptographyError,
+};
+
+/// Mostly small plaintexts, with the occasional megabyte sized one.
+fn plaintext() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        4 => vec(any::<u8>(), 0..=256),
+        1 => vec(any::<u8>(), (1 << 20)..=(1 << 20) + 16),
+    ]
+}
+
+/// Any bytes, including empty passwords and ones containing nulls.
+fn password() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        Just(Vec::new()),
+        Just(vec![0u8; 4]),
+        vec(any::<u8>(), 0..64)
+    ]
+}
+
+proptest! {
+    // Every case runs argon2 at least once.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn round_trips(data in plaintext(), password in password()) {
+        let encrypted = encrypt(&data, &password).unwrap();
+
+        prop_assert_eq!(decrypt(&encrypted, &password).unwrap(), data);
+    }
+
+    #[test]
+    fn wrong_password_is_rejected(
+        data in vec(any::<u8>(), 0..64),
+        password in password(),
+        wrong in password(),
+    ) {
+        prop_assume!(password != wrong);
+
+        let encrypted = encrypt(&data, &password).unwrap();
+
+        prop_assert_eq!(decrypt(&encrypted, &wrong), Err(CryptographyError::IncorrectPassword));
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn arbitrary_blobs_never_panic(data in vec(any::<u8>(), 0..512)) {
+        let _ = decrypt(&data, b"password");
+    }
+
+    #[test]
+    fn arbitrary_blobs_with_magic_never_panic(fields in vec(any::<u8>(), 0..128)) {
+        let mut data = b"TCRY\x01".to_vec();
+        data.extend_from_slice(&fields);
+
+        let _ = decrypt(&data, b"password");
+    }
+
+    #[test]
+    fn arbitrary_streams_never_panic(data in vec(any::<u8>(), 0..512), split in 0usize..512) {
+        let mut decryptor = StreamingDecryptor::new(b"password");
+        let split = split.min(data.len());
+
+        let _ = decryptor
+            .push(&data[..split])
+            .and_then(|_| decryptor.push(&data[split..]))
+            .and_then(|_| decryptor.finish());
+    }
+}
+
+proptest! {
+    // Every case runs argon2 twice.
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn truncated_streams_never_decrypt(
+        data in vec(any::<u8>(), 0..256),
+        chunk_size in 1usize..64,
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", chunk_size).unwrap();
+        let mut encrypted = encryptor.push(&data).unwrap();
+        encrypted.extend(encryptor.finish().unwrap());
+
+        let cut = cut.index(encrypted.len());
+        let mut decryptor = StreamingDecryptor::new(b"password");
+        let result = decryptor.push(&encrypted[..cut]).and_then(|_| decryptor.finish());
+
+        // 44 byte header in front of the frames.
+        let expected = if cut < 44 {
+            CryptographyError::DecodingFailure
+        } else {
+            CryptographyError::Truncated
+        };
+        prop_assert_eq!(result, Err(expected));
+    }
+}
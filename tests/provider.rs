@@ -0,0 +1,123 @@
+use tinycrypt::{
+    decrypt, decrypt_with_provider, encrypt, encrypt_with_provider, CryptographyError, KeyProvider,
+};
+
+/// Hands out a single key, deterministic like a hardware token would be.
+struct TestProvider([u8; 32]);
+
+impl KeyProvider for TestProvider {
+    fn provide_key(&self, context: &[u8]) -> Result<[u8; 32], CryptographyError> {
+        match context {
+            b"backup-key" => Ok(self.0),
+            _ => Err(CryptographyError::KeyNotFound),
+        }
+    }
+}
+
+#[test]
+fn provider_round_trip() {
+    let provider = TestProvider([7u8; 32]);
+    let blob = encrypt_with_provider(b"Hello, world!", &provider, b"backup-key").unwrap();
+
+    assert_eq!(
+        decrypt_with_provider(&blob, &provider).unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_with_provider(&blob, &TestProvider([8u8; 32])),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn provider_errors_are_passed_through() {
+    assert_eq!(
+        encrypt_with_provider(b"Hello, world!", &TestProvider([7u8; 32]), b"other-key"),
+        Err(CryptographyError::KeyNotFound)
+    );
+}
+
+#[test]
+fn provider_and_password_blobs_dont_mix() {
+    let provider = TestProvider([7u8; 32]);
+    let blob = encrypt_with_provider(b"Hello, world!", &provider, b"backup-key").unwrap();
+
+    assert_eq!(
+        decrypt(&blob, b"password"),
+        Err(CryptographyError::KeyNotFound)
+    );
+
+    let password_blob = encrypt(b"Hello, world!", b"password").unwrap();
+    assert_eq!(
+        decrypt_with_provider(&password_blob, &provider),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+/// Holds one key per epoch, like a KMS rotating its master key monthly.
+struct RotatingProvider {
+    keys: Vec<[u8; 32]>,
+}
+
+impl KeyProvider for RotatingProvider {
+    fn provide_key(&self, _context: &[u8]) -> Result<[u8; 32], CryptographyError> {
+        Err(CryptographyError::KeyNotFound)
+    }
+
+    fn current_epoch(&self) -> Option<u32> {
+        Some(self.keys.len() as u32 - 1)
+    }
+
+    fn provide_epoch_key(
+        &self,
+        _context: &[u8],
+        epoch: u32,
+    ) -> Result<[u8; 32], CryptographyError> {
+        self.keys
+            .get(epoch as usize)
+            .copied()
+            .ok_or(CryptographyError::UnknownEpoch(epoch))
+    }
+}
+
+#[test]
+fn rotated_keys_still_decrypt_older_epochs() {
+    let mut provider = RotatingProvider {
+        keys: vec![[1u8; 32]],
+    };
+    let old = encrypt_with_provider(b"old", &provider, b"backup-key").unwrap();
+
+    provider.keys.push([2u8; 32]);
+    let new = encrypt_with_provider(b"new", &provider, b"backup-key").unwrap();
+
+    let epoch = |blob: &[u8]| tinycrypt::Header::parse(blob).unwrap().0.epoch();
+    assert_eq!(epoch(&old), Some(0));
+    assert_eq!(epoch(&new), Some(1));
+
+    assert_eq!(decrypt_with_provider(&old, &provider).unwrap(), b"old");
+    assert_eq!(decrypt_with_provider(&new, &provider).unwrap(), b"new");
+
+    // A provider that lost the newest epoch.
+    assert_eq!(
+        decrypt_with_provider(
+            &new,
+            &RotatingProvider {
+                keys: vec![[1u8; 32]]
+            }
+        ),
+        Err(CryptographyError::UnknownEpoch(1))
+    );
+    // Or doesn't rotate at all.
+    assert_eq!(
+        decrypt_with_provider(&old, &TestProvider([1u8; 32])),
+        Err(CryptographyError::UnknownEpoch(0))
+    );
+}
+
+#[test]
+fn unrotated_blobs_have_no_epoch() {
+    let blob =
+        encrypt_with_provider(b"Hello, world!", &TestProvider([7u8; 32]), b"backup-key").unwrap();
+
+    assert_eq!(tinycrypt::Header::parse(&blob).unwrap().0.epoch(), None);
+}
@@ -0,0 +1,52 @@
+use tinycrypt::{
+    raw::{open, seal},
+    CryptographyError,
+};
+
+#[test]
+fn seal_open_round_trip() {
+    let (ciphertext, nonce, salt) = seal(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(ciphertext.len(), 13 + 16);
+    assert_eq!(
+        open(&ciphertext, &nonce, &salt, b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn open_needs_the_exact_nonce_and_salt() {
+    let (ciphertext, nonce, salt) = seal(b"Hello, world!", b"password").unwrap();
+    let (_, other_nonce, other_salt) = seal(b"Hello, world!", b"password").unwrap();
+
+    for (nonce, salt, password) in [
+        (&nonce, &salt, &b"wrong"[..]),
+        (&other_nonce, &salt, &b"password"[..]),
+        (&nonce, &other_salt, &b"password"[..]),
+    ] {
+        assert_eq!(
+            open(&ciphertext, nonce, salt, password),
+            Err(CryptographyError::IncorrectPassword)
+        );
+    }
+}
+
+// Under the insecure test KDF the packed header carries an extra KDF field.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn packing_the_fields_gives_a_regular_blob() {
+    let (ciphertext, nonce, salt) = seal(b"Hello, world!", b"password").unwrap();
+
+    let mut blob = b"TCRY\x01".to_vec();
+    blob.extend_from_slice(&50u16.to_le_bytes());
+    blob.extend_from_slice(&[1, 32, 0]);
+    blob.extend_from_slice(&salt);
+    blob.extend_from_slice(&[2, 12, 0]);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&ciphertext);
+
+    assert_eq!(
+        tinycrypt::decrypt(&blob, b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
@@ -0,0 +1,69 @@
+use tinycrypt::{
+    creation_time, decrypt, decrypt_with_memory_limit, encrypt_timestamped, encrypt_with_context,
+    encrypt_with_params, reencrypt_envelope, rekey, Argon2Params, CryptographyError,
+};
+
+#[test]
+fn copies_are_unlinkable() {
+    let encrypted = encrypt_with_context(b"Hello, world!", b"password", b"notes").unwrap();
+
+    let first = reencrypt_envelope(&encrypted, b"password").unwrap();
+    let second = reencrypt_envelope(&encrypted, b"password").unwrap();
+
+    assert_ne!(first, encrypted);
+    assert_ne!(first, second);
+    // Salts and nonces are all fresh.
+    assert_ne!(first[14..46], encrypted[14..46]);
+    assert_ne!(first[14..46], second[14..46]);
+
+    for copy in [&first, &second] {
+        assert_eq!(decrypt(copy, b"password").unwrap(), b"Hello, world!");
+    }
+}
+
+#[test]
+fn header_metadata_is_kept() {
+    let timestamped = encrypt_timestamped(b"Hello, world!", b"password").unwrap();
+    let copy = reencrypt_envelope(&timestamped, b"password").unwrap();
+    assert_eq!(creation_time(&copy), creation_time(&timestamped));
+
+    let params = Argon2Params {
+        mem_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+    let light = encrypt_with_params(b"Hello, world!", b"password", &params).unwrap();
+    let copy = reencrypt_envelope(&light, b"password").unwrap();
+    assert_eq!(
+        decrypt_with_memory_limit(&copy, b"password", params.estimated_memory_bytes()).unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn needs_the_password() {
+    let encrypted = encrypt_with_context(b"Hello, world!", b"password", b"notes").unwrap();
+
+    assert_eq!(
+        reencrypt_envelope(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn rekey_changes_the_password() {
+    let encrypted = encrypt_with_context(b"Hello, world!", b"old", b"notes").unwrap();
+
+    assert_eq!(
+        rekey(&encrypted, b"wrong", b"new"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let rekeyed = rekey(&encrypted, b"old", b"new").unwrap();
+    assert_ne!(rekeyed[14..46], encrypted[14..46]);
+    assert_eq!(decrypt(&rekeyed, b"new").unwrap(), b"Hello, world!");
+    assert_eq!(
+        decrypt(&rekeyed, b"old"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
@@ -0,0 +1,78 @@
+use tinycrypt::{
+    blob_version, decrypt, decrypt_min_version, encrypt, encrypt_versioned, CryptographyError,
+};
+
+#[test]
+fn older_versions_are_rejected() {
+    let old = encrypt_versioned(b"retries = 3", b"password", 6).unwrap();
+    let current = encrypt_versioned(b"retries = 5", b"password", 7).unwrap();
+
+    assert_eq!(blob_version(&old), Ok(6));
+    assert_eq!(blob_version(&current), Ok(7));
+
+    assert_eq!(
+        decrypt_min_version(&current, b"password", 7).unwrap(),
+        b"retries = 5"
+    );
+    assert_eq!(
+        decrypt_min_version(&current, b"password", 0).unwrap(),
+        b"retries = 5"
+    );
+    // Checked before deriving the key, so even the wrong password gets the rollback error.
+    for password in [&b"password"[..], b"wrong"] {
+        assert_eq!(
+            decrypt_min_version(&old, password, 7),
+            Err(CryptographyError::RollbackDetected)
+        );
+    }
+
+    assert_eq!(decrypt(&old, b"password").unwrap(), b"retries = 3");
+}
+
+#[test]
+fn versions_are_authenticated() {
+    let mut blob = encrypt_versioned(b"retries = 3", b"password", 6).unwrap();
+
+    // Bump the version following the context label prefix, then fix up the checksum.
+    let label = b"tinycrypt-v1-versioned";
+    let version = blob
+        .windows(label.len())
+        .position(|window| window == label)
+        .unwrap()
+        + label.len();
+    blob[version] = 9;
+    blob[5..9].fill(0);
+    let checksum = crc32(&blob);
+    blob[5..9].copy_from_slice(&checksum.to_le_bytes());
+
+    assert_eq!(blob_version(&blob), Ok(9));
+    assert_eq!(
+        decrypt_min_version(&blob, b"password", 7),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn unversioned_blobs_are_rejected() {
+    let blob = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(blob_version(&blob), Err(CryptographyError::DecodingFailure));
+    assert_eq!(
+        decrypt_min_version(&blob, b"password", 0),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
@@ -0,0 +1,58 @@
+use tinycrypt::{combine_blob, decrypt, encrypt, split_blob, CryptographyError};
+
+#[test]
+fn any_threshold_of_shards_reconstructs() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+    let shards = split_blob(&encrypted, 5, 3).unwrap();
+
+    for a in 0..5 {
+        for b in a + 1..5 {
+            for c in b + 1..5 {
+                let combined = combine_blob(&[&shards[a], &shards[b], &shards[c]]).unwrap();
+                assert_eq!(combined, encrypted);
+            }
+        }
+    }
+
+    assert_eq!(
+        decrypt(&combine_blob(&shards).unwrap(), b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn too_few_shards_fail() {
+    let shards = split_blob(b"some blob", 5, 3).unwrap();
+
+    assert_eq!(
+        combine_blob(&shards[..2]),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        combine_blob(&[&shards[1], &shards[1], &shards[1]]),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn shards_of_different_splits_are_rejected() {
+    let first = split_blob(b"some blob", 3, 2).unwrap();
+    let second = split_blob(b"some blob", 3, 2).unwrap();
+
+    assert_eq!(
+        combine_blob(&[&first[0], &second[1]]),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    assert_eq!(
+        split_blob(b"some blob", 3, 0),
+        Err(CryptographyError::EncodingFailure)
+    );
+    assert_eq!(
+        split_blob(b"some blob", 3, 4),
+        Err(CryptographyError::EncodingFailure)
+    );
+}
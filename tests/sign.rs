@@ -0,0 +1,91 @@
+use tinycrypt::{
+    decrypt, encrypt, encrypt_with, rekey, sign_detached, verify_detached, verify_header,
+    CryptographyError, EncryptOptions, SIGNATURE_LEN,
+};
+
+#[test]
+fn detached_signature_verifies() {
+    let blob = encrypt(b"Hello, world!", b"password").unwrap();
+    let signature = sign_detached(&blob, b"password").unwrap();
+
+    assert_eq!(signature.len(), SIGNATURE_LEN);
+    assert_eq!(verify_detached(&blob, &signature, b"password"), Ok(()));
+    // Deterministic for the same blob and password.
+    assert_eq!(sign_detached(&blob, b"password").unwrap(), signature);
+}
+
+#[test]
+fn detached_signature_rejects_changes() {
+    let blob = encrypt(b"Hello, world!", b"password").unwrap();
+    let other = encrypt(b"Hello, world!", b"password").unwrap();
+    let signature = sign_detached(&blob, b"password").unwrap();
+
+    let mut tampered_signature = signature.clone();
+    tampered_signature[0] ^= 1;
+
+    for (blob, signature, password) in [
+        (&blob, &signature, &b"wrong"[..]),
+        (&other, &signature, &b"password"[..]),
+        (&blob, &tampered_signature, &b"password"[..]),
+    ] {
+        assert_eq!(
+            verify_detached(blob, signature, password),
+            Err(CryptographyError::IncorrectPassword)
+        );
+    }
+
+    assert_eq!(
+        verify_detached(&blob, &signature[1..], b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn header_verifier_needs_only_the_header() {
+    let options = EncryptOptions::new().verifier();
+    let blob = encrypt_with(&[7u8; 4096], b"password", &options).unwrap();
+
+    assert_eq!(verify_header(&blob[..128], b"password"), Ok(()));
+    assert_eq!(
+        verify_header(&blob[..128], b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(decrypt(&blob, b"password").unwrap(), [7u8; 4096]);
+
+    // The verifier is kept under the new password.
+    let rekeyed = rekey(&blob, b"password", b"new password").unwrap();
+    assert_eq!(verify_header(&rekeyed, b"new password"), Ok(()));
+    assert_eq!(
+        verify_header(&rekeyed, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn header_verifier_rejects_changes() {
+    let blob = encrypt_with(
+        b"Hello, world!",
+        b"password",
+        &EncryptOptions::new().verifier(),
+    )
+    .unwrap();
+
+    // Without a verifier, or without the whole header.
+    for header in [
+        &encrypt(b"Hello, world!", b"password").unwrap()[..],
+        &blob[..60],
+    ] {
+        assert_eq!(
+            verify_header(header, b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+    }
+
+    // A flipped nonce byte, which the verifier covers.
+    let mut tampered = blob.clone();
+    tampered[50] ^= 1;
+    assert_eq!(
+        verify_header(&tampered, b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
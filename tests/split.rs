@@ -0,0 +1,36 @@
+use tinycrypt::{decrypt, decrypt_parts, encrypt_split, CryptographyError};
+
+#[test]
+fn parts_round_trip() {
+    let (header, ciphertext) = encrypt_split(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(
+        decrypt_parts(&header, &ciphertext, b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt(&[header, ciphertext].concat(), b"password").unwrap(),
+        b"Hello, world!"
+    );
+}
+
+#[test]
+fn malformed_parts_are_rejected_before_decrypting() {
+    let (header, ciphertext) = encrypt_split(b"Hello, world!", b"password").unwrap();
+    let (_, other_ciphertext) = encrypt_split(b"Hello, world!", b"password").unwrap();
+    let with_ciphertext = [&header[..], &ciphertext[..]].concat();
+
+    for (header, ciphertext) in [
+        (&header[..header.len() - 1], &ciphertext[..]),
+        (&with_ciphertext[..], &ciphertext[..]),
+        (&header[..], &ciphertext[..15]),
+        // Checked by the checksum, which covers both parts.
+        (&header[..], &other_ciphertext[..]),
+        (&header[..], &ciphertext[..ciphertext.len() - 1]),
+    ] {
+        assert_eq!(
+            decrypt_parts(header, ciphertext, b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    io::{Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tinycrypt::{
+    stream::{
+        convert_to_single, convert_to_streaming, decrypt_stream, decrypt_stream_with_progress,
+        decrypt_streaming, encrypt_stream, encrypt_stream_cancellable, read_associated_header,
+        rekey_stream, DecryptingReader, EncryptingWriter, StreamingDecryptor, StreamingEncryptor,
+        DEFAULT_CHUNK_SIZE,
+    },
+    CryptographyError,
+};
+
+const CHUNK_SIZE: usize = 16;
+
+fn encrypt_padded(data: &[u8]) -> Vec<u8> {
+    let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", CHUNK_SIZE)
+        .unwrap()
+        .with_plaintext_len(data.len() as u64)
+        .unwrap();
+
+    let mut encrypted = Vec::new();
+
+    for piece in data.chunks(5) {
+        encrypted.extend(encryptor.push(piece).unwrap());
+    }
+
+    encrypted.extend(encryptor.finish().unwrap());
+    encrypted
+}
+
+fn decrypt(encrypted: &[u8]) -> Result<Vec<u8>, CryptographyError> {
+    let mut decryptor = StreamingDecryptor::new(b"password");
+    let mut decrypted = decryptor.push(encrypted)?;
+    decrypted.extend(decryptor.finish()?);
+
+    Ok(decrypted)
+}
+
+#[test]
+fn padded_stream_reconstructs_exact_length() {
+    // Empty, a single partial chunk, exactly one and two chunks, and a partial tail.
+    for length in [0, 5, CHUNK_SIZE, CHUNK_SIZE * 2, CHUNK_SIZE * 2 + 3] {
+        let data: Vec<u8> = (0..length as u8).collect();
+        let encrypted = encrypt_padded(&data);
+
+        assert_eq!(decrypt(&encrypted).unwrap(), data, "length {length}");
+    }
+}
+
+#[test]
+fn padded_stream_hides_tail_length() {
+    assert_eq!(
+        encrypt_padded(&[1; 1]).len(),
+        encrypt_padded(&[1; CHUNK_SIZE]).len()
+    );
+}
+
+#[test]
+fn declared_length_must_match_input() {
+    let encryptor = StreamingEncryptor::with_chunk_size(b"password", CHUNK_SIZE)
+        .unwrap()
+        .with_plaintext_len(10)
+        .unwrap();
+    let mut too_much = StreamingEncryptor::with_chunk_size(b"password", CHUNK_SIZE)
+        .unwrap()
+        .with_plaintext_len(10)
+        .unwrap();
+
+    assert_eq!(encryptor.finish(), Err(CryptographyError::EncodingFailure));
+    assert_eq!(
+        too_much.push(&[0; 11]),
+        Err(CryptographyError::EncodingFailure)
+    );
+}
+
+/// Reader that raises the cancel flag once it has handed out `limit` bytes.
+struct CancelAfter<'a> {
+    data: &'a [u8],
+    limit: usize,
+    cancel: &'a AtomicBool,
+}
+
+impl Read for CancelAfter<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.data.read(buf)?;
+        self.limit = self.limit.saturating_sub(read);
+
+        if self.limit == 0 {
+            self.cancel.store(true, Ordering::Relaxed);
+        }
+
+        Ok(read)
+    }
+}
+
+#[test]
+fn cancelled_stream_does_not_decrypt() {
+    let data = vec![7u8; DEFAULT_CHUNK_SIZE * 3];
+    let cancel = AtomicBool::new(false);
+    let reader = CancelAfter {
+        data: &data,
+        limit: DEFAULT_CHUNK_SIZE * 2,
+        cancel: &cancel,
+    };
+
+    let mut encrypted = Vec::new();
+
+    assert_eq!(
+        encrypt_stream_cancellable(reader, &mut encrypted, b"password", &cancel),
+        Err(CryptographyError::Cancelled)
+    );
+    assert!(!encrypted.is_empty());
+    assert_eq!(
+        decrypt_stream(&encrypted[..], &mut Vec::new(), b"password"),
+        Err(CryptographyError::Truncated)
+    );
+}
+
+#[test]
+fn writer_round_trips() {
+    let mut writer =
+        EncryptingWriter::with_chunk_size(Vec::new(), b"password", CHUNK_SIZE).unwrap();
+
+    for piece in [&b"Hello"[..], b", ", b"world!", &[b'!'; 40]] {
+        writer.write_all(piece).unwrap();
+    }
+
+    let encrypted = writer.finish().unwrap();
+
+    let mut expected = b"Hello, world!".to_vec();
+    expected.extend_from_slice(&[b'!'; 40]);
+
+    assert_eq!(decrypt(&encrypted).unwrap(), expected);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "without calling finish")]
+fn unfinished_writer_panics_in_debug() {
+    let mut writer = EncryptingWriter::new(Vec::new(), b"password").unwrap();
+    writer.write_all(b"Hello, world!").unwrap();
+}
+
+#[test]
+fn streaming_stops_at_corrupted_chunk() {
+    let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", CHUNK_SIZE).unwrap();
+    let mut encrypted = Vec::new();
+
+    for chunk in 1..=5u8 {
+        encrypted.extend(encryptor.push(&[chunk; CHUNK_SIZE]).unwrap());
+    }
+
+    encrypted.extend(encryptor.finish().unwrap());
+
+    let intact: Vec<Vec<u8>> = decrypt_streaming(&encrypted[..], b"password")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        intact,
+        (1..=5u8)
+            .map(|chunk| vec![chunk; CHUNK_SIZE])
+            .collect::<Vec<_>>()
+    );
+
+    // 44 byte header, then frames of a 4 byte length, the chunk and its tag.
+    let frame_len = 4 + CHUNK_SIZE + 16;
+    encrypted[44 + frame_len * 2 + 4] ^= 1;
+
+    let mut chunks = decrypt_streaming(&encrypted[..], b"password");
+
+    assert_eq!(chunks.next(), Some(Ok(vec![1; CHUNK_SIZE])));
+    assert_eq!(chunks.next(), Some(Ok(vec![2; CHUNK_SIZE])));
+    assert_eq!(
+        chunks.next(),
+        Some(Err(CryptographyError::IncorrectPassword))
+    );
+    assert_eq!(chunks.next(), None);
+}
+
+/// Stream of b"tinycrypt stream golden file, version 1" under b"password" with 16 byte chunks.
+const GOLDEN_STREAM: &[u8] = include_bytes!("data/stream_v1.bin");
+const GOLDEN_PLAINTEXT: &[u8] = b"tinycrypt stream golden file, version 1";
+
+#[test]
+fn golden_stream_decrypts() {
+    assert_eq!(decrypt(GOLDEN_STREAM).unwrap(), GOLDEN_PLAINTEXT);
+}
+
+/// Decodes the golden stream following only the documented layout, as a non-Rust implementation would.
+#[test]
+fn golden_stream_matches_documented_layout() {
+    use aes_gcm_siv::{
+        aead::{Aead, KeyInit, Payload},
+        Aes256GcmSiv, Nonce,
+    };
+
+    let (header, mut frames) = GOLDEN_STREAM.split_at(44);
+    let salt = &header[..32];
+    let nonce_prefix = &header[32..39];
+    let chunk_size = u32::from_le_bytes(header[39..43].try_into().unwrap());
+
+    assert_eq!(chunk_size, 16);
+    assert_eq!(header[43], 0, "no declared length");
+
+    let config = argon2::Config {
+        hash_length: 32,
+        ad: tinycrypt::DEFAULT_CONTEXT,
+        ..Default::default()
+    };
+    let key = argon2::hash_raw(b"password", salt, &config).unwrap();
+    let cipher = Aes256GcmSiv::new_from_slice(&key).unwrap();
+
+    let mut plaintext = Vec::new();
+    let mut counter = 0u32;
+
+    while !frames.is_empty() {
+        let length = u32::from_le_bytes(frames[..4].try_into().unwrap()) as usize;
+        let chunk = &frames[4..4 + length];
+        frames = &frames[4 + length..];
+
+        let mut nonce = nonce_prefix.to_vec();
+        nonce.extend_from_slice(&counter.to_be_bytes());
+        nonce.push(frames.is_empty() as u8);
+
+        let decrypted = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: chunk,
+                    aad: header,
+                },
+            )
+            .unwrap();
+
+        plaintext.extend_from_slice(&decrypted);
+        counter += 1;
+    }
+
+    assert_eq!(counter, 3);
+    assert_eq!(plaintext, GOLDEN_PLAINTEXT);
+}
+
+#[test]
+fn progress_reports_every_byte_once_done() {
+    let data = vec![7u8; 3 * DEFAULT_CHUNK_SIZE + 5];
+    let mut encrypted = Vec::new();
+    encrypt_stream(&data[..], &mut encrypted, b"password").unwrap();
+
+    let mut calls = Vec::new();
+    let mut decrypted = Vec::new();
+    decrypt_stream_with_progress(
+        &encrypted[..],
+        &mut decrypted,
+        b"password",
+        Some(encrypted.len() as u64),
+        |done, total| calls.push((done, total)),
+    )
+    .unwrap();
+
+    assert_eq!(decrypted, data);
+    assert!(calls.len() > 1);
+    assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(
+        calls.last(),
+        Some(&(encrypted.len() as u64, Some(encrypted.len() as u64)))
+    );
+
+    let mut totals = Vec::new();
+    decrypt_stream_with_progress(
+        &encrypted[..],
+        std::io::sink(),
+        b"password",
+        None,
+        |_, total| totals.push(total),
+    )
+    .unwrap();
+    assert!(totals.iter().all(Option::is_none));
+}
+
+fn encrypt_with_associated(associated: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut encryptor = StreamingEncryptor::with_chunk_size(b"password", CHUNK_SIZE)
+        .unwrap()
+        .with_associated_header(associated)
+        .unwrap();
+
+    let mut encrypted = encryptor.push(data).unwrap();
+    encrypted.extend(encryptor.finish().unwrap());
+
+    encrypted
+}
+
+#[test]
+fn associated_header_is_authenticated() {
+    let data = vec![7u8; CHUNK_SIZE * 3];
+    let encrypted = encrypt_with_associated(b"table of contents", &data);
+
+    // Readable in the clear right after the fixed header fields and its length.
+    assert_eq!(&encrypted[48..65], b"table of contents");
+    assert_eq!(
+        read_associated_header(&encrypted[..], b"password").unwrap(),
+        b"table of contents"
+    );
+
+    let mut decryptor = StreamingDecryptor::new(b"password");
+    assert_eq!(decryptor.push(&encrypted[..48]).unwrap(), b"");
+    assert_eq!(decryptor.associated_header(), None);
+
+    let mut decrypted = decryptor.push(&encrypted[48..]).unwrap();
+    assert_eq!(
+        decryptor.associated_header(),
+        Some(&b"table of contents"[..])
+    );
+    decrypted.extend(decryptor.finish().unwrap());
+    assert_eq!(decrypted, data);
+
+    // Also when the only chunk is the last one.
+    let short = encrypt_with_associated(b"", b"Hi");
+    assert_eq!(
+        read_associated_header(&short[..], b"password").unwrap(),
+        b""
+    );
+
+    let mut tampered = encrypted.clone();
+    tampered[50] ^= 1;
+
+    assert_eq!(
+        read_associated_header(&tampered[..], b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        decrypt(&tampered),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        read_associated_header(&encrypt_padded(b"Hi")[..], b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn blobs_convert_to_streams_and_back() {
+    let data = vec![7u8; CHUNK_SIZE * 3 + 5];
+    let blob = tinycrypt::encrypt(&data, b"password").unwrap();
+
+    let stream = convert_to_streaming(&blob, b"password", CHUNK_SIZE).unwrap();
+    // 44 byte header, then one frame per chunk plus the final one.
+    assert_eq!(stream.len(), 44 + 4 * (4 + 16) + data.len());
+    assert_eq!(decrypt(&stream), Ok(data.clone()));
+
+    let single = convert_to_single(&stream, b"password").unwrap();
+    assert_eq!(tinycrypt::decrypt(&single, b"password").unwrap(), data);
+
+    assert_eq!(
+        convert_to_streaming(&blob, b"wrong", CHUNK_SIZE),
+        Err(CryptographyError::IncorrectPassword)
+    );
+    assert_eq!(
+        convert_to_single(&stream[..stream.len() - 1], b"password"),
+        Err(CryptographyError::Truncated)
+    );
+}
+
+#[test]
+fn reader_returns_authenticated_plaintext() {
+    let data: Vec<u8> = (0..CHUNK_SIZE as u8 * 3 + 5).collect();
+
+    let mut encrypted = Vec::new();
+    let mut writer =
+        EncryptingWriter::with_chunk_size(&mut encrypted, b"password", CHUNK_SIZE).unwrap();
+    writer.write_all(&data).unwrap();
+    writer.finish().unwrap();
+
+    let mut decrypted = Vec::new();
+    DecryptingReader::new(&encrypted[..], b"password")
+        .read_to_end(&mut decrypted)
+        .unwrap();
+    assert_eq!(decrypted, data);
+
+    // Small reads split chunks up.
+    let mut reader = DecryptingReader::new(&encrypted[..], b"password");
+    let mut piece = [0u8; 5];
+    reader.read_exact(&mut piece).unwrap();
+    assert_eq!(piece, data[..5]);
+
+    // A truncated stream never reports the end of the data.
+    let mut reader = DecryptingReader::new(&encrypted[..encrypted.len() - 1], b"password");
+    let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(
+        error.into_inner().unwrap().downcast_ref(),
+        Some(&CryptographyError::Truncated)
+    );
+    assert!(reader.read(&mut piece).is_err());
+}
+
+#[test]
+fn rekey_stream_changes_the_password() {
+    let data = vec![3u8; 3 * DEFAULT_CHUNK_SIZE + 5];
+    let mut encrypted = Vec::new();
+    encrypt_stream(&data[..], &mut encrypted, b"old").unwrap();
+
+    let mut rekeyed = Vec::new();
+    rekey_stream(&encrypted[..], &mut rekeyed, b"old", b"new").unwrap();
+
+    let mut decrypted = Vec::new();
+    decrypt_stream(&rekeyed[..], &mut decrypted, b"new").unwrap();
+    assert_eq!(decrypted, data);
+    assert_eq!(
+        decrypt_stream(&rekeyed[..], &mut Vec::new(), b"old"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let cut = &encrypted[..encrypted.len() - 1];
+    let mut partial = Vec::new();
+    assert_eq!(
+        rekey_stream(cut, &mut partial, b"old", b"new"),
+        Err(CryptographyError::Truncated)
+    );
+    assert_eq!(
+        decrypt_stream(&partial[..], &mut Vec::new(), b"new"),
+        Err(CryptographyError::Truncated)
+    );
+}
@@ -0,0 +1,151 @@
+use tinycrypt::{
+    decrypt, encrypt, encrypt_with, encrypted_len, rekey, Argon2Params, CipherSuite,
+    CryptographyError, EncryptOptions, Header,
+};
+
+const LIGHT: Argon2Params = Argon2Params {
+    mem_cost: 64,
+    time_cost: 1,
+    lanes: 1,
+};
+
+#[test]
+fn every_suite_round_trips() {
+    for &suite in CipherSuite::ALL {
+        let options = EncryptOptions::new().params(LIGHT).suite(suite);
+        let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+        let (header, _) = Header::parse(&encrypted).unwrap();
+        assert_eq!(header.suite(), suite);
+
+        assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+        assert_eq!(
+            decrypt(&encrypted, b"wrong"),
+            Err(CryptographyError::IncorrectPassword)
+        );
+
+        // Rekeying keeps the suite.
+        let rekeyed = rekey(&encrypted, b"password", b"new password").unwrap();
+        assert_eq!(Header::parse(&rekeyed).unwrap().0.suite(), suite);
+        assert_eq!(
+            decrypt(&rekeyed, b"new password").unwrap(),
+            b"Hello, world!"
+        );
+    }
+}
+
+#[test]
+fn default_blobs_have_no_suite_field() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+    let (header, _) = Header::parse(&encrypted).unwrap();
+
+    assert_eq!(header.suite(), CipherSuite::Aes256GcmSiv);
+    assert_eq!(encrypted.len(), encrypted_len(13));
+}
+
+#[test]
+fn unknown_suites_are_reported() {
+    let encrypted = encrypt_with(
+        b"Hello, world!",
+        b"password",
+        &EncryptOptions::new().params(LIGHT),
+    )
+    .unwrap();
+
+    // Suite 0 is never written out.
+    assert_eq!(
+        decrypt(&with_suite_field(&encrypted, 0), b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        decrypt(&with_suite_field(&encrypted, 9), b"password"),
+        Err(CryptographyError::UnsupportedCipherSuite(9))
+    );
+
+    #[cfg(not(feature = "chacha20poly1305"))]
+    assert_eq!(
+        decrypt(&with_suite_field(&encrypted, 1), b"password"),
+        Err(CryptographyError::UnsupportedCipherSuite(1))
+    );
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn nonce_must_match_the_suite() {
+    // An AES-256-GCM-SIV blob's 12 byte nonce is too short for XChaCha20-Poly1305.
+    let encrypted = encrypt_with(
+        b"Hello, world!",
+        b"password",
+        &EncryptOptions::new().params(LIGHT),
+    )
+    .unwrap();
+
+    assert_eq!(
+        decrypt(&with_suite_field(&encrypted, 2), b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[cfg(feature = "chacha20poly1305")]
+#[test]
+fn swapped_suites_fail_to_decrypt() {
+    let options = EncryptOptions::new().params(LIGHT);
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    // Claiming ChaCha20-Poly1305 for an AES-256-GCM-SIV blob, its nonce is the right size.
+    assert_eq!(
+        decrypt(&with_suite_field(&encrypted, 1), b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let chacha = options.suite(CipherSuite::ChaCha20Poly1305);
+    let mut encrypted = encrypt_with(b"Hello, world!", b"password", &chacha).unwrap();
+
+    // The suite field is authenticated along with the rest of the header.
+    let fields_len = u16::from_le_bytes([encrypted[9], encrypted[10]]) as usize;
+    assert_eq!(
+        encrypted[11 + fields_len - 4..11 + fields_len],
+        [0x8b, 1, 0, 1]
+    );
+    encrypted[11 + fields_len - 1] = 2;
+    fix_checksum(&mut encrypted);
+
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+/// Appends a minor 4 field and a suite field with `id` to a blob's header, as a build with more suites would.
+fn with_suite_field(blob: &[u8], id: u8) -> Vec<u8> {
+    let fields_len = u16::from_le_bytes([blob[9], blob[10]]) as usize;
+    let newer_fields = [7, 1, 0, 4, 0x8b, 1, 0, id];
+
+    let mut out = blob[..11 + fields_len].to_vec();
+    out[9..11].copy_from_slice(&((fields_len + newer_fields.len()) as u16).to_le_bytes());
+    out.extend_from_slice(&newer_fields);
+    out.extend_from_slice(&blob[11 + fields_len..]);
+    fix_checksum(&mut out);
+
+    out
+}
+
+fn fix_checksum(blob: &mut [u8]) {
+    blob[5..9].fill(0);
+    let checksum = crc32(blob);
+    blob[5..9].copy_from_slice(&checksum.to_le_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
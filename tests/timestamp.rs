@@ -0,0 +1,73 @@
+use std::time::{Duration, SystemTime};
+use tinycrypt::{creation_time, decrypt, encrypt, encrypt_timestamped, CryptographyError};
+
+#[test]
+fn timestamp_round_trips() {
+    let before = SystemTime::now() - Duration::from_secs(1);
+    let encrypted = encrypt_timestamped(b"Hello, world!", b"password").unwrap();
+    let created = creation_time(&encrypted).unwrap();
+
+    assert!(created >= before && created <= SystemTime::now());
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+}
+
+#[test]
+fn untimestamped_blob_has_no_creation_time() {
+    let encrypted = encrypt(b"Hello, world!", b"password").unwrap();
+
+    assert_eq!(
+        creation_time(&encrypted),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn altered_timestamp_fails_to_decrypt() {
+    let mut encrypted = encrypt_timestamped(b"Hello, world!", b"password").unwrap();
+    let created = creation_time(&encrypted).unwrap();
+
+    // The timestamp is the last header field, right before the ciphertext.
+    let seconds = created
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let position = encrypted
+        .windows(8)
+        .position(|window| window == seconds.to_le_bytes())
+        .unwrap();
+    encrypted[position] ^= 1;
+
+    // The blob checksum catches the change before the AEAD gets to, see tests/checksum.rs for a recomputed checksum.
+    assert_eq!(
+        creation_time(&encrypted),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
+
+#[test]
+fn unrepresentable_timestamp_is_rejected() {
+    let mut encrypted = encrypt_timestamped(b"Hello, world!", b"password").unwrap();
+    let created = creation_time(&encrypted)
+        .unwrap()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let position = encrypted
+        .windows(8)
+        .position(|window| window == created.to_le_bytes())
+        .unwrap();
+    encrypted[position..position + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    assert_eq!(
+        creation_time(&encrypted),
+        Err(CryptographyError::DecodingFailure)
+    );
+    assert_eq!(
+        decrypt(&encrypted, b"password"),
+        Err(CryptographyError::DecodingFailure)
+    );
+}
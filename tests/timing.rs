@@ -0,0 +1,30 @@
+use std::time::Instant;
+use tinycrypt::{decrypt_timed, encrypt_timed, CryptographyError};
+
+#[test]
+fn timed_round_trip() {
+    let start = Instant::now();
+    let (encrypted, timings) = encrypt_timed(b"Hello, world!", b"password").unwrap();
+    assert!(timings.kdf + timings.cipher <= start.elapsed());
+
+    let start = Instant::now();
+    let (decrypted, timings) = decrypt_timed(&encrypted, b"password").unwrap();
+    assert!(timings.kdf + timings.cipher <= start.elapsed());
+    assert_eq!(decrypted, b"Hello, world!");
+
+    assert_eq!(
+        decrypt_timed(&encrypted, b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+// Only argon2 is slow enough to dominate reliably.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn kdf_dominates() {
+    let (encrypted, timings) = encrypt_timed(b"Hello, world!", b"password").unwrap();
+    assert!(timings.kdf > timings.cipher);
+
+    let (_, timings) = decrypt_timed(&encrypted, b"password").unwrap();
+    assert!(timings.kdf > timings.cipher);
+}
@@ -0,0 +1,65 @@
+use std::io::Cursor;
+use tinycrypt::{decrypt_trailer, encrypt, encrypt_trailer, CryptographyError};
+
+fn trailer_blob(data: &[u8]) -> Vec<u8> {
+    let mut blob = Vec::new();
+    encrypt_trailer(data, b"password", &mut blob).unwrap();
+
+    blob
+}
+
+#[test]
+fn trailer_round_trip() {
+    let blob = trailer_blob(b"Hello, world!");
+
+    assert!(blob.ends_with(b"TCRT"));
+    assert_eq!(
+        decrypt_trailer(Cursor::new(&blob), b"password").unwrap(),
+        b"Hello, world!"
+    );
+    assert_eq!(
+        decrypt_trailer(Cursor::new(&blob), b"wrong"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+}
+
+#[test]
+fn ciphertext_comes_first() {
+    let blob = trailer_blob(b"Hello, world!");
+    let header_len = u32::from_le_bytes(blob[blob.len() - 8..blob.len() - 4].try_into().unwrap());
+
+    // Ciphertext and tag, then the regular binary header.
+    assert_eq!(&blob[13 + 16..][..4], b"TCRY");
+    assert_eq!(blob.len(), 13 + 16 + header_len as usize + 8);
+}
+
+#[test]
+fn rejects_malformed_trailers() {
+    let blob = trailer_blob(b"Hello, world!");
+
+    let mut tampered = blob.clone();
+    tampered[0] ^= 1;
+    assert_eq!(
+        decrypt_trailer(Cursor::new(&tampered), b"password"),
+        Err(CryptographyError::IncorrectPassword)
+    );
+
+    let too_long = {
+        let mut blob = blob.clone();
+        let len = blob.len();
+        blob[len - 8..len - 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        blob
+    };
+
+    for malformed in [
+        &blob[..blob.len() - 1],
+        &too_long,
+        &encrypt(b"Hello, world!", b"password").unwrap(),
+        &b"TCRT"[..],
+    ] {
+        assert_eq!(
+            decrypt_trailer(Cursor::new(malformed), b"password"),
+            Err(CryptographyError::DecodingFailure)
+        );
+    }
+}
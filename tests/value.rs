@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use tinycrypt::{decrypt_value, decrypt_value_bounded, encrypt, encrypt_value, CryptographyError};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Payload {
+    id: u64,
+    tags: Vec<String>,
+}
+
+#[test]
+fn value_round_trip() {
+    let payload = Payload {
+        id: 7,
+        tags: vec!["a".into(), "b".into()],
+    };
+    let encrypted = encrypt_value(&payload, b"password").unwrap();
+
+    assert_eq!(
+        decrypt_value::<Payload>(&encrypted, b"password").unwrap(),
+        payload
+    );
+    assert_eq!(
+        decrypt_value_bounded::<Payload>(&encrypted, b"password", 64).unwrap(),
+        payload
+    );
+}
+
+#[test]
+fn oversized_plaintext_is_rejected_before_decrypting() {
+    let encrypted = encrypt_value(&vec![0u8; 1000], b"password").unwrap();
+
+    // 8 byte length prefix plus the contents.
+    assert_eq!(
+        decrypt_value_bounded::<Vec<u8>>(&encrypted, b"wrong", 1007),
+        Err(CryptographyError::InputTooLarge)
+    );
+    assert_eq!(
+        decrypt_value_bounded::<Vec<u8>>(&encrypted, b"password", 1008).unwrap(),
+        vec![0u8; 1000]
+    );
+}
+
+#[test]
+fn crafted_length_prefix_does_not_allocate() {
+    // A tiny plaintext claiming to hold a vector of u64::MAX bytes.
+    let encrypted = encrypt(&u64::MAX.to_le_bytes(), b"password").unwrap();
+
+    assert!(matches!(
+        decrypt_value_bounded::<Vec<u8>>(&encrypted, b"password", 1024),
+        Err(CryptographyError::Bincode(_))
+    ));
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn cbor_values_round_trip() {
+    use tinycrypt::{decrypt, encrypt_value_with, Codec};
+
+    let payload = Payload {
+        id: 7,
+        tags: vec!["a".into(), "b".into()],
+    };
+    let encrypted = encrypt_value_with(&payload, b"password", Codec::Cbor).unwrap();
+
+    assert_eq!(
+        decrypt_value::<Payload>(&encrypted, b"password").unwrap(),
+        payload
+    );
+    assert_eq!(
+        decrypt_value_bounded::<Payload>(&encrypted, b"password", 64).unwrap(),
+        payload
+    );
+
+    // The plaintext is plain CBOR for anyone else to parse.
+    let plaintext = decrypt(&encrypted, b"password").unwrap();
+    let parsed: Payload = ciborium::from_reader(&plaintext[..]).unwrap();
+    assert_eq!(parsed, payload);
+}
@@ -0,0 +1,133 @@
+//! Known-answer vectors locking down the blob format: a fixed salt, nonce & password must always give these bytes.
+
+use tinycrypt::decrypt;
+
+struct Vector {
+    plaintext: &'static [u8],
+    password: &'static [u8],
+    salt: u8,
+    nonce: u8,
+    blob: &'static str,
+}
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        plaintext: b"Hello, world!",
+        password: b"password",
+        salt: 1,
+        nonce: 2,
+        blob: "5443525902bca279b832000120000101010101010101010101010101010101010101010101010101010101010101020c00020202020202020202020202250a59fdac6bc1b6abf335d858dc3dc12c5e12398beeff663bff5c623d",
+    },
+    Vector {
+        plaintext: b"",
+        password: b"password",
+        salt: 3,
+        nonce: 4,
+        blob: "5443525902e5209dbc32000120000303030303030303030303030303030303030303030303030303030303030303020c00040404040404040404040404e2854b2b3f50f45725bc1d3761d3afb6",
+    },
+    Vector {
+        plaintext: b"tinycrypt known-answer test vector",
+        password: b"correct horse battery staple",
+        salt: 0xaa,
+        nonce: 0x55,
+        blob: "5443525902b7040d553200012000aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa020c0055555555555555555555555579137ab0e9106556e373b47cad58ee70de888072a35a74158b88e335006dc7684e9a5d1cf9c69728ec1450c5565fba0f5fcb",
+    },
+];
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn vectors_decrypt() {
+    for vector in VECTORS {
+        let blob = unhex(vector.blob);
+
+        assert_eq!(blob[14..46], [vector.salt; 32]);
+        assert_eq!(blob[49..61], [vector.nonce; 12]);
+        assert_eq!(decrypt(&blob, vector.password).unwrap(), vector.plaintext);
+    }
+}
+
+// Builds with the insecure test KDF derive keys differently and mark it in the header.
+#[cfg(not(feature = "insecure-fast-kdf"))]
+#[test]
+fn vectors_encrypt_to_known_bytes() {
+    use tinycrypt::{encrypt_with_randomness, Randomness};
+
+    /// Fills the salt and nonce with a single repeated byte each.
+    struct Fixed {
+        salt: u8,
+        nonce: u8,
+    }
+
+    impl Randomness for Fixed {
+        fn fill_salt(&mut self, buf: &mut [u8]) {
+            buf.fill(self.salt);
+        }
+
+        fn fill_nonce(&mut self, buf: &mut [u8]) {
+            buf.fill(self.nonce);
+        }
+    }
+
+    for vector in VECTORS {
+        let mut fixed = Fixed {
+            salt: vector.salt,
+            nonce: vector.nonce,
+        };
+
+        let blob = encrypt_with_randomness(vector.plaintext, vector.password, &mut fixed).unwrap();
+
+        assert_eq!(blob, unhex(vector.blob));
+    }
+}
+
+/// Decrypts the first vector following only the documented layout, as a non-Rust implementation would.
+#[test]
+fn vector_matches_documented_layout() {
+    use aes_gcm_siv::{
+        aead::{Aead, KeyInit, Payload},
+        Aes256GcmSiv, Nonce,
+    };
+
+    let blob = unhex(VECTORS[0].blob);
+    let (header, ciphertext) = blob.split_at(61);
+
+    // The header is associated data with its checksum zeroed.
+    let mut aad = header.to_vec();
+    aad[5..9].fill(0);
+
+    assert_eq!(&header[..4], b"TCRY");
+    assert_eq!(header[4], 2, "version");
+    assert_eq!(
+        u16::from_le_bytes([header[9], header[10]]),
+        50,
+        "fields length"
+    );
+    assert_eq!(&header[11..14], &[1, 32, 0], "salt tag & length");
+    assert_eq!(&header[46..49], &[2, 12, 0], "nonce tag & length");
+
+    let config = argon2::Config {
+        hash_length: 32,
+        ad: tinycrypt::DEFAULT_CONTEXT,
+        ..Default::default()
+    };
+    let key = argon2::hash_raw(VECTORS[0].password, &header[14..46], &config).unwrap();
+
+    let plaintext = Aes256GcmSiv::new_from_slice(&key)
+        .unwrap()
+        .decrypt(
+            Nonce::from_slice(&header[49..61]),
+            Payload {
+                msg: ciphertext,
+                aad: &aad,
+            },
+        )
+        .unwrap();
+
+    assert_eq!(plaintext, VECTORS[0].plaintext);
+}
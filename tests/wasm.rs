@@ -0,0 +1,30 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+// Runs under wasm-bindgen-test-runner, see the wasm feature in the README.
+
+use tinycrypt::{decrypt, encrypt_with, EncryptOptions, KdfPreset, KeyDerivation};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn wasm_round_trip() {
+    let options = EncryptOptions::new().params(KdfPreset::Interactive.into());
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    assert_eq!(decrypt(&encrypted, b"password").unwrap(), b"Hello, world!");
+}
+
+#[wasm_bindgen_test]
+fn wasm_derivation_steps() {
+    let options = EncryptOptions::new().params(KdfPreset::Interactive.into());
+    let encrypted = encrypt_with(b"Hello, world!", b"password", &options).unwrap();
+
+    let mut derivation = KeyDerivation::for_blob(&encrypted, b"password").unwrap();
+    let mut progress = Vec::new();
+
+    while !derivation.step() {
+        progress.push(derivation.progress());
+    }
+
+    assert_eq!(progress.len(), 19);
+    assert_eq!(derivation.decrypt(&encrypted).unwrap(), b"Hello, world!");
+}